@@ -15,6 +15,10 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 fn main() {
+    // Build details shown in the About tab
+    println!("cargo:rustc-env=METGEN_BUILD_TARGET={}", std::env::var("TARGET").unwrap_or_default());
+    println!("cargo:rustc-env=METGEN_BUILD_PROFILE={}", std::env::var("PROFILE").unwrap_or_default());
+
     #[cfg(target_os = "windows")]
     {
         println!("cargo:rerun-if-changed=icon.ico");
@@ -0,0 +1,220 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// The airport database: the bundled airports.csv, or an airports.csv in the data
+// folder in its place, indexed once. Columns are found by their header, so the
+// override can be the bundled layout (ICAO,Latitude,Longitude with optional
+// Name, IATA, City and Country columns) or OurAirports' airports.csv as
+// downloaded, which adds names, IATA codes, cities and countries for search.
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+use crate::paths;
+
+// Bundle the airports.csv file into the binary
+pub const BUNDLED_AIRPORTS_CSV: &str = include_str!("../airports.csv");
+pub const AIRPORTS_FILE: &str = "airports.csv";
+
+// Accepted headers for each column, compared case-insensitively. For the
+// identifier the first column present with a value wins, since OurAirports
+// leaves icao_code empty for many small fields.
+const ICAO_HEADERS: &[&str] = &["icao", "icao_code", "gps_code", "ident"];
+const LATITUDE_HEADERS: &[&str] = &["latitude", "latitude_deg"];
+const LONGITUDE_HEADERS: &[&str] = &["longitude", "longitude_deg"];
+const NAME_HEADERS: &[&str] = &["name"];
+const IATA_HEADERS: &[&str] = &["iata", "iata_code"];
+const CITY_HEADERS: &[&str] = &["city", "municipality"];
+const COUNTRY_HEADERS: &[&str] = &["country", "iso_country"];
+
+pub struct Airport {
+    pub icao: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub name: Option<String>,
+    pub iata: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+}
+
+impl Airport {
+    /// One-line description for search results, e.g. "EGLL LHR London Heathrow Airport, London (GB)".
+    pub fn label(&self) -> String {
+        let mut label = self.icao.clone();
+        for part in [&self.iata, &self.name].into_iter().flatten() {
+            label.push(' ');
+            label.push_str(part);
+        }
+        if let Some(city) = &self.city {
+            label.push_str(&format!(", {}", city));
+        }
+        if let Some(country) = &self.country {
+            label.push_str(&format!(" ({})", country));
+        }
+        label
+    }
+}
+
+pub struct AirportDb {
+    airports: Vec<Airport>,
+    by_icao: HashMap<String, usize>,
+    has_names: bool,
+}
+
+static DB: OnceLock<AirportDb> = OnceLock::new();
+
+/// The airport database, read and indexed on first use.
+pub fn get() -> &'static AirportDb {
+    DB.get_or_init(|| {
+        // Try to read from the external file first
+        let external = fs::read_to_string(paths::data_file(AIRPORTS_FILE)).ok();
+        let db = external.as_deref().map(AirportDb::parse).filter(|db| !db.airports.is_empty());
+        db.unwrap_or_else(|| AirportDb::parse(BUNDLED_AIRPORTS_CSV))
+    })
+}
+
+// Index of the first of `names` in the header row
+fn column(headers: &csv::StringRecord, names: &[&str]) -> Option<usize> {
+    names.iter().find_map(|name| headers.iter().position(|header| header.trim().eq_ignore_ascii_case(name)))
+}
+
+impl AirportDb {
+    fn parse(data: &str) -> AirportDb {
+        // The bundled file starts with the license as // comment lines
+        let mut reader = csv::ReaderBuilder::new()
+            .comment(Some(b'/'))
+            .flexible(true)
+            .from_reader(data.as_bytes());
+        let Ok(headers) = reader.headers().cloned() else {
+            return AirportDb { airports: Vec::new(), by_icao: HashMap::new(), has_names: false };
+        };
+        let icao_columns: Vec<usize> = ICAO_HEADERS.iter().filter_map(|name| column(&headers, &[name])).collect();
+        let (latitude, longitude) = (column(&headers, LATITUDE_HEADERS), column(&headers, LONGITUDE_HEADERS));
+        let optional = |names: &[&str]| column(&headers, names);
+        let (name, iata, city, country) =
+            (optional(NAME_HEADERS), optional(IATA_HEADERS), optional(CITY_HEADERS), optional(COUNTRY_HEADERS));
+
+        let mut airports = Vec::new();
+        let mut by_icao = HashMap::new();
+        for record in reader.records().flatten() {
+            let text = |index: Option<usize>| {
+                index
+                    .and_then(|index| record.get(index))
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+            };
+            let Some(icao) = icao_columns.iter().find_map(|&index| text(Some(index))).map(|icao| icao.to_uppercase()) else {
+                continue;
+            };
+            let (Some(lat), Some(lon)) = (
+                text(latitude).and_then(|value| value.parse::<f64>().ok()),
+                text(longitude).and_then(|value| value.parse::<f64>().ok()),
+            ) else {
+                continue;
+            };
+            // The first entry for an identifier is kept
+            if by_icao.contains_key(&icao) {
+                continue;
+            }
+            by_icao.insert(icao.clone(), airports.len());
+            airports.push(Airport {
+                icao,
+                latitude: lat,
+                longitude: lon,
+                name: text(name),
+                iata: text(iata).map(|iata| iata.to_uppercase()),
+                city: text(city),
+                country: text(country),
+            });
+        }
+        let has_names = airports.iter().any(|airport| airport.name.is_some());
+        AirportDb { airports, by_icao, has_names }
+    }
+
+    /// The airport with this ICAO code.
+    pub fn airport(&self, icao: &str) -> Option<&Airport> {
+        self.by_icao.get(&icao.trim().to_uppercase()).map(|&index| &self.airports[index])
+    }
+
+    /// Every airport, in file order.
+    pub fn airports(&self) -> &[Airport] {
+        &self.airports
+    }
+
+    /// Whether any airport has a name, i.e. search by name can find anything.
+    pub fn has_names(&self) -> bool {
+        self.has_names
+    }
+
+    /// Airports matching a free-text query such as "heathrow", "LHR", "EGL" or
+    /// "london city", best first. Every word of the query has to match the
+    /// identifier, IATA code, name, city or country; words are matched as
+    /// prefixes, substrings or, failing that, as letters in order ("hthrw").
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&Airport> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+        let mut matches: Vec<(u32, &Airport)> = self
+            .airports
+            .iter()
+            .filter_map(|airport| {
+                words
+                    .iter()
+                    .map(|word| word_score(airport, word))
+                    .sum::<Option<u32>>()
+                    .map(|score| (score, airport))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.icao.cmp(&b.1.icao)));
+        matches.into_iter().take(limit).map(|(_, airport)| airport).collect()
+    }
+}
+
+// How well one query word matches an airport, or None if it doesn't
+fn word_score(airport: &Airport, word: &str) -> Option<u32> {
+    let codes = [Some(&airport.icao), airport.iata.as_ref()];
+    if codes.iter().flatten().any(|code| code.eq_ignore_ascii_case(word)) {
+        return Some(100);
+    }
+    if airport.icao.to_lowercase().starts_with(word) {
+        return Some(60);
+    }
+
+    let texts: Vec<String> = [&airport.name, &airport.city, &airport.country]
+        .into_iter()
+        .flatten()
+        .map(|text| text.to_lowercase())
+        .collect();
+    if texts.iter().any(|text| text.split(|c: char| !c.is_alphanumeric()).any(|part| part.starts_with(word))) {
+        return Some(50);
+    }
+    if texts.iter().any(|text| text.contains(word)) {
+        return Some(30);
+    }
+    // Too short to mean anything as scattered letters
+    if word.chars().count() >= 3 && texts.iter().any(|text| is_subsequence(word, text)) {
+        return Some(10);
+    }
+    None
+}
+
+// True if the letters of `word` appear in `text` in order
+fn is_subsequence(word: &str, text: &str) -> bool {
+    let mut letters = text.chars();
+    word.chars().all(|wanted| letters.any(|c| c == wanted))
+}
@@ -0,0 +1,155 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Bookmarks: saved points of interest that aren't airports (seaplane bases,
+// helipads, landmarks). They are stored apart from the saved airports and never
+// looked up as ICAO codes; the identifier only labels the METAR header.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use crate::config::{active_profile, get_user_airports, UserAirport};
+use crate::metar_format::{station_identifier, IdentifierPadding};
+use crate::storage;
+
+#[derive(Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookmarkKind {
+    SeaplaneBase,
+    Helipad,
+    Landmark,
+    #[default]
+    Other,
+}
+
+impl BookmarkKind {
+    pub const ALL: [BookmarkKind; 4] =
+        [BookmarkKind::SeaplaneBase, BookmarkKind::Helipad, BookmarkKind::Landmark, BookmarkKind::Other];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BookmarkKind::SeaplaneBase => "Seaplane base",
+            BookmarkKind::Helipad => "Helipad",
+            BookmarkKind::Landmark => "Landmark",
+            BookmarkKind::Other => "Other",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            BookmarkKind::SeaplaneBase => "seaplane_base",
+            BookmarkKind::Helipad => "helipad",
+            BookmarkKind::Landmark => "landmark",
+            BookmarkKind::Other => "other",
+        }
+    }
+
+    pub fn from_key(key: &str) -> BookmarkKind {
+        BookmarkKind::ALL.into_iter().find(|kind| kind.key() == key).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    #[serde(default)]
+    pub kind: BookmarkKind,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Four character identifier used in the METAR header, unique among the
+    /// saved airports and bookmarks
+    pub identifier: String,
+}
+
+/// The active profile's bookmarks, in the order they were added.
+pub fn get_bookmarks() -> Vec<Bookmark> {
+    storage::get().bookmarks(&active_profile())
+}
+
+/// Makes a bookmark that doesn't clash with the given saved airports and
+/// bookmarks. Without an identifier one is made up from the name.
+pub fn new_bookmark(
+    name: &str,
+    kind: BookmarkKind,
+    (latitude, longitude): (f64, f64),
+    identifier: &str,
+    airports: &[UserAirport],
+    bookmarks: &[Bookmark],
+) -> Result<Bookmark, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Please enter a name for the bookmark".to_string());
+    }
+
+    let mut taken: Vec<String> = airports.iter().map(|airport| airport.icao.to_uppercase()).collect();
+    taken.extend(bookmarks.iter().map(|bookmark| bookmark.identifier.clone()));
+
+    let identifier = if identifier.trim().is_empty() {
+        generate_identifier(name, &taken)
+    } else {
+        let identifier = station_identifier(identifier, IdentifierPadding::Reject)?;
+        if taken.contains(&identifier) {
+            return Err(format!("{} is already used by a saved airport or bookmark", identifier));
+        }
+        identifier
+    };
+
+    Ok(Bookmark { name: name.to_string(), kind, latitude, longitude, identifier })
+}
+
+/// Adds a bookmark to the active profile, checked against what is stored now.
+pub fn add_bookmark(
+    name: &str,
+    kind: BookmarkKind,
+    position: (f64, f64),
+    identifier: &str,
+) -> Result<Bookmark, String> {
+    let mut bookmarks = get_bookmarks();
+    let bookmark = new_bookmark(name, kind, position, identifier, &get_user_airports(), &bookmarks)?;
+    bookmarks.push(bookmark.clone());
+    storage::get().set_bookmarks(&active_profile(), &bookmarks)?;
+    Ok(bookmark)
+}
+
+pub fn delete_bookmark(identifier: &str) -> io::Result<()> {
+    let mut bookmarks = get_bookmarks();
+    let len_before = bookmarks.len();
+    bookmarks.retain(|bookmark| bookmark.identifier != identifier);
+    if bookmarks.len() == len_before {
+        return Ok(());
+    }
+    storage::get().set_bookmarks(&active_profile(), &bookmarks).map_err(io::Error::other)
+}
+
+// Four characters from the name, padded with X ("Lake Hood" becomes "LAKE",
+// "Ox" becomes "OXXX"), numbered where that is taken ("LA01")
+fn generate_identifier(name: &str, taken: &[String]) -> String {
+    let letters: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .take(4)
+        .collect();
+    let base = format!("{:X<4}", letters);
+    if !taken.contains(&base) {
+        return base;
+    }
+
+    let stem: String = base.chars().take(2).collect();
+    (1..100)
+        .map(|n| format!("{}{:02}", stem, n))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or(base)
+}
@@ -0,0 +1,155 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// The stations open in the output panel, saved so a briefing in progress
+// survives closing the app mid-flight.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use crate::input_handler::MetarSource;
+use crate::paths;
+
+const BRIEFING_FILE: &str = "briefing.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct BriefingStation {
+    pub icao: String,
+    /// Station name from the saved airport, shown above the report
+    #[serde(default)]
+    pub name: Option<String>,
+    pub coordinates: Option<(f64, f64)>,
+    /// Provider key, or None for an existing METAR
+    pub provider: Option<String>,
+    /// Provider that produced the report after a failover
+    #[serde(default)]
+    pub fallback: Option<String>,
+    /// Key of the source an existing METAR came from
+    #[serde(default)]
+    pub source: Option<String>,
+    pub input_method: String,
+    pub metar: String,
+    /// When the report's conditions were observed, if known
+    #[serde(default)]
+    pub observed: Option<DateTime<Utc>>,
+    pub generated: DateTime<Utc>,
+    pub auto_refresh: bool,
+    pub refresh_minutes: u64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Briefing {
+    pub active: usize,
+    pub stations: Vec<BriefingStation>,
+}
+
+/// Text layouts the briefing can be exported in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    /// Station headings with the full report below each
+    PlainText,
+    /// One line per station, for the flight notes fields of logging tools such
+    /// as SimToolkitPro and Volanta
+    FlightNotes,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::PlainText, ExportFormat::FlightNotes, ExportFormat::Markdown];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::PlainText => "Plain text",
+            ExportFormat::FlightNotes => "SimToolkitPro / Volanta notes",
+            ExportFormat::Markdown => "Markdown",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            _ => "txt",
+        }
+    }
+}
+
+// Where a station's report came from, so dispatch notes never pass a
+// synthesized report off as a real one
+fn source(station: &BriefingStation) -> &'static str {
+    match station.fallback.as_deref().or(station.provider.as_deref()) {
+        Some("onecall") => "synthesized, OpenWeather One Call",
+        Some("openmeteo") => "synthesized, Open-Meteo",
+        Some(_) => "synthesized, OpenWeather",
+        None => station.source.as_deref().and_then(MetarSource::from_key).unwrap_or(MetarSource::Noaa).label(),
+    }
+}
+
+fn name_suffix(station: &BriefingStation, separator: &str) -> String {
+    station.name.as_ref().map(|name| format!("{}{}", separator, name)).unwrap_or_default()
+}
+
+/// The briefing as text in the given format.
+pub fn export(briefing: &Briefing, format: ExportFormat, now: DateTime<Utc>) -> String {
+    let title = format!("METGen weather briefing {}", now.format("%d%H%MZ %b %Y"));
+    let age = |station: &BriefingStation| (now - station.generated).num_minutes().max(0);
+    let mut text = String::new();
+    match format {
+        ExportFormat::PlainText => {
+            text.push_str(&format!("{}\n", title));
+            for station in &briefing.stations {
+                text.push_str(&format!(
+                    "\n{}{} ({}, {} min old)\n{}\n",
+                    station.icao, name_suffix(station, " - "), source(station), age(station), station.metar
+                ));
+            }
+        }
+        ExportFormat::FlightNotes => {
+            text.push_str(&format!("WX {}\n", now.format("%d%H%MZ")));
+            for station in &briefing.stations {
+                match &station.name {
+                    Some(name) => text.push_str(&format!("{}: {}\n", name, station.metar)),
+                    None => text.push_str(&format!("{}\n", station.metar)),
+                }
+            }
+            if briefing.stations.iter().any(|station| station.provider.is_some()) {
+                text.push_str("Synthesized by METGen, not for real-world use\n");
+            }
+        }
+        ExportFormat::Markdown => {
+            text.push_str(&format!("# {}\n", title));
+            for station in &briefing.stations {
+                text.push_str(&format!(
+                    "\n## {}{}\n\n_{}, {} min old_\n\n```\n{}\n```\n",
+                    station.icao, name_suffix(station, " - "), source(station), age(station), station.metar
+                ));
+            }
+        }
+    }
+    text
+}
+
+pub fn save(briefing: &Briefing) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(briefing).map_err(|e| e.to_string())?;
+    fs::write(paths::data_file(BRIEFING_FILE), contents).map_err(|e| format!("Failed to write {}: {}", BRIEFING_FILE, e))
+}
+
+/// The saved briefing, or an empty one if there is none or it can't be read.
+pub fn load() -> Briefing {
+    fs::read_to_string(paths::data_file(BRIEFING_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
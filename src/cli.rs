@@ -0,0 +1,181 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Command line subcommands, for scripts and flight-sim launchers. They run
+// without opening a window and exit with 0 on success, 1 when no report could
+// be produced and 2 for bad arguments.
+//
+//   metgen generate --icao KJFK --units imperial
+//   metgen generate --coordinates 47.1,-122.3 --ident KXYZ --format json
+//   echo "KSEA KBFI" | metgen pipe
+
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use crate::coordinates::parse_coordinate_pair;
+use crate::generation::{ApiType, GenerationSettings};
+use crate::icao_region;
+use crate::input_handler::{self, validate_lat_lon};
+use crate::noaa_cache;
+use crate::pipe::{self, Format, PipeResult, UNNAMED_IDENTIFIER};
+use crate::units::UnitPreset;
+
+const SUBCOMMANDS: [&str; 3] = ["generate", "pipe", "help"];
+
+const USAGE: &str = "\
+Usage: metgen [--profile <name>] [--locked]               start the app
+       metgen [--profile <name>] generate <station> [options]
+       metgen [--profile <name>] pipe [--format text|json] [--provider standard|onecall|openmeteo]
+
+Station (one of):
+  --icao <code>             ICAO code; the real NOAA METAR is used when there is one
+  --coordinates <lat,lon>   position, in any format the app accepts
+  --location <place>        place name, looked up with OpenWeather geocoding
+
+Options:
+  --ident <code>            identifier for the report (default ZZZZ, or the ICAO code)
+  --synthesize              synthesize even when NOAA has a METAR for the ICAO code
+  --units <preset>          metric, imperial, canada, uk or mps (default: from the config)
+  --provider <api>          standard, onecall or openmeteo (default standard)
+  --format text|json        bare METAR (default) or a JSON object
+
+--cli refuses to start the app when no subcommand is given, for launchers.";
+
+enum Station {
+    Icao(String),
+    Coordinates(String),
+    Location(String),
+}
+
+/// Runs the subcommand in `args`, if there is one, and returns the exit code.
+/// None means the app should start as usual.
+pub fn run(args: &[String], config: &Value) -> Option<i32> {
+    let position = (1..args.len()).find(|&i| SUBCOMMANDS.contains(&args[i].as_str()) && args[i - 1] != "--profile");
+    let Some(position) = position else {
+        if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+            print_usage();
+            return Some(0);
+        }
+        if args.iter().any(|arg| arg == "--cli") {
+            eprintln!("{}", USAGE);
+            return Some(2);
+        }
+        return None;
+    };
+
+    let rest = &args[position + 1..];
+    Some(match args[position].as_str() {
+        "generate" => generate(rest, config),
+        "pipe" => pipe::run(rest, config),
+        _ => {
+            print_usage();
+            0
+        }
+    })
+}
+
+// Ignores a closed stdout, e.g. when piped into head
+fn print_usage() {
+    let _ = writeln!(io::stdout(), "{}", USAGE);
+}
+
+fn generate(args: &[String], config: &Value) -> i32 {
+    let mut station = None;
+    let mut identifier = None;
+    let mut synthesize = false;
+    let mut config = config.clone();
+    let mut api = ApiType::Standard;
+    let mut format = Format::Text;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--synthesize" {
+            synthesize = true;
+            continue;
+        }
+        let value = args.next().map(String::as_str);
+        match (arg.as_str(), value) {
+            ("--icao", Some(icao)) => station = Some(Station::Icao(icao.trim().to_uppercase())),
+            ("--coordinates", Some(coordinates)) => station = Some(Station::Coordinates(coordinates.to_string())),
+            ("--location", Some(location)) => station = Some(Station::Location(location.to_string())),
+            ("--ident", Some(ident)) => identifier = Some(ident.trim().to_uppercase()),
+            ("--units", Some(key)) if UnitPreset::ALL.iter().any(|preset| preset.key() == key) => config["units"] = json!(key),
+            ("--provider", Some(key)) if ApiType::ALL.iter().any(|api| api.key() == key) => {
+                api = ApiType::ALL.into_iter().find(|api| api.key() == key).unwrap_or_default();
+            }
+            ("--format", Some(key)) if Format::from_key(key).is_some() => format = Format::from_key(key).unwrap_or(format),
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+    let Some(station) = station else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let settings = GenerationSettings::from_config(&config, api);
+    let result = report(&station, identifier, synthesize, &settings);
+    let failed = result.metar.is_err();
+    if let Err(e) = pipe::write_result(&mut io::stdout(), &result, format) {
+        eprintln!("Failed to write the report: {}", e);
+        return 1;
+    }
+    if failed { 1 } else { 0 }
+}
+
+fn report(station: &Station, identifier: Option<String>, synthesize: bool, settings: &GenerationSettings) -> PipeResult {
+    let (input, coordinates) = match station {
+        Station::Icao(icao) => {
+            if !synthesize {
+                if let Some(existing) = noaa_cache::cached_metar(icao)
+                    .or_else(|| input_handler::poll_metar(icao, &settings.alternate_sources))
+                {
+                    return PipeResult { input: icao.clone(), icao: icao.clone(), source: existing.source.key(), metar: Ok(existing.raw) };
+                }
+            }
+            let coordinates = input_handler::resolve_icao_to_lat_lon(icao, &settings.alternate_sources)
+                .ok_or_else(|| format!("Could not resolve ICAO code: {}", icao));
+            (icao, coordinates)
+        }
+        Station::Coordinates(text) => {
+            let coordinates = parse_coordinate_pair(text)
+                .and_then(|(lat, lon)| validate_lat_lon(lat, lon))
+                .ok_or_else(|| "Invalid latitude/longitude values".to_string());
+            (text, coordinates)
+        }
+        Station::Location(place) => {
+            let coordinates = input_handler::resolve_freeform_input(place, &settings.api_key)
+                .ok_or_else(|| format!("Could not find location: {}", place));
+            (place, coordinates)
+        }
+    };
+
+    let icao = identifier.unwrap_or_else(|| match station {
+        Station::Icao(icao) => icao.clone(),
+        // Coordinates and places given without --ident
+        _ => UNNAMED_IDENTIFIER.to_string(),
+    });
+    match coordinates {
+        Ok((lat, lon)) => {
+            if let Some(warning) = icao_region::mismatch_warning(&icao, lat, lon) {
+                eprintln!("Warning: {}", warning);
+            }
+            pipe::synthesize(input, &icao, lat, lon, settings)
+        }
+        Err(e) => PipeResult { input: input.clone(), icao, source: "", metar: Err(e) },
+    }
+}
@@ -16,32 +16,148 @@
 
 use std::fs;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value, json};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use crate::paths;
+use crate::storage;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserAirport {
     pub icao: String,
     pub latitude: f64,
     pub longitude: f64,
+    /// Optional station name shown above the METAR, e.g. "MY RANCH STRIP (private)"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 const CONFIG_FILE: &str = "config.json";
+const PROFILE_FILE_PREFIX: &str = "config-";
+pub const DEFAULT_PROFILE: &str = "default";
+
+// Name of the profile whose config file is currently in use
+static ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+// Environment variables that take precedence over the keys stored in config.json
+const ENV_OWM_KEY: &str = "METGEN_OWM_KEY";
+const ENV_ONECALL_KEY: &str = "METGEN_ONECALL_KEY";
+
+// Keys of the alternate METAR sources, as (config field, environment variable).
+// Decrypted into "decrypted_<field>" like the OpenWeather keys.
+const ALTERNATE_SOURCE_KEYS: [(&str, &str); 2] =
+    [("checkwx_api_key", "METGEN_CHECKWX_KEY"), ("avwx_api_key", "METGEN_AVWX_KEY")];
+
+// Upgrades a config from one schema version to the next. MIGRATIONS[n] takes a
+// version n config to version n + 1; files written before versioning are version 0.
+type Migration = fn(&mut Value);
+
+const MIGRATIONS: [Migration; 1] = [migrate_user_airports];
+
+/// Schema version written by this build.
+pub const SCHEMA_VERSION: u64 = MIGRATIONS.len() as u64;
+
+// Version 0 -> 1: saved airports were written by hand or by older builds with string
+// coordinates and untrimmed, lowercase identifiers, which the loader skipped.
+// Coordinates become numbers and identifiers are trimmed and uppercased; entries
+// that still can't be used are kept under "user_airports_unreadable" instead of
+// being lost.
+fn migrate_user_airports(config: &mut Value) {
+    let Some(airports) = config["user_airports"].as_array().cloned() else {
+        config["user_airports"] = json!([]);
+        return;
+    };
+
+    let number = |value: &Value| value.as_f64().or_else(|| value.as_str()?.trim().parse().ok());
+    let mut migrated: Vec<Value> = Vec::new();
+    let mut unreadable = Vec::new();
+    for mut airport in airports {
+        let icao = airport["icao"].as_str().map(|icao| icao.trim().to_uppercase());
+        match (icao, number(&airport["latitude"]), number(&airport["longitude"])) {
+            (Some(icao), Some(lat), Some(lon)) if !icao.is_empty() => {
+                if migrated.iter().any(|a| a["icao"].as_str() == Some(&icao)) {
+                    continue;
+                }
+                airport["icao"] = json!(icao);
+                airport["latitude"] = json!(lat);
+                airport["longitude"] = json!(lon);
+                migrated.push(airport);
+            }
+            _ => unreadable.push(airport),
+        }
+    }
+
+    config["user_airports"] = Value::Array(migrated);
+    if !unreadable.is_empty() {
+        config["user_airports_unreadable"] = Value::Array(unreadable);
+    }
+}
+
+// Runs every migration the config hasn't had yet. Returns true if it changed.
+// A config from a newer build is left alone rather than downgraded.
+fn migrate_config(config: &mut Value) -> bool {
+    if !config.is_object() {
+        return false;
+    }
+    let version = config["schema_version"].as_u64().unwrap_or(0);
+    if version >= SCHEMA_VERSION {
+        if version > SCHEMA_VERSION {
+            eprintln!(
+                "Config schema version {} is newer than this build supports ({}); some settings may be ignored",
+                version, SCHEMA_VERSION
+            );
+        }
+        return false;
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(config);
+    }
+    config["schema_version"] = json!(SCHEMA_VERSION);
+    true
+}
+
+// Migrates the config file in place, keeping the original as <file>.v<version>.bak
+fn migrate_config_file(contents: &str, config: &mut Value) {
+    let version = config["schema_version"].as_u64().unwrap_or(0);
+    if !migrate_config(config) {
+        return;
+    }
+
+    let path = config_path();
+    let backup = PathBuf::from(format!("{}.v{}.bak", path.display(), version));
+    if let Err(e) = fs::write(&backup, contents) {
+        eprintln!("Failed to back up {} before migrating it: {}", path.display(), e);
+        return;
+    }
+    match serde_json::to_string_pretty(config) {
+        Ok(config_str) => {
+            if let Err(e) = fs::write(&path, config_str) {
+                eprintln!("Failed to write migrated {}: {}", path.display(), e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize migrated config: {}", e),
+    }
+}
 
 pub fn load_config() -> (Value, String, String) {
-    match fs::read_to_string(CONFIG_FILE) {
+    match fs::read_to_string(config_path()) {
         Ok(contents) => {
             match serde_json::from_str(&contents) {
                 Ok(json) => {
-                    let config: Value = json;
+                    let mut config: Value = json;
+                    migrate_config_file(&contents, &mut config);
                     let api_key = config["api_key"].as_str().unwrap_or("").to_string();
                     let one_call_api_key = config["one_call_api_key"].as_str().unwrap_or("").to_string();
-                    
+
                     // Decrypt API keys
-                    let decrypted_api_key = decrypt_key(&api_key);
-                    let decrypted_one_call_api_key = decrypt_key(&one_call_api_key);
-                    
+                    let decrypted_api_key = env_key_override(ENV_OWM_KEY)
+                        .unwrap_or_else(|| decrypt_key(&api_key));
+                    let decrypted_one_call_api_key = env_key_override(ENV_ONECALL_KEY)
+                        .unwrap_or_else(|| decrypt_key(&one_call_api_key));
+
                     (config, decrypted_api_key, decrypted_one_call_api_key)
                 }
                 Err(_) => (Value::Null, String::new(), String::new())
@@ -51,94 +167,179 @@ pub fn load_config() -> (Value, String, String) {
     }
 }
 
+/// The active profile's saved airports, in display order.
 pub fn get_user_airports() -> Vec<UserAirport> {
-    if let Ok(contents) = fs::read_to_string(CONFIG_FILE) {
-        if let Ok(config) = serde_json::from_str::<Value>(&contents) {
-            if let Some(airports) = config["user_airports"].as_array() {
-                return airports
-                    .iter()
-                    .filter_map(|airport| {
-                        if let (Some(icao), Some(lat), Some(lon)) = (
-                            airport["icao"].as_str(),
-                            airport["latitude"].as_f64(),
-                            airport["longitude"].as_f64(),
-                        ) {
-                            Some(UserAirport {
-                                icao: icao.to_string(),
-                                latitude: lat,
-                                longitude: lon,
-                            })
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-            }
-        }
+    storage::get().user_airports(&active_profile())
+}
+
+// Loads the active profile's saved airports, applies the change and stores them
+// back if it reports that anything changed
+fn update_user_airports(change: impl FnOnce(&mut Vec<UserAirport>) -> bool) -> io::Result<()> {
+    let storage = storage::get();
+    let profile = active_profile();
+    let mut airports = storage.user_airports(&profile);
+    if change(&mut airports) {
+        storage.set_user_airports(&profile, &airports).map_err(io::Error::other)?;
     }
-    Vec::new()
+    Ok(())
 }
 
 pub fn save_user_airport(icao: String, lat: f64, lon: f64) -> io::Result<()> {
-    let mut config = if let Ok(contents) = fs::read_to_string(CONFIG_FILE) {
-        serde_json::from_str::<Value>(&contents).unwrap_or_else(|_| json!({
-            "api_key": "",
-            "one_call_api_key": "",
-            "units": "metric",
-            "user_airports": []
-        }))
-    } else {
-        json!({
-            "api_key": "",
-            "one_call_api_key": "",
-            "units": "metric",
-            "user_airports": []
-        })
-    };
+    update_user_airports(|airports| {
+        if airports.iter().any(|a| a.icao == icao) {
+            return false;
+        }
+        airports.push(UserAirport { icao, latitude: lat, longitude: lon, name: None });
+        true
+    })
+}
 
-    // Initialize user_airports array if it doesn't exist
-    if config.get("user_airports").is_none() {
-        config["user_airports"] = json!([]);
-    }
+pub fn delete_user_airport(icao: &str) -> io::Result<()> {
+    update_user_airports(|airports| {
+        let len_before = airports.len();
+        airports.retain(|a| a.icao != icao);
+        airports.len() != len_before
+    })
+}
 
-    // Check if airport already exists
-    let should_add = if let Some(airports) = config["user_airports"].as_array() {
-        !airports.iter().any(|a| a["icao"].as_str() == Some(&icao))
-    } else {
-        true
-    };
+/// Saves several airports in one write, skipping any whose ICAO is already saved.
+/// Returns the number of airports added.
+pub fn save_user_airports(new_airports: &[UserAirport]) -> io::Result<usize> {
+    let mut added = 0;
+    update_user_airports(|airports| {
+        for airport in new_airports {
+            if !airports.iter().any(|a| a.icao == airport.icao) {
+                airports.push(airport.clone());
+                added += 1;
+            }
+        }
+        added > 0
+    })?;
+    Ok(added)
+}
 
-    if should_add {
-        if let Some(airports) = config["user_airports"].as_array_mut() {
-            airports.push(json!({
-                "icao": icao,
-                "latitude": lat,
-                "longitude": lon
-            }));
-            
-            let config_str = serde_json::to_string_pretty(&config)?;
-            fs::write(CONFIG_FILE, config_str)?;
+/// Sets or, with an empty name, removes a saved airport's station name.
+pub fn set_user_airport_name(icao: &str, name: &str) -> io::Result<()> {
+    let name = name.trim();
+    update_user_airports(|airports| match airports.iter_mut().find(|a| a.icao == icao) {
+        Some(airport) => {
+            airport.name = Some(name.to_string()).filter(|name| !name.is_empty());
+            true
         }
-    }
-    
-    Ok(())
+        None => false,
+    })
 }
 
-pub fn delete_user_airport(icao: &str) -> io::Result<()> {
-    if let Ok(contents) = fs::read_to_string(CONFIG_FILE) {
-        if let Ok(mut config) = serde_json::from_str::<Value>(&contents) {
-            if let Some(airports) = config["user_airports"].as_array_mut() {
-                let len_before = airports.len();
-                airports.retain(|a| a["icao"].as_str() != Some(icao));
-                
-                if airports.len() != len_before {
-                    let config_str = serde_json::to_string_pretty(&config)?;
-                    fs::write(CONFIG_FILE, config_str)?;
-                }
+/// Moves the saved airport `icao` into the position currently held by `target_icao`.
+pub fn move_user_airport(icao: &str, target_icao: &str) -> io::Result<()> {
+    update_user_airports(|airports| {
+        let from = airports.iter().position(|a| a.icao == icao);
+        let to = airports.iter().position(|a| a.icao == target_icao);
+        match (from, to) {
+            (Some(from), Some(to)) => {
+                let airport = airports.remove(from);
+                airports.insert(to, airport);
+                true
             }
+            _ => false,
         }
+    })
+}
+
+/// Saved airports stored in a profile's config file, the JSON storage for them.
+pub fn read_config_airports(profile: &str) -> Vec<UserAirport> {
+    let Ok(contents) = fs::read_to_string(profile_path(profile)) else {
+        return Vec::new();
+    };
+    let Ok(config) = serde_json::from_str::<Value>(&contents) else {
+        return Vec::new();
+    };
+    config["user_airports"]
+        .as_array()
+        .map(|airports| {
+            airports
+                .iter()
+                .filter_map(|airport| {
+                    Some(UserAirport {
+                        icao: airport["icao"].as_str()?.to_string(),
+                        latitude: airport["latitude"].as_f64()?,
+                        longitude: airport["longitude"].as_f64()?,
+                        name: airport["name"].as_str().map(|name| name.to_string()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A top-level value from a profile's config file, Null if it is missing.
+pub fn read_profile_setting(profile: &str, key: &str) -> Value {
+    fs::read_to_string(profile_path(profile))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+        .map(|mut config| config[key].take())
+        .unwrap_or(Value::Null)
+}
+
+/// Stores a top-level value in a profile's config file.
+pub fn write_profile_setting(profile: &str, key: &str, value: Value) -> io::Result<()> {
+    update_config_file(&profile_path(profile), |config| config[key] = value)
+}
+
+/// Replaces the saved airports in a profile's config file.
+pub fn write_config_airports(profile: &str, airports: &[UserAirport]) -> io::Result<()> {
+    update_config_file(&profile_path(profile), |config| config["user_airports"] = json!(airports))
+}
+
+// Number of unpinned entries kept in the recent ICAO list
+const MAX_RECENT_ICAOS: usize = 10;
+
+fn string_list(config: &Value, key: &str) -> Vec<String> {
+    config[key]
+        .as_array()
+        .map(|items| items.iter().filter_map(|item| item.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+// Reads the config, applies the change and writes it back
+pub fn update_config_file(path: &Path, change: impl FnOnce(&mut Value)) -> io::Result<()> {
+    let contents = fs::read_to_string(path)?;
+    let mut config = serde_json::from_str::<Value>(&contents)?;
+    change(&mut config);
+    let config_str = serde_json::to_string_pretty(&config)?;
+    fs::write(path, config_str)
+}
+
+/// Returns the recently used ICAOs (most recent first) and the pinned ICAOs.
+pub fn recent_icaos(config: &Value) -> (Vec<String>, Vec<String>) {
+    (string_list(config, "recent_icaos"), string_list(config, "pinned_icaos"))
+}
+
+/// Moves an ICAO to the front of the recent list. Pinned entries are never evicted.
+pub fn add_recent_icao(config: &mut Value, icao: &str) {
+    let pinned = string_list(config, "pinned_icaos");
+    let mut recent = string_list(config, "recent_icaos");
+    recent.retain(|entry| entry != icao);
+    recent.insert(0, icao.to_string());
+
+    let mut unpinned = 0;
+    recent.retain(|entry| {
+        if pinned.contains(entry) {
+            return true;
+        }
+        unpinned += 1;
+        unpinned <= MAX_RECENT_ICAOS
+    });
+    config["recent_icaos"] = json!(recent);
+}
+
+pub fn set_icao_pinned(config: &mut Value, icao: &str, pinned: bool) {
+    let mut pinned_icaos = string_list(config, "pinned_icaos");
+    pinned_icaos.retain(|entry| entry != icao);
+    if pinned {
+        pinned_icaos.push(icao.to_string());
     }
-    Ok(())
+    config["pinned_icaos"] = json!(pinned_icaos);
 }
 
 pub fn encrypt_key(key: &str) -> String {
@@ -152,9 +353,32 @@ fn decrypt_key(encrypted: &str) -> String {
         .unwrap_or_default()
 }
 
+/// Loads an optional `.env` file from the program's directory into the process
+/// environment. Variables already set in the environment are left untouched.
+pub fn load_env_file() {
+    let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) else {
+        return;
+    };
+    let _ = dotenvy::from_path(dir.join(".env"));
+}
+
+/// Returns the key set in the given environment variable, if present and non-empty.
+fn env_key_override(var: &str) -> Option<String> {
+    std::env::var(var)
+        .ok()
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+}
+
+/// Returns true if either API key is being supplied through the environment.
+pub fn keys_from_env() -> bool {
+    env_key_override(ENV_OWM_KEY).is_some() || env_key_override(ENV_ONECALL_KEY).is_some()
+}
+
 pub fn ensure_config_exists() -> io::Result<bool> {
-    if !std::path::Path::new(CONFIG_FILE).exists() {
+    if !config_path().exists() {
         let default_config = json!({
+            "schema_version": SCHEMA_VERSION,
             "api_key": "",
             "one_call_api_key": "",
             "units": "metric",
@@ -162,9 +386,92 @@ pub fn ensure_config_exists() -> io::Result<bool> {
         });
         
         let config_str = serde_json::to_string_pretty(&default_config)?;
-        fs::write(CONFIG_FILE, config_str)?;
+        fs::write(config_path(), config_str)?;
         Ok(true) // Return true to indicate this was first run
     } else {
         Ok(false) // Return false to indicate config already existed
     }
-}
\ No newline at end of file
+}
+
+/// Returns the name of the active profile.
+pub fn active_profile() -> String {
+    ACTIVE_PROFILE
+        .lock()
+        .ok()
+        .and_then(|profile| profile.clone())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// Switches the active profile. All subsequent config reads and writes go to its file.
+pub fn set_active_profile(name: &str) {
+    if let Ok(mut profile) = ACTIVE_PROFILE.lock() {
+        *profile = Some(name.to_string());
+    }
+}
+
+/// Path of the config file for the active profile. The default profile keeps using
+/// config.json, every other profile lives in its own config-<name>.json.
+pub fn config_path() -> PathBuf {
+    profile_path(&active_profile())
+}
+
+fn profile_path(name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        paths::config_file(CONFIG_FILE)
+    } else {
+        paths::config_file(&format!("{}{}.json", PROFILE_FILE_PREFIX, name))
+    }
+}
+
+/// Profile names may only contain letters, digits, '-' and '_'.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 32
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Lists the default profile followed by every profile found next to config.json.
+pub fn list_profiles() -> Vec<String> {
+    let mut profiles = Vec::new();
+    if let Ok(entries) = fs::read_dir(paths::config_dir()) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if let Some(name) = file_name
+                .strip_prefix(PROFILE_FILE_PREFIX)
+                .and_then(|rest| rest.strip_suffix(".json"))
+            {
+                if is_valid_profile_name(name) && name != DEFAULT_PROFILE {
+                    profiles.push(name.to_string());
+                }
+            }
+        }
+    }
+    profiles.sort();
+    profiles.insert(0, DEFAULT_PROFILE.to_string());
+    profiles
+}
+
+/// Loads the active profile's config with decrypted keys and the runtime-only
+/// flags merged in, creating the file first if needed. Returns Null on failure.
+pub fn load_runtime_config() -> Value {
+    // Create default config if it doesn't exist
+    let is_first_run = ensure_config_exists().unwrap_or(false);
+
+    // Load config, including decrypted keys
+    let (config_json, decrypted_api_key, decrypted_one_call_api_key) = load_config();
+    if config_json.is_null() {
+        return Value::Null;
+    }
+
+    // Insert decrypted keys back into the config Value
+    let mut config = config_json;
+    config["decrypted_api_key"] = Value::String(decrypted_api_key);
+    config["decrypted_one_call_api_key"] = Value::String(decrypted_one_call_api_key);
+    for (field, var) in ALTERNATE_SOURCE_KEYS {
+        let key = env_key_override(var).unwrap_or_else(|| decrypt_key(config[field].as_str().unwrap_or("")));
+        config[format!("decrypted_{}", field)] = Value::String(key);
+    }
+    config["is_first_run"] = Value::Bool(is_first_run);
+    config["profile"] = Value::String(active_profile());
+    config
+}
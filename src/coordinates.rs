@@ -0,0 +1,375 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Parsing of latitude/longitude text as users type or paste it:
+// decimal degrees with '.' or ',' as the decimal separator, degree-minute-second
+// notation with hemisphere letters, combined "lat, lon" strings, map URLs and
+// plus codes.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Axis {
+    Latitude,
+    Longitude,
+}
+
+impl Axis {
+    fn limit(self) -> f64 {
+        match self {
+            Axis::Latitude => 90.0,
+            Axis::Longitude => 180.0,
+        }
+    }
+
+    fn hemispheres(self) -> (char, char) {
+        match self {
+            Axis::Latitude => ('N', 'S'),
+            Axis::Longitude => ('E', 'W'),
+        }
+    }
+}
+
+/// Parses a single latitude or longitude, e.g. "48.3537", "48,3537", "-122.3",
+/// "48°21'13\"N", "N 48 21.22" or "122W". Returns None if the text is not a
+/// coordinate or is out of range for the axis.
+pub fn parse_coordinate(input: &str, axis: Axis) -> Option<f64> {
+    let mut text = input.trim().to_uppercase();
+    if text.is_empty() {
+        return None;
+    }
+
+    // Hemisphere letter at either end
+    let (positive, negative) = axis.hemispheres();
+    let mut sign = 1.0;
+    if let Some(letter) = text.chars().last().filter(|c| c.is_ascii_alphabetic()) {
+        sign = hemisphere_sign(letter, positive, negative)?;
+        text.pop();
+    } else if let Some(letter) = text.chars().next().filter(|c| c.is_ascii_alphabetic()) {
+        sign = hemisphere_sign(letter, positive, negative)?;
+        text.remove(0);
+    }
+
+    // Degree, minute and second marks become separators; ',' is a decimal separator
+    let normalized: String = text
+        .chars()
+        .map(|c| match c {
+            '°' | 'º' | '\'' | '’' | '′' | '"' | '”' | '″' => ' ',
+            ',' => '.',
+            _ => c,
+        })
+        .collect();
+
+    let mut normalized = normalized.trim().to_string();
+    if let Some(rest) = normalized.strip_prefix('-') {
+        if sign < 0.0 {
+            return None; // Both a minus sign and S/W
+        }
+        sign = -1.0;
+        normalized = rest.trim().to_string();
+    } else if let Some(rest) = normalized.strip_prefix('+') {
+        normalized = rest.trim().to_string();
+    }
+
+    let parts: Vec<f64> = normalized
+        .split_whitespace()
+        .map(|part| part.parse::<f64>().ok().filter(|value| value.is_finite() && *value >= 0.0))
+        .collect::<Option<Vec<f64>>>()?;
+
+    let value = match parts.as_slice() {
+        [degrees] => *degrees,
+        [degrees, minutes] if *minutes < 60.0 => degrees + minutes / 60.0,
+        [degrees, minutes, seconds] if *minutes < 60.0 && *seconds < 60.0 && minutes.fract() == 0.0 => {
+            degrees + minutes / 60.0 + seconds / 3600.0
+        }
+        _ => return None,
+    };
+
+    let value = sign * value;
+    if value.abs() <= axis.limit() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+fn hemisphere_sign(letter: char, positive: char, negative: char) -> Option<f64> {
+    if letter == positive {
+        Some(1.0)
+    } else if letter == negative {
+        Some(-1.0)
+    } else {
+        None
+    }
+}
+
+/// Parses a combined latitude/longitude string such as "47.45, -122.31",
+/// "47,45; -122,31", "47.45 -122.31" or "47°27'N 122°18'W".
+pub fn parse_coordinate_pair(input: &str) -> Option<(f64, f64)> {
+    let text = input.trim().to_uppercase();
+    let (lat, lon) = split_pair(&text)?;
+    Some((
+        parse_coordinate(lat, Axis::Latitude)?,
+        parse_coordinate(lon, Axis::Longitude)?,
+    ))
+}
+
+// Works out where the latitude ends and the longitude begins
+fn split_pair(text: &str) -> Option<(&str, &str)> {
+    // An explicit separator that can't be a decimal comma
+    if let Some((lat, lon)) = text.split_once(';') {
+        return Some((lat, lon));
+    }
+    if let Some((lat, lon)) = text.split_once(", ") {
+        return Some((lat, lon));
+    }
+
+    // A trailing N/S hemisphere letter ends the latitude
+    if let Some(index) = text.find(['N', 'S']) {
+        let (lat, lon) = text.split_at(index + 1);
+        if !lon.trim().is_empty() && !lat.trim_end_matches(['N', 'S']).trim().is_empty() {
+            return Some((lat, lon.trim_start_matches([',', ' '])));
+        }
+    }
+
+    let commas = text.matches(',').count();
+    if commas == 1 && text.contains('.') {
+        // "47.45,-122.31"
+        return text.split_once(',');
+    }
+    if commas == 3 && !text.contains('.') {
+        // Decimal commas without spaces: "47,45,-122,31"
+        let second = text.match_indices(',').nth(1)?.0;
+        return Some((&text[..second], &text[second + 1..]));
+    }
+
+    // Two plain numbers separated by whitespace
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    if parts.len() == 2 {
+        return Some((parts[0], parts[1]));
+    }
+    None
+}
+
+// Coordinate patterns in Google Maps and OpenStreetMap URLs, most precise first
+const MAP_URL_PATTERNS: [&str; 5] = [
+    // Google Maps place marker: .../data=!3d47.4502!4d-122.3088
+    r"!3d(-?\d+(?:\.\d+)?)!4d(-?\d+(?:\.\d+)?)",
+    // OpenStreetMap marker: ?mlat=47.4502&mlon=-122.3088
+    r"mlat=(-?\d+(?:\.\d+)?)&mlon=(-?\d+(?:\.\d+)?)",
+    // Google Maps query parameters: ?q=47.4502,-122.3088 or ll=...
+    r"[?&](?:q|query|ll|center|destination)=(-?\d+(?:\.\d+)?),\s*(-?\d+(?:\.\d+)?)",
+    // Google Maps viewport: /@47.4502,-122.3088,15z
+    r"@(-?\d+(?:\.\d+)?),(-?\d+(?:\.\d+)?)",
+    // OpenStreetMap viewport: #map=15/47.4502/-122.3088
+    r"#map=\d+(?:\.\d+)?/(-?\d+(?:\.\d+)?)/(-?\d+(?:\.\d+)?)",
+];
+
+static MAP_URL_REGEXES: OnceLock<Vec<Regex>> = OnceLock::new();
+
+/// Extracts coordinates from a pasted Google Maps or OpenStreetMap URL, or
+/// decodes a full Open Location Code ("plus code") such as "84VVJM22+22".
+pub fn parse_map_location(input: &str) -> Option<(f64, f64)> {
+    let text = input.trim();
+    if text.contains("://") || text.contains("maps.") || text.contains("openstreetmap") {
+        return parse_map_url(text);
+    }
+    decode_plus_code(text)
+}
+
+fn parse_map_url(url: &str) -> Option<(f64, f64)> {
+    let url = url.replace("%2C", ",").replace("%2c", ",");
+    let regexes = MAP_URL_REGEXES.get_or_init(|| {
+        MAP_URL_PATTERNS
+            .iter()
+            .filter_map(|pattern| Regex::new(pattern).ok())
+            .collect()
+    });
+
+    regexes.iter().find_map(|regex| {
+        let captures = regex.captures(&url)?;
+        let lat = captures[1].parse::<f64>().ok()?;
+        let lon = captures[2].parse::<f64>().ok()?;
+        ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+    })
+}
+
+const PLUS_CODE_ALPHABET: &str = "23456789CFGHJMPQRVWX";
+const PLUS_CODE_SEPARATOR_POSITION: usize = 8;
+// Degrees covered by each digit of the five leading latitude/longitude pairs
+const PLUS_CODE_PAIR_RESOLUTIONS: [f64; 5] = [20.0, 1.0, 0.05, 0.0025, 0.000125];
+
+/// Decodes a full plus code to the coordinates of the centre of its area.
+/// Short codes ("JM22+22 Seattle") need a reference location and are not supported.
+pub fn decode_plus_code(code: &str) -> Option<(f64, f64)> {
+    let code = code.trim().to_uppercase();
+    if code.find('+') != Some(PLUS_CODE_SEPARATOR_POSITION) || code.matches('+').count() != 1 {
+        return None;
+    }
+
+    // Padded codes ("84VV0000+") cover a larger area; the padding carries no digits
+    let digits: Vec<f64> = code
+        .chars()
+        .filter(|c| *c != '+')
+        .take_while(|c| *c != '0')
+        .map(|c| PLUS_CODE_ALPHABET.find(c).map(|value| value as f64))
+        .collect::<Option<Vec<f64>>>()?;
+    if digits.len() < 2 || (!digits.len().is_multiple_of(2) && digits.len() < 10) {
+        return None;
+    }
+    if digits[0] >= 9.0 || digits[1] >= 18.0 {
+        return None;
+    }
+
+    let mut lat = -90.0;
+    let mut lon = -180.0;
+    let mut lat_size = 0.0;
+    let mut lon_size = 0.0;
+    for (pair, resolution) in digits.chunks(2).take(5).zip(PLUS_CODE_PAIR_RESOLUTIONS) {
+        lat += pair[0] * resolution;
+        lon += pair[1] * resolution;
+        lat_size = resolution;
+        lon_size = resolution;
+    }
+
+    // Digits past the tenth refine a 5 row x 4 column grid
+    for digit in digits.iter().skip(10) {
+        lat_size /= 5.0;
+        lon_size /= 4.0;
+        lat += (digit / 4.0).floor() * lat_size;
+        lon += (digit % 4.0) * lon_size;
+    }
+
+    Some((lat + lat_size / 2.0, lon + lon_size / 2.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("coordinate should parse");
+        assert!((actual - expected).abs() < 1e-6, "{} is not {}", actual, expected);
+    }
+
+    #[test]
+    fn decimal_commas() {
+        assert_near(parse_coordinate("51,5", Axis::Latitude), 51.5);
+        assert_near(parse_coordinate("-0,12", Axis::Longitude), -0.12);
+        assert_near(parse_coordinate("48.3537", Axis::Latitude), 48.3537);
+    }
+
+    #[test]
+    fn degrees_minutes_seconds_with_symbols() {
+        assert_near(parse_coordinate("48°21'13\"N", Axis::Latitude), 48.0 + 21.0 / 60.0 + 13.0 / 3600.0);
+        assert_near(parse_coordinate("122°18′36″W", Axis::Longitude), -(122.0 + 18.0 / 60.0 + 36.0 / 3600.0));
+    }
+
+    #[test]
+    fn degrees_minutes_seconds_without_symbols() {
+        assert_near(parse_coordinate("51 30 26 N", Axis::Latitude), 51.0 + 30.0 / 60.0 + 26.0 / 3600.0);
+        assert_near(parse_coordinate("N 48 21.22", Axis::Latitude), 48.0 + 21.22 / 60.0);
+    }
+
+    #[test]
+    fn hemisphere_letters() {
+        assert_near(parse_coordinate("33.9S", Axis::Latitude), -33.9);
+        assert_near(parse_coordinate("w0.12", Axis::Longitude), -0.12);
+        assert_near(parse_coordinate("151.2E", Axis::Longitude), 151.2);
+        // The wrong axis' letters, and a minus sign with S/W, are rejected
+        assert_eq!(parse_coordinate("51.5E", Axis::Latitude), None);
+        assert_eq!(parse_coordinate("-33.9S", Axis::Latitude), None);
+    }
+
+    #[test]
+    fn pair_pasted_into_the_latitude_field() {
+        assert_eq!(parse_coordinate("51.5, -0.12", Axis::Latitude), None);
+        assert_eq!(parse_coordinate_pair("51.5, -0.12"), Some((51.5, -0.12)));
+        assert_eq!(parse_coordinate_pair("51,5; -0,12"), Some((51.5, -0.12)));
+        assert_eq!(parse_coordinate_pair("51.5,-0.12"), Some((51.5, -0.12)));
+        assert_eq!(parse_coordinate_pair("51,5,-0,12"), Some((51.5, -0.12)));
+        let (lat, lon) = parse_coordinate_pair("47°27'N 122°18'W").unwrap();
+        assert_near(Some(lat), 47.45);
+        assert_near(Some(lon), -122.3);
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        assert_eq!(parse_coordinate("90.1", Axis::Latitude), None);
+        assert_eq!(parse_coordinate("-91", Axis::Latitude), None);
+        assert_eq!(parse_coordinate("180.5", Axis::Longitude), None);
+        assert_eq!(parse_coordinate("48 61 00", Axis::Latitude), None);
+        assert_eq!(parse_coordinate_pair("95.0, 10.0"), None);
+        assert_near(parse_coordinate("-180", Axis::Longitude), -180.0);
+    }
+
+    #[test]
+    fn text_that_is_not_a_coordinate() {
+        assert_eq!(parse_coordinate("", Axis::Latitude), None);
+        assert_eq!(parse_coordinate("KSEA", Axis::Latitude), None);
+        assert_eq!(parse_coordinate("NaN", Axis::Latitude), None);
+    }
+
+    fn assert_location(actual: Option<(f64, f64)>, lat: f64, lon: f64) {
+        let (actual_lat, actual_lon) = actual.expect("location should parse");
+        assert_near(Some(actual_lat), lat);
+        assert_near(Some(actual_lon), lon);
+    }
+
+    #[test]
+    fn full_plus_codes_decode_to_the_centre_of_their_area() {
+        assert_location(decode_plus_code("849VCWC8+R9"), 37.4220625, -122.0840625);
+        assert_location(decode_plus_code(" 849vcwc8+r9 "), 37.4220625, -122.0840625);
+        // Padded codes cover a larger area
+        assert_location(decode_plus_code("849V0000+"), 37.5, -122.5);
+        assert_location(parse_map_location("849VCWC8+R9"), 37.4220625, -122.0840625);
+    }
+
+    #[test]
+    fn short_and_invalid_plus_codes_are_rejected() {
+        assert_eq!(decode_plus_code("CWC8+R9"), None);
+        assert_eq!(decode_plus_code("CWC8+R9 Mountain View"), None);
+        assert_eq!(decode_plus_code("849VCWC8R9"), None);
+        assert_eq!(decode_plus_code("849VCWC8+R9+"), None);
+        assert_eq!(decode_plus_code("849VCWCA+R9"), None);
+        // The first digit would be north of 90 degrees
+        assert_eq!(decode_plus_code("F49VCWC8+R9"), None);
+    }
+
+    #[test]
+    fn google_maps_urls() {
+        let place = "https://www.google.com/maps/place/Seattle-Tacoma/@47.45,-122.30,15z/data=!3d47.4502!4d-122.3088";
+        assert_eq!(parse_map_location(place), Some((47.4502, -122.3088)));
+        assert_eq!(parse_map_location("https://www.google.com/maps/@51.47,-0.4543,14z"), Some((51.47, -0.4543)));
+        assert_eq!(parse_map_location("https://maps.google.com/?q=47.4502%2C-122.3088"), Some((47.4502, -122.3088)));
+    }
+
+    #[test]
+    fn openstreetmap_urls() {
+        let marker = "https://www.openstreetmap.org/?mlat=51.4775&mlon=-0.4614#map=14/51.4700/-0.4543";
+        assert_eq!(parse_map_location(marker), Some((51.4775, -0.4614)));
+        assert_eq!(parse_map_location("https://www.openstreetmap.org/#map=14/51.4700/-0.4543"), Some((51.47, -0.4543)));
+    }
+
+    #[test]
+    fn malformed_map_urls_have_no_location() {
+        assert_eq!(parse_map_location("https://www.google.com/maps/place/Heathrow"), None);
+        assert_eq!(parse_map_location("https://maps.google.com/?q=Seattle"), None);
+        assert_eq!(parse_map_location("https://www.openstreetmap.org/#map=14/95.0/-0.4543"), None);
+        assert_eq!(parse_map_location("https://www.google.com/maps/@51.47"), None);
+    }
+}
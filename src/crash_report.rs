@@ -0,0 +1,132 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Crash reports. The GUI build has no console on Windows, so a panic would
+// otherwise vanish without a trace. Reports are written locally and are only
+// ever submitted when the user opts in and confirms.
+
+use std::backtrace::Backtrace;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use reqwest::Url;
+use crate::paths;
+
+const CRASH_REPORT_DIR: &str = "crash_reports";
+const PENDING_EXTENSION: &str = "txt";
+const HANDLED_EXTENSION: &str = "handled";
+// Keeps the prefilled issue URL within what browsers and GitHub accept
+const MAX_SUBMITTED_REPORT_CHARS: usize = 6000;
+
+/// Installs a panic hook that writes a crash report before the default hook runs.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => info
+                .payload()
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "Unknown panic".to_string()),
+        };
+        let location = info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()))
+            .unwrap_or_default();
+        let report = format!(
+            "METGen crash report\n\
+             Version: {}\n\
+             OS: {} ({})\n\
+             Time: {}\n\
+             Thread: {}\n\
+             Panic: {}\n\
+             Location: {}\n\n\
+             Backtrace:\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            Utc::now().format("%Y-%m-%d %H:%M:%SZ"),
+            std::thread::current().name().unwrap_or("unnamed"),
+            message,
+            location,
+            Backtrace::force_capture(),
+        );
+
+        match write_report(&anonymize(&report)) {
+            Some(path) => eprintln!("Crash report written to {}", path.display()),
+            None => eprintln!("Failed to write crash report."),
+        }
+        default_hook(info);
+    }));
+}
+
+// Strips the user's home directory from paths so reports don't carry usernames
+fn anonymize(report: &str) -> String {
+    let home = std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")).unwrap_or_default();
+    if home.len() > 1 {
+        report.replace(&home, "~")
+    } else {
+        report.to_string()
+    }
+}
+
+fn write_report(report: &str) -> Option<PathBuf> {
+    let dir = paths::data_file(CRASH_REPORT_DIR);
+    fs::create_dir_all(&dir).ok()?;
+    let path = dir.join(format!(
+        "crash-{}.{}",
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        PENDING_EXTENSION
+    ));
+    fs::write(&path, report).ok()?;
+    Some(path)
+}
+
+/// Crash reports that have not yet been submitted or dismissed, oldest first.
+pub fn pending_reports() -> Vec<PathBuf> {
+    let mut reports: Vec<PathBuf> = fs::read_dir(paths::data_file(CRASH_REPORT_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == PENDING_EXTENSION))
+                .collect()
+        })
+        .unwrap_or_default();
+    reports.sort();
+    reports
+}
+
+/// Marks a report as dealt with so it is not offered again. The file is kept.
+pub fn mark_handled(path: &Path) {
+    if let Err(e) = fs::rename(path, path.with_extension(HANDLED_EXTENSION)) {
+        eprintln!("Failed to mark crash report {} as handled: {}", path.display(), e);
+    }
+}
+
+/// A prefilled "new issue" URL for the report, which the user reviews before submitting.
+pub fn issue_url(report: &str) -> Option<String> {
+    let mut body: String = report.chars().take(MAX_SUBMITTED_REPORT_CHARS).collect();
+    if body.len() < report.len() {
+        body.push_str("\n[truncated]");
+    }
+    let body = format!("```\n{}\n```", body);
+    let base = format!("{}/issues/new", env!("CARGO_PKG_REPOSITORY"));
+    Url::parse_with_params(&base, &[("title", "Crash report"), ("body", body.as_str())])
+        .ok()
+        .map(|url| url.to_string())
+}
@@ -0,0 +1,126 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Disk-backed cache of downloaded data, one file per entry in the cache folder.
+// When the folder grows past the size limit the least recently written
+// entries are removed first.
+
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use crate::paths;
+
+const CACHE_DIR: &str = "cache";
+pub const DEFAULT_MAX_SIZE_MB: u64 = 50;
+
+static MAX_SIZE_BYTES: AtomicU64 = AtomicU64::new(DEFAULT_MAX_SIZE_MB * 1024 * 1024);
+static HITS: AtomicU64 = AtomicU64::new(0);
+
+/// Cache size limit in megabytes from "cache_max_mb" in the config.
+pub fn max_size_from_config(config: &Value) -> u64 {
+    config["cache_max_mb"].as_u64().filter(|&mb| mb > 0).unwrap_or(DEFAULT_MAX_SIZE_MB)
+}
+
+/// Sets the size limit, trimming the cache in the background if it is already larger.
+pub fn set_max_size_mb(megabytes: u64) {
+    MAX_SIZE_BYTES.store(megabytes * 1024 * 1024, Ordering::Relaxed);
+    thread::spawn(enforce_limit);
+}
+
+// Entries are named after the namespace and a hash of the key, so keys can be
+// any text (locations, URLs) without becoming invalid file names
+fn entry_path(namespace: &str, key: &str) -> PathBuf {
+    // FNV-1a, stable across builds unlike the std hasher
+    let hash = key
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    paths::data_file(CACHE_DIR).join(format!("{}-{:016x}", namespace, hash))
+}
+
+/// The cached contents for a key, if written less than `max_age` ago.
+pub fn get(namespace: &str, key: &str, max_age: Duration) -> Option<String> {
+    let path = entry_path(namespace, key);
+    let age = fs::metadata(&path).ok()?.modified().ok()?.elapsed().ok()?;
+    if age > max_age {
+        return None;
+    }
+    fs::read_to_string(path).ok()
+}
+
+/// Counts a provider request that was answered from the cache instead.
+pub fn record_hit() {
+    HITS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Provider requests answered from the cache since the last call, for the
+/// history to keep with the next report.
+pub fn take_hits() -> u64 {
+    HITS.swap(0, Ordering::Relaxed)
+}
+
+/// Stores contents for a key, replacing any earlier entry.
+pub fn put(namespace: &str, key: &str, contents: &str) -> Result<(), String> {
+    fs::create_dir_all(paths::data_file(CACHE_DIR)).map_err(|e| format!("Failed to create {}: {}", CACHE_DIR, e))?;
+    let path = entry_path(namespace, key);
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    enforce_limit();
+    Ok(())
+}
+
+// Cache files with their size and last write time
+fn entries() -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(dir) = fs::read_dir(paths::data_file(CACHE_DIR)) else {
+        return Vec::new();
+    };
+    dir.filter_map(|entry| {
+        let entry = entry.ok()?;
+        let metadata = entry.metadata().ok()?;
+        metadata.is_file().then(|| (entry.path(), metadata.len(), metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+    })
+    .collect()
+}
+
+/// Total size of the cache in bytes.
+pub fn usage() -> u64 {
+    entries().iter().map(|(_, size, _)| size).sum()
+}
+
+/// Removes every cached entry.
+pub fn clear() -> io::Result<()> {
+    match fs::remove_dir_all(paths::data_file(CACHE_DIR)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn enforce_limit() {
+    let limit = MAX_SIZE_BYTES.load(Ordering::Relaxed);
+    let mut entries = entries();
+    let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    entries.sort_by_key(|(_, _, modified)| *modified);
+    for (path, size, _) in entries {
+        if total <= limit {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+}
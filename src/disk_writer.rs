@@ -0,0 +1,80 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Disk writes the GUI doesn't wait for, such as history records and the saved
+// briefing. They run one at a time on a background thread, in the order they
+// were made, so a later write is never overtaken by an earlier one.
+
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+type Write = Box<dyn FnOnce() + Send>;
+
+static WRITER: OnceLock<Mutex<Sender<Write>>> = OnceLock::new();
+
+fn writer() -> &'static Mutex<Sender<Write>> {
+    WRITER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Write>();
+        thread::spawn(move || {
+            for write in receiver {
+                write();
+            }
+        });
+        Mutex::new(sender)
+    })
+}
+
+/// Queues a write to run after the ones already queued.
+pub fn write(write: impl FnOnce() + Send + 'static) {
+    if let Ok(sender) = writer().lock() {
+        let _ = sender.send(Box::new(write));
+    }
+}
+
+/// Waits until every queued write has run, e.g. before exiting.
+pub fn flush() {
+    if WRITER.get().is_none() {
+        return;
+    }
+    let (done, finished) = mpsc::channel();
+    write(move || {
+        let _ = done.send(());
+    });
+    let _ = finished.recv();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn writes_run_in_order_before_flush_returns() {
+        let done = Arc::new(Mutex::new(Vec::new()));
+        for n in 0..5 {
+            let done = done.clone();
+            write(move || {
+                // Earlier writes take longer, so running them in parallel would reorder them
+                thread::sleep(Duration::from_millis(5 - n));
+                done.lock().unwrap().push(n);
+            });
+        }
+        flush();
+        assert_eq!(*done.lock().unwrap(), [0, 1, 2, 3, 4]);
+    }
+}
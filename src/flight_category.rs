@@ -0,0 +1,84 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Flight category (VFR, MVFR, IFR, LIFR) of a METAR, from the ceiling and
+// visibility in its main body. Trends and remarks are not considered.
+
+use crate::metar_decoder::decode_metar;
+use crate::units;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum FlightCategory {
+    Vfr,
+    MarginalVfr,
+    Ifr,
+    LowIfr,
+}
+
+impl FlightCategory {
+    pub fn label(self) -> &'static str {
+        match self {
+            FlightCategory::Vfr => "VFR",
+            FlightCategory::MarginalVfr => "MVFR",
+            FlightCategory::Ifr => "IFR",
+            FlightCategory::LowIfr => "LIFR",
+        }
+    }
+
+    // Ceiling in feet above ground
+    fn from_ceiling(ceiling: u32) -> Self {
+        match ceiling {
+            0..=499 => FlightCategory::LowIfr,
+            500..=999 => FlightCategory::Ifr,
+            1000..=3000 => FlightCategory::MarginalVfr,
+            _ => FlightCategory::Vfr,
+        }
+    }
+
+    // Visibility in statute miles
+    fn from_visibility(visibility: f64) -> Self {
+        if visibility < 1.0 {
+            FlightCategory::LowIfr
+        } else if visibility < 3.0 {
+            FlightCategory::Ifr
+        } else if visibility <= 5.0 {
+            FlightCategory::MarginalVfr
+        } else {
+            FlightCategory::Vfr
+        }
+    }
+}
+
+/// The flight category of a METAR, or None when it reports neither a
+/// visibility nor any cloud information.
+pub fn flight_category(metar: &str) -> Option<FlightCategory> {
+    let decoded = decode_metar(metar);
+    let clouds_reported = decoded.cavok
+        || decoded.sky_clear.is_some()
+        || !decoded.clouds.is_empty()
+        || decoded.vertical_visibility_ft.is_some();
+    if decoded.visibility_m.is_none() && !clouds_reported {
+        return None;
+    }
+
+    let by_visibility = decoded
+        .visibility_m
+        .map(|meters| FlightCategory::from_visibility(units::meters_to_statute_miles(meters)))
+        .unwrap_or(FlightCategory::Vfr);
+    let by_ceiling = decoded.ceiling_ft().map(FlightCategory::from_ceiling).unwrap_or(FlightCategory::Vfr);
+    // The more restrictive of the two decides
+    Some(if by_ceiling > by_visibility { by_ceiling } else { by_visibility })
+}
@@ -0,0 +1,80 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Joystick and gamepad buttons, so a spare cockpit button can regenerate the
+// current station. Only available when built with the "gamepad" feature.
+
+use serde_json::{json, Value};
+use std::sync::mpsc;
+
+pub const SUPPORTED: bool = cfg!(feature = "gamepad");
+
+// A button press, identified by device name and the raw button code. Raw codes
+// are used because most joysticks and throttles have no standard button layout.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+pub struct ButtonPress {
+    pub device: String,
+    pub button: u32,
+}
+
+impl ButtonPress {
+    pub fn from_config(config: &Value) -> Option<Self> {
+        let binding = config.get("gamepad_binding")?;
+        Some(Self {
+            device: binding["device"].as_str()?.to_string(),
+            button: binding["button"].as_u64()?.try_into().ok()?,
+        })
+    }
+
+    pub fn to_config(&self) -> Value {
+        json!({ "device": self.device, "button": self.button })
+    }
+}
+
+/// Starts listening for button presses on a background thread. `wake` is called
+/// after each press so the GUI updates even when it is not focused. Returns None
+/// when gamepad support is not built in or no input backend is available.
+pub fn listen(wake: impl Fn() + Send + 'static) -> Option<mpsc::Receiver<ButtonPress>> {
+    #[cfg(feature = "gamepad")]
+    {
+        let mut gilrs = gilrs::Gilrs::new()
+            .map_err(|e| eprintln!("Failed to set up gamepad input: {}", e))
+            .ok()?;
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            while let Some(event) = gilrs.next_event_blocking(None) {
+                if let gilrs::EventType::ButtonPressed(_, code) = event.event {
+                    let press = ButtonPress {
+                        device: gilrs.gamepad(event.id).name().to_string(),
+                        button: code.into_u32(),
+                    };
+                    if sender.send(press).is_err() {
+                        break;
+                    }
+                    wake();
+                }
+            }
+        });
+        Some(receiver)
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    {
+        let _ = wake;
+        None
+    }
+}
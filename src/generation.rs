@@ -0,0 +1,217 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Synthesis shared by the GUI and pipe mode: which weather provider to use and
+// the settings a report is generated with.
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use crate::input_handler::{self, AlternateSources};
+use crate::marine;
+use crate::metar_format::{apply_output_policy, station_identifier, FormatOptions, IdentifierPadding, OutputPolicy};
+use crate::metar_generator;
+use crate::one_call_metar;
+use crate::open_meteo;
+use crate::severe_weather;
+use crate::station_type::{apply_station_type, StationType};
+use crate::units::{self, MetarUnits};
+use crate::volcanic_ash::{self, AshMode};
+
+#[derive(Default, PartialEq, Clone, Copy)]
+pub enum ApiType {
+    #[default]
+    Standard,
+    OneCall,
+    // Needs no API key
+    OpenMeteo,
+}
+
+impl ApiType {
+    /// Default failover order, richest data first
+    pub const ALL: [ApiType; 3] = [ApiType::OneCall, ApiType::Standard, ApiType::OpenMeteo];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ApiType::Standard => "Standard",
+            ApiType::OneCall => "One Call",
+            ApiType::OpenMeteo => "Open-Meteo",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            ApiType::Standard => "standard",
+            ApiType::OneCall => "onecall",
+            ApiType::OpenMeteo => "openmeteo",
+        }
+    }
+
+    /// Provider name used for rate limiting and health
+    pub fn provider(self) -> &'static str {
+        match self {
+            ApiType::Standard => input_handler::OWM_STANDARD,
+            ApiType::OneCall => input_handler::OWM_ONE_CALL,
+            ApiType::OpenMeteo => input_handler::OPEN_METEO,
+        }
+    }
+}
+
+/// Provider priority order from "provider_order" in the config. Providers it
+/// leaves out are appended in the default order.
+pub fn provider_order_from_config(config: &Value) -> Vec<ApiType> {
+    let mut order: Vec<ApiType> = config["provider_order"]
+        .as_array()
+        .map(|keys| {
+            keys.iter()
+                .filter_map(|key| ApiType::ALL.into_iter().find(|api| Some(api.key()) == key.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    for api in ApiType::ALL {
+        if !order.contains(&api) {
+            order.push(api);
+        }
+    }
+    order.dedup();
+    order
+}
+
+/// A synthesized report
+pub struct Synthesized {
+    /// Provider that produced the report, which differs from the selected one after a failover
+    pub api: ApiType,
+    pub metar: String,
+    /// When the provider observed the conditions, if it said
+    pub observed_at: Option<DateTime<Utc>>,
+}
+
+// A report, its observation time and the provider's alerts, if it has any
+type ProviderReport = (String, Option<DateTime<Utc>>, Vec<String>);
+
+/// Provider, keys, units and output policy captured when a request starts
+pub struct GenerationSettings {
+    pub api: ApiType,
+    pub api_key: String,
+    pub one_call_api_key: String,
+    pub units: MetarUnits,
+    pub policy: OutputPolicy,
+    pub format: FormatOptions,
+    pub one_call_exclude: Vec<String>,
+    pub language: String,
+    // Providers tried in turn when `api` fails; empty without failover
+    pub failover: Vec<ApiType>,
+    pub identifier_padding: IdentifierPadding,
+    // Add sea conditions to the remarks for coastal and water positions
+    pub marine_data: bool,
+    pub volcanic_ash: AshMode,
+    // Follow the hourly forecast between provider updates (One Call only)
+    pub interpolate_temperature: bool,
+    pub station_type: StationType,
+    // Keys for the real-METAR sources used when NOAA has no report
+    pub alternate_sources: AlternateSources,
+}
+
+impl GenerationSettings {
+    /// Settings as stored in the config, generating with `api` first.
+    pub fn from_config(config: &Value, api: ApiType) -> GenerationSettings {
+        let failover = if config["provider_failover"].as_bool().unwrap_or(false) {
+            provider_order_from_config(config)
+        } else {
+            Vec::new()
+        };
+        GenerationSettings {
+            api,
+            api_key: config["decrypted_api_key"].as_str().unwrap_or("").to_string(),
+            one_call_api_key: config["decrypted_one_call_api_key"].as_str().unwrap_or("").to_string(),
+            units: units::units_from_config(config).1,
+            policy: OutputPolicy::from_config(config),
+            format: FormatOptions::from_config(config),
+            one_call_exclude: one_call_metar::excluded_parts_from_config(config),
+            language: input_handler::owm_language(config["owm_language"].as_str()),
+            failover,
+            identifier_padding: IdentifierPadding::from_config(config),
+            marine_data: config["marine_data"].as_bool().unwrap_or(false),
+            volcanic_ash: AshMode::from_config(config),
+            interpolate_temperature: config["interpolate_temperature"].as_bool().unwrap_or(false),
+            station_type: StationType::from_config(config),
+            alternate_sources: AlternateSources::from_config(config),
+        }
+    }
+
+    /// Synthesizes with the selected provider, falling through the failover chain
+    /// on errors.
+    pub fn synthesize(&self, icao: &str, lat: f64, lon: f64) -> Result<Synthesized, String> {
+        let icao = &station_identifier(icao, self.identifier_padding)?;
+        let mut providers = vec![self.api];
+        providers.extend(self.failover.iter().filter(|&&api| api != self.api));
+        let mut failures = Vec::new();
+        for &api in &providers {
+            let (mut metar, observed_at, alerts) = match self.synthesize_with(api, icao, lat, lon) {
+                Ok(synthesized) => synthesized,
+                Err(e) => {
+                    failures.push(format!("{}: {}", api.label(), e));
+                    continue;
+                }
+            };
+            severe_weather::check(lat, lon, &alerts);
+            metar = apply_station_type(&metar, self.station_type, self.format.include_remarks);
+            if self.volcanic_ash != AshMode::Off {
+                let advisories = volcanic_ash::check(lat, lon, &alerts);
+                if self.volcanic_ash == AshMode::Inject && !advisories.is_empty() {
+                    metar = volcanic_ash::inject(&metar);
+                }
+            }
+            if self.marine_data {
+                if let Some(conditions) = marine::fetch(lat, lon) {
+                    metar = marine::add_remarks(&metar, &conditions);
+                }
+            }
+            return Ok(Synthesized { api, metar: apply_output_policy(&metar, self.policy), observed_at });
+        }
+
+        if providers.len() > 1 {
+            Err(format!("Failed to generate METAR with any provider. {}", failures.join(". ")))
+        } else {
+            Err(format!("Failed to generate METAR. {}", failures.join(". ")))
+        }
+    }
+
+    // The provider's report, or why it couldn't give one
+    fn synthesize_with(&self, api: ApiType, icao: &str, lat: f64, lon: f64) -> Result<ProviderReport, String> {
+        match api {
+            ApiType::Standard => {
+                metar_generator::generate_metar(icao, lat, lon, &self.api_key, &self.language, &self.units, &self.format)
+                    .map(|(metar, observed_at)| (metar, observed_at, Vec::new()))
+            },
+            ApiType::OneCall => {
+                let weather_data = one_call_metar::fetch_weather_data(lat, lon, &self.one_call_api_key, &self.one_call_exclude, &self.language)
+                    .map_err(|e| e.guidance())?;
+                let mut parsed = one_call_metar::parse_weather_data(weather_data)
+                    .ok_or_else(|| "The One Call response has no usable conditions".to_string())?;
+                if self.interpolate_temperature {
+                    one_call_metar::interpolate_temperatures(&mut parsed, Utc::now());
+                }
+                let metar = one_call_metar::generate_metar(icao, &parsed, &self.units, &self.format);
+                Ok((metar, parsed.current.observed_at, parsed.alerts))
+            },
+            ApiType::OpenMeteo => {
+                open_meteo::generate_metar(icao, lat, lon, &self.units, &self.format)
+                    .map(|(metar, observed_at)| (metar, observed_at, Vec::new()))
+                    .ok_or_else(|| "No usable data from Open-Meteo".to_string())
+            },
+        }
+    }
+}
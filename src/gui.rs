@@ -1,10 +1,52 @@
 use eframe::egui::{self, Color32, RichText, Rounding, Stroke, Vec2};
+use chrono::{DateTime, Timelike, Utc};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 
-use crate::config::{get_user_airports, save_user_airport, delete_user_airport, UserAirport};
-use crate::metar_generator;
+use crate::airport_db;
+use crate::bookmarks::{self, Bookmark, BookmarkKind};
+use crate::briefing::{self, Briefing, BriefingStation, ExportFormat};
+use crate::config::{
+    save_user_airport, delete_user_airport, UserAirport,
+    active_profile, is_valid_profile_name, list_profiles,
+    add_recent_icao, recent_icaos, set_icao_pinned, move_user_airport, save_user_airports, set_user_airport_name,
+};
+use crate::metar_decoder::{self, DecodedMetar};
+use crate::noaa_cache;
+use crate::notifications::{ErrorQueue, NotificationCenter, Severity};
+use crate::generation::{provider_order_from_config, ApiType, GenerationSettings};
 use crate::one_call_metar;
-use crate::input_handler;
+use crate::ops_profile::{self, OpsProfile};
+use crate::paths;
+use crate::save_worker::{self, queue_setting, ProfileSwitch};
+use crate::scheduler::{Priority, Scheduler};
+use crate::severe_weather::{self, SevereWeatherSettings};
+use crate::input_handler::{self, AlternateSources, KeyCheck, MetarSource, RealMetar};
+use crate::crash_report;
+use crate::disk_cache;
+use crate::disk_writer;
+use crate::gamepad::{self, ButtonPress};
+use crate::flight_category::{flight_category, FlightCategory};
+use crate::history::{self, HistoryEntry, Retention, UsageStats};
+use crate::storage::{self, ReportQuery};
+use crate::hotkey::{self, RegenerateHotkey};
+use crate::icao_region;
+use crate::coordinates::{parse_coordinate, parse_coordinate_pair, parse_map_location, Axis};
+use crate::metar_format::{self, FormatOptions, IdentifierPadding, OutputPolicy};
+use crate::msfs;
+use crate::space_weather::{self, SpaceWeather};
+use crate::speech;
+use crate::station_type::StationType;
+use crate::units::{self, MetarUnits, PressureUnit, TemperatureUnit, UnitPreset, VisibilityUnit, WindUnit};
+use crate::volcanic_ash::{self, AshMode};
+use crate::waypoints;
+use crate::xplane;
 
 // Retro color scheme
 const CYAN_GLOW: Color32 = Color32::from_rgb(0, 255, 255);
@@ -19,76 +61,641 @@ const GENERATE_BUTTON_COLOR: Color32 = Color32::from_rgb(0, 255, 0);
 const GENERATE_BUTTON_TEXT: Color32 = Color32::BLACK;
 
 #[derive(Default, PartialEq, Clone, Copy)]
-enum Units {
+enum InputMethod {
     #[default]
-    Metric,
-    Imperial,
+    None,
+    Icao,
+    LatLon,
+    Location,
+    Bookmark,
+}
+
+impl InputMethod {
+    const ALL: [InputMethod; 5] =
+        [InputMethod::None, InputMethod::Icao, InputMethod::LatLon, InputMethod::Location, InputMethod::Bookmark];
+
+    fn key(self) -> &'static str {
+        match self {
+            InputMethod::None => "none",
+            InputMethod::Icao => "icao",
+            InputMethod::LatLon => "latlon",
+            InputMethod::Location => "location",
+            InputMethod::Bookmark => "bookmark",
+        }
+    }
+}
+
+// Shown in the About tab
+const LICENSE_TEXT: &str = include_str!("../LICENSE.md");
+
+// Third-party data credited in the About tab as (source, what it provides, link)
+const DATA_CREDITS: [(&str, &str, &str); 5] = [
+    ("OpenWeather", "Current weather, forecasts and geocoding", "https://openweathermap.org"),
+    ("NOAA Aviation Weather Center", "Real METARs and airport locations", "https://aviationweather.gov"),
+    ("CheckWX", "Real METARs and stations when NOAA has none (with a key)", "https://www.checkwxapi.com"),
+    ("AVWX", "Real METARs and stations when NOAA has none (with a key)", "https://avwx.rest"),
+    ("OurAirports", "Bundled airport database (public domain)", "https://ourairports.com"),
+];
+
+// OpenWeather pages linked from the API key setup guide
+const OWM_SIGN_UP_URL: &str = "https://home.openweathermap.org/users/sign_up";
+const OWM_API_KEYS_URL: &str = "https://home.openweathermap.org/api_keys";
+const OWM_ONE_CALL_URL: &str = "https://openweathermap.org/api/one-call-3";
+
+// Keys of the alternate real-METAR sources in the Configuration tab, as
+// (label, config field, hint)
+const ALTERNATE_SOURCE_KEY_FIELDS: [(&str, &str, &str); 2] = [
+    ("CheckWX API Key:", "checkwx_api_key", "Optional, free from checkwxapi.com"),
+    ("AVWX API Key:", "avwx_api_key", "Optional, free from account.avwx.rest"),
+];
+
+// A new OpenWeather key is rejected until it activates, which can take a
+// couple of hours, so it is checked once a minute for up to three hours
+const KEY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const KEY_CHECK_ATTEMPTS: u32 = 180;
+
+// Cache size limits offered in the Configuration tab, in megabytes
+const CACHE_SIZE_LIMITS: [u64; 5] = [25, 50, 100, 250, 500];
+// History retention choices in the Configuration tab; None keeps everything
+const HISTORY_ENTRY_LIMITS: [Option<usize>; 5] = [Some(1_000), Some(10_000), Some(50_000), Some(250_000), None];
+const HISTORY_DAY_LIMITS: [Option<u32>; 5] = [Some(30), Some(90), Some(365), Some(3 * 365), None];
+// Report History search periods in days; None searches everything
+const REPORT_SEARCH_PERIODS: [Option<u32>; 5] = [Some(1), Some(7), Some(30), Some(365), None];
+// Most reports listed by a Report History search
+const REPORT_SEARCH_LIMIT: usize = 200;
+// Decoded reports kept between frames before the cache starts over
+const DECODE_CACHE_SIZE: usize = 64;
+
+// Background refreshes run on a few workers, spaced out per provider. The
+// OpenWeather free tier allows 60 calls a minute, Open-Meteo 600.
+const SCHEDULER_WORKERS: usize = 3;
+const PROVIDER_RATE_LIMITS: [(&str, Duration); 6] = [
+    (input_handler::NOAA, Duration::from_millis(500)),
+    (input_handler::CHECKWX, Duration::from_secs(1)),
+    (input_handler::AVWX, Duration::from_secs(1)),
+    (input_handler::OWM_STANDARD, Duration::from_secs(1)),
+    (input_handler::OWM_ONE_CALL, Duration::from_secs(1)),
+    (input_handler::OPEN_METEO, Duration::from_millis(200)),
+];
+
+// Standard observation minutes offered in the Configuration tab. Other
+// minutes can be set as "observation_minutes" in the config file.
+const OBSERVATION_TIME_PRESETS: [(&str, &[u32]); 6] = [
+    ("Exact time", &[]),
+    ("Hourly at :00", &[0]),
+    ("Hourly at :50", &[50]),
+    ("Hourly at :53 (US ASOS)", &[53]),
+    ("Half-hourly at :20/:50", &[20, 50]),
+    ("Half-hourly at :00/:30", &[0, 30]),
+];
+
+// Refresh intervals offered for a station session, in minutes
+const REFRESH_INTERVALS: [u64; 4] = [10, 15, 30, 60];
+
+// Closing line of the severe weather banner
+const SEVERE_WEATHER_ADVICE: &str =
+    "Review before loading the flight: expect severe turbulence, windshear and sudden loss of visibility, and consider delaying or planning an alternate";
+
+// Observation data older than this when a report is generated is flagged below it
+const STALE_DATA_MINUTES: i64 = 30;
+
+// A station kept open in the output panel. Each one keeps its own report,
+// provider and auto-refresh settings.
+struct StationSession {
+    icao: String,
+    // Station name of a saved airport, shown above the METAR but never part of it
+    name: Option<String>,
+    coordinates: Option<(f64, f64)>,
+    // None when the report is an existing METAR rather than a synthesized one
+    api: Option<ApiType>,
+    // Where an existing METAR came from; None for a synthesized one
+    metar_source: Option<MetarSource>,
+    input_method: InputMethod,
+    metar: String,
+    // When the report's conditions were observed, if known
+    observed_at: Option<DateTime<Utc>>,
+    auto_refresh: bool,
+    refresh_minutes: u64,
+    last_generated: Instant,
+    // Set when the report no longer matches the session settings and should be regenerated
+    stale: bool,
+    // Provider that produced the current report when `api` failed and failover took over
+    fallback: Option<ApiType>,
+}
+
+impl StationSession {
+    fn refresh_due(&self, hold: MetarHold) -> bool {
+        self.stale
+            || self.auto_refresh
+                && self.last_generated.elapsed() >= Duration::from_secs(self.refresh_minutes * 60)
+                && self.held_until(hold).is_none_or(|until| Utc::now() >= until)
+    }
+
+    // Wall-clock time the current report was generated
+    fn generated_at(&self) -> DateTime<Utc> {
+        Utc::now() - chrono::Duration::from_std(self.last_generated.elapsed()).unwrap_or_default()
+    }
+
+    // Issuance time the current report is held until, if holding
+    fn held_until(&self, hold: MetarHold) -> Option<DateTime<Utc>> {
+        hold.next_issuance(self.generated_at())
+    }
+
+    // Age of the observation data, counting on while the report is on screen,
+    // e.g. "observation data age: 4 min (OWM), generated 12:46Z", and whether it
+    // is stale
+    fn freshness(&self) -> (String, bool) {
+        let generated = self.generated_at();
+        let source = match self.fallback.or(self.api) {
+            Some(ApiType::Standard | ApiType::OneCall) => "OWM",
+            Some(ApiType::OpenMeteo) => "Open-Meteo",
+            None => self.metar_source.map_or(input_handler::NOAA, MetarSource::label),
+        };
+        let age = self.observed_at.map(|observed| (Utc::now() - observed).num_minutes().max(0));
+        let text = format!(
+            "observation data age: {} ({}), generated {}Z",
+            age.map_or("unknown".to_string(), |age| format!("{} min", age)),
+            source,
+            generated.format("%H:%M")
+        );
+        (text, age.is_some_and(|age| age > STALE_DATA_MINUTES))
+    }
 }
 
+// Holds each station's report until the next routine issuance time instead of
+// changing it with every auto-refresh, so ATIS letters and briefings stay
+// consistent during a session
 #[derive(Default, PartialEq, Clone, Copy)]
-enum InputMethod {
+enum MetarHold {
     #[default]
-    None,
-    Icao,
+    Off,
+    HalfHour,
+    Hour,
+}
+
+impl MetarHold {
+    const ALL: [MetarHold; 3] = [MetarHold::Off, MetarHold::HalfHour, MetarHold::Hour];
+
+    fn label(self) -> &'static str {
+        match self {
+            MetarHold::Off => "Off",
+            MetarHold::HalfHour => "Until the next half hour",
+            MetarHold::Hour => "Until the next hour",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            MetarHold::Off => "off",
+            MetarHold::HalfHour => "half_hour",
+            MetarHold::Hour => "hour",
+        }
+    }
+
+    fn from_config(config: &Value) -> Self {
+        let key = config.get("metar_hold").and_then(|hold| hold.as_str()).unwrap_or_default();
+        Self::ALL.into_iter().find(|hold| hold.key() == key).unwrap_or_default()
+    }
+
+    // First issuance boundary after `time`
+    fn next_issuance(self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let step = match self {
+            MetarHold::Off => return None,
+            MetarHold::HalfHour => 30,
+            MetarHold::Hour => 60,
+        };
+        let minutes_past = (time.minute() % step) as i64;
+        let boundary = time.with_second(0)?.with_nanosecond(0)? - chrono::Duration::minutes(minutes_past);
+        Some(boundary + chrono::Duration::minutes(step as i64))
+    }
+}
+
+// Which button started a background request, so that button can show its progress
+#[derive(PartialEq, Clone)]
+enum RequestKind {
+    IcaoLookup,
     LatLon,
     Location,
+    SavedAirport(String),
+    Bookmark(String),
+    Synthesize,
+    Route,
+    Prefetch,
+    SpaceWeather,
+}
+
+// A station's report, ready to show in its session
+struct Report {
+    icao: String,
+    coordinates: Option<(f64, f64)>,
+    // None for an existing METAR
+    api: Option<ApiType>,
+    fallback: Option<ApiType>,
+    metar_source: Option<MetarSource>,
+    input_method: InputMethod,
+    metar: String,
+    // When the conditions were observed: the provider's `dt`, or an existing report's time group
+    observed_at: Option<DateTime<Utc>>,
+}
+
+impl Report {
+    fn real(existing: RealMetar) -> Report {
+        let observed_at = metar_decoder::decode_metar(&existing.raw).observed_at(Utc::now());
+        Report {
+            icao: existing.icao,
+            coordinates: existing.coordinates,
+            api: None,
+            fallback: None,
+            metar_source: Some(existing.source),
+            input_method: InputMethod::Icao,
+            metar: existing.raw,
+            observed_at,
+        }
+    }
+}
+
+// Result of a background request, applied to the app state once it arrives
+enum RequestOutcome {
+    ExistingMetar(RealMetar),
+    Report(Report),
+    Refreshed {
+        icao: String,
+        fallback: Option<ApiType>,
+        metar_source: Option<MetarSource>,
+        result: Result<(String, Option<DateTime<Utc>>), String>,
+    },
+    // One Report or Failed per route station, in route order
+    Route(Vec<(String, RequestOutcome)>),
+    Prefetched {
+        downloaded: usize,
+        cached: usize,
+    },
+    SpaceWeather(SpaceWeather),
+    Failed(String),
+}
+
+struct PendingRequest {
+    kind: RequestKind,
+    started: Instant,
+    receiver: mpsc::Receiver<RequestOutcome>,
+}
+
+// Whether a station's new report has severe weather that its previous one didn't
+fn newly_severe(previous: Option<&str>, metar: &str) -> bool {
+    let severe = |metar: &str| !severe_weather::phenomena(&metar_decoder::decode_metar(metar)).is_empty();
+    severe(metar) && !previous.is_some_and(severe)
 }
 
+// Decodes a report, reusing the result from earlier frames while its text is unchanged
+fn decode_cached(cache: &mut HashMap<String, Rc<DecodedMetar>>, metar: &str) -> Rc<DecodedMetar> {
+    if let Some(decoded) = cache.get(metar) {
+        return decoded.clone();
+    }
+    if cache.len() >= DECODE_CACHE_SIZE {
+        cache.clear();
+    }
+    let decoded = Rc::new(metar_decoder::decode_metar(metar));
+    cache.insert(metar.to_string(), decoded.clone());
+    decoded
+}
+
+// Runs work on a background thread and repaints once its result is ready, so
+// the frame never waits on the disk
+fn spawn_background<T: Send + 'static>(ctx: &egui::Context, work: impl FnOnce() -> T + Send + 'static) -> mpsc::Receiver<T> {
+    let (sender, receiver) = mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let _ = sender.send(work());
+        ctx.request_repaint();
+    });
+    receiver
+}
+
+// A value read from disk on a background thread, kept until it is reloaded
+#[derive(Default)]
+enum Loading<T> {
+    // Not read yet, or to be read again
+    #[default]
+    Stale,
+    Pending(mpsc::Receiver<T>),
+    Ready(T),
+}
+
+impl<T: Send + 'static> Loading<T> {
+    // Starts reading the value, replacing what was read before
+    fn start(&mut self, ctx: &egui::Context, load: impl FnOnce() -> T + Send + 'static) {
+        *self = Loading::Pending(spawn_background(ctx, load));
+    }
+
+    // The value once read, starting the read if it hasn't been
+    fn get(&mut self, ctx: &egui::Context, load: impl FnOnce() -> T + Send + 'static) -> Option<&T> {
+        if let Loading::Stale = self {
+            self.start(ctx, load);
+        }
+        self.poll()
+    }
+
+    // The value once read, without starting a read
+    fn poll(&mut self) -> Option<&T> {
+        if let Loading::Pending(receiver) = self {
+            if let Ok(value) = receiver.try_recv() {
+                *self = Loading::Ready(value);
+            }
+        }
+        match self {
+            Loading::Ready(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn is_pending(&self) -> bool {
+        matches!(self, Loading::Pending(_))
+    }
+
+    // Reads the value again the next time it is asked for
+    fn reload(&mut self) {
+        *self = Loading::Stale;
+    }
+}
+
+// Synthesizes a report for a new session
+fn report(settings: &GenerationSettings, icao: String, lat: f64, lon: f64, input_method: InputMethod) -> RequestOutcome {
+    match settings.synthesize(&icao, lat, lon) {
+        Ok(synthesized) => RequestOutcome::Report(Report {
+            icao,
+            coordinates: Some((lat, lon)),
+            api: Some(settings.api),
+            fallback: (synthesized.api != settings.api).then_some(synthesized.api),
+            metar_source: None,
+            input_method,
+            metar: synthesized.metar,
+            observed_at: synthesized.observed_at,
+        }),
+        Err(e) => RequestOutcome::Failed(e),
+    }
+}
+
+// A route station's report: the real METAR if there is one, otherwise a
+// synthesized one for the airport's position
+fn route_report(settings: &GenerationSettings, icao: String) -> RequestOutcome {
+    if let Some(existing) = noaa_cache::cached_metar(&icao)
+        .or_else(|| input_handler::poll_metar(&icao, &settings.alternate_sources))
+    {
+        return RequestOutcome::Report(Report { icao, ..Report::real(existing) });
+    }
+    match input_handler::resolve_icao_to_lat_lon(&icao, &settings.alternate_sources) {
+        Some((lat, lon)) => report(settings, icao, lat, lon, InputMethod::Icao),
+        None => RequestOutcome::Failed(format!("Could not resolve ICAO code: {}", icao)),
+    }
+}
+
+// Station identifiers in a route such as "KSEA KPDX, KEUG" or "KSEA-KPDX",
+// uppercased and without repeats. DCT between stations is skipped.
+fn parse_route(text: &str) -> Vec<String> {
+    let mut stations: Vec<String> = Vec::new();
+    for station in text.split(|c: char| c.is_whitespace() || c == ',' || c == '-').map(str::to_uppercase) {
+        if !station.is_empty() && station != "DCT" && !stations.contains(&station) {
+            stations.push(station);
+        }
+    }
+    stations
+}
+
+// A station of the last route generated, with the error if it failed. Its
+// report is the one in the station's session.
+struct RouteStation {
+    icao: String,
+    error: Option<String>,
+}
+
+#[derive(Default)]
 pub struct MetGenApp {
     input_icao: String,
     input_lat: String,
     input_lon: String,
     input_location: String,
-    generated_metar: String,
-    error_message: Option<String>,
-    success_message: Option<String>,
+    // Airport search text, with the ICAO codes and labels it matched, searched
+    // again only when the text changes
+    airport_search: String,
+    airport_search_results: Vec<(String, String)>,
+    // Stations typed into Flight Route, and the result of the last route generated
+    route_input: String,
+    route: Vec<RouteStation>,
+    // Saved airports and bookmarks of the active profile, as last read back by
+    // the save worker
+    saved_airports: Rc<Vec<UserAirport>>,
+    saved_bookmarks: Rc<Vec<Bookmark>>,
+    // Reports decoded for the station tabs, widget, summary and Parse tab
+    decoded_reports: HashMap<String, Rc<DecodedMetar>>,
+    sessions: Vec<StationSession>,
+    active_session: usize,
+    // Error and success banners, and the history shown from the header
+    notifications: NotificationCenter,
+    show_notifications: bool,
+    // Failed background refreshes, coalesced per station
+    refresh_errors: ErrorQueue,
     config: Option<Value>,
     selected_api: ApiType,
     selected_tab: Tab,
-    selected_units: Units,
-    existing_metar: Option<String>,
-    last_input_method: InputMethod,
-    saved_lat: f64,
-    saved_lon: f64,
-    saved_icao: String,
+    // Tab opened at startup
+    default_tab: Tab,
+    // ICAO typed into the header to generate from any tab
+    quick_icao: String,
+    unit_preset: UnitPreset,
+    metar_units: MetarUnits,
+    // Also show each report converted to the other common unit convention
+    dual_units: bool,
+    output_policy: OutputPolicy,
+    identifier_padding: IdentifierPadding,
+    metar_hold: MetarHold,
+    // Minutes past the hour reports are stamped with; empty for the exact time
+    observation_minutes: Vec<u32>,
+    existing_metar: Option<RealMetar>,
+    pending_request: Option<PendingRequest>,
+    egui_ctx: egui::Context,
+    locked: bool,
+    recent_icaos: Vec<String>,
+    pinned_icaos: Vec<String>,
+    profiles: Vec<String>,
+    new_profile_name: String,
+    show_qr_code: bool,
+    // QR code texture and the METAR it encodes, rebuilt when the METAR changes
+    qr_code: Option<(String, egui::TextureHandle)>,
+    speech_rate: f64,
+    // Speech engine process of a readback in progress
+    speech: Option<Child>,
+    // Reports from earlier crashes, offered for submission when opted in
+    crash_reports: Vec<PathBuf>,
+    // Computed from the history when the Diagnostics tab is opened
+    usage_stats: Loading<UsageStats>,
+    // Report History search on the Diagnostics tab
+    report_search_icao: String,
+    report_search_days: Option<u32>,
+    report_search_results: Loading<Vec<HistoryEntry>>,
+    // Station shown in the always-on-top widget window, if it is open
+    widget_station: Option<String>,
+    regenerate_hotkey: Option<RegenerateHotkey>,
+    hotkey_input: String,
+    // X-Plane folder being edited in the Configuration tab
+    xplane_path_input: String,
+    gamepad_presses: Option<mpsc::Receiver<ButtonPress>>,
+    gamepad_binding: Option<ButtonPress>,
+    // Set while waiting for the button press to bind
+    binding_gamepad: bool,
+    // Index into noaa_cache::REGIONS, or its length for a custom area
+    prefetch_region: usize,
+    prefetch_area: String,
+    // Parse tab inputs
+    parse_input: String,
+    runway: String,
+    minimum_ceiling: String,
+    minimum_visibility: String,
+    ops_profile: OpsProfile,
+    // Last space weather check from the Diagnostics tab
+    space_weather: Option<SpaceWeather>,
+    // Field elevation in feet for the rotor density altitude
+    field_elevation: String,
+    conversions: Conversions,
+    // Station names being edited in the Saved Airports tab, by ICAO
+    airport_name_edits: HashMap<String, String>,
+    // New bookmark form on the Saved Airports tab
+    bookmark_name: String,
+    bookmark_kind: BookmarkKind,
+    bookmark_coordinates: String,
+    bookmark_identifier: String,
+    // Set when the open stations changed and the briefing should be saved
+    briefing_dirty: bool,
+    // Runs station refreshes in the background, separately from pending_request
+    scheduler: Option<Scheduler<RequestOutcome>>,
+    cache_max_mb: u64,
+    // Cache size in bytes, measured when the Configuration tab is shown
+    cache_usage: Loading<u64>,
+    history_retention: Retention,
+    // Size of the history file in bytes, read when the Configuration tab needs it
+    history_size: Loading<u64>,
+    // Prunes and cache clears in progress, each with its outcome message
+    disk_tasks: Vec<mpsc::Receiver<Result<String, String>>>,
+    provider_failover: bool,
+    provider_order: Vec<ApiType>,
+    show_key_helper: bool,
+    key_helper_input: String,
+    key_verification: Option<KeyVerification>,
 }
 
-impl Default for MetGenApp {
-    fn default() -> Self {
+// Background checks of a key saved from the setup guide, until OpenWeather
+// accepts it. Dropping it stops the checks.
+struct KeyVerification {
+    one_call: bool,
+    attempts: u32,
+    last_check: Option<KeyCheck>,
+    receiver: mpsc::Receiver<KeyCheck>,
+    stop: Arc<AtomicBool>,
+}
+
+impl KeyVerification {
+    fn start(api_key: String, one_call: bool, ctx: egui::Context) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        std::thread::spawn(move || {
+            for _ in 0..KEY_CHECK_ATTEMPTS {
+                let check = input_handler::check_owm_key(&api_key, one_call);
+                let active = matches!(check, KeyCheck::Active);
+                if sender.send(check).is_err() || active {
+                    ctx.request_repaint();
+                    return;
+                }
+                ctx.request_repaint();
+
+                let next_check = Instant::now() + KEY_CHECK_INTERVAL;
+                while Instant::now() < next_check {
+                    if stopped.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    std::thread::sleep(Duration::from_secs(1));
+                }
+            }
+        });
+
         Self {
-            input_icao: String::new(),
-            input_lat: String::new(),
-            input_lon: String::new(),
-            input_location: String::new(),
-            generated_metar: String::new(),
-            error_message: None,
-            success_message: None,
-            config: None,
-            selected_api: ApiType::default(),
-            selected_tab: Tab::default(),
-            selected_units: Units::default(),
-            existing_metar: None,
-            last_input_method: InputMethod::None,
-            saved_lat: 0.0,
-            saved_lon: 0.0,
-            saved_icao: String::new(),
-        }
-    }
-}
-
-#[derive(Default, PartialEq, Clone)]
+            one_call,
+            attempts: 0,
+            last_check: None,
+            receiver,
+            stop,
+        }
+    }
+
+    fn key_name(&self) -> &'static str {
+        if self.one_call { "One Call API key" } else { "Standard API key" }
+    }
+}
+
+impl Drop for KeyVerification {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default, PartialEq, Clone, Copy)]
 pub enum Tab {
     #[default]
     GenerateMetar,
     SavedAirports,
+    Parse,
     Configuration,
+    Diagnostics,
+    About,
 }
 
-#[derive(Default, PartialEq, Clone, Copy)]
-enum ApiType {
-    #[default]
-    Standard,
-    OneCall,
+impl Tab {
+    const ALL: [Tab; 6] =
+        [Tab::GenerateMetar, Tab::SavedAirports, Tab::Parse, Tab::Configuration, Tab::Diagnostics, Tab::About];
+
+    fn label(self) -> &'static str {
+        match self {
+            Tab::GenerateMetar => "Generate METAR",
+            Tab::SavedAirports => "Saved Airports",
+            Tab::Parse => "Parse",
+            Tab::Configuration => "Configuration",
+            Tab::Diagnostics => "Diagnostics",
+            Tab::About => "About",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Tab::GenerateMetar => "generate",
+            Tab::SavedAirports => "saved_airports",
+            Tab::Parse => "parse",
+            Tab::Configuration => "configuration",
+            Tab::Diagnostics => "diagnostics",
+            Tab::About => "about",
+        }
+    }
+
+    // The tab opened at startup, from "default_tab" in the config
+    fn from_config(config: &Value) -> Tab {
+        let key = config.get("default_tab").and_then(|t| t.as_str()).unwrap_or_default();
+        Tab::ALL.into_iter().find(|tab| tab.key() == key).unwrap_or_default()
+    }
+}
+
+// Adds a report to the history without waiting for the write
+fn record_history(icao: &str, provider: &str, metar: &str) {
+    let (icao, provider, metar) = (icao.to_string(), provider.to_string(), metar.to_string());
+    disk_writer::write(move || history::record(&icao, &provider, &metar));
+}
+
+// Provider name recorded in the history; no API means an existing METAR from
+// its source
+fn provider_name(api: Option<ApiType>, metar_source: Option<MetarSource>) -> &'static str {
+    match api {
+        Some(ApiType::Standard) => "OpenWeatherMap Standard",
+        Some(ApiType::OneCall) => "OpenWeatherMap One Call",
+        Some(ApiType::OpenMeteo) => "Open-Meteo",
+        None => metar_source.map_or(input_handler::NOAA, MetarSource::label),
+    }
 }
 
 impl MetGenApp {
@@ -112,34 +719,451 @@ impl MetGenApp {
         style.visuals.panel_fill = PANEL_BACKGROUND;
         cc.egui_ctx.set_style(style);
         
-        // Initialize selected_units from config
-        let selected_units = if let Some(units) = config.get("units").and_then(|u| u.as_str()) {
-            match units {
-                "imperial" => Units::Imperial,
-                _ => Units::Metric,
-            }
-        } else {
-            Units::default()
-        };
+        // Initialize units from config
+        let (unit_preset, metar_units) = units::units_from_config(&config);
+        let output_policy = OutputPolicy::from_config(&config);
+        let identifier_padding = IdentifierPadding::from_config(&config);
+        let ops_profile = OpsProfile::from_config(&config);
+        let dual_units = config["dual_units"].as_bool().unwrap_or(false);
+        let metar_hold = MetarHold::from_config(&config);
+        let observation_minutes = metar_format::observation_minutes_from_config(&config);
+        let speech_rate = speech_rate_from_config(&config);
+        let xplane_path_input = xplane::xplane_path_from_config(&config)
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
 
         // Set initial tab based on first run status
+        let default_tab = Tab::from_config(&config);
         let selected_tab = if config.get("is_first_run").and_then(|v| v.as_bool()).unwrap_or(false) {
             Tab::Configuration
         } else {
-            Tab::default()
+            default_tab
+        };
+
+        let locked = config.get("locked").and_then(|v| v.as_bool()).unwrap_or(false);
+        let (recent_icaos, pinned_icaos) = recent_icaos(&config);
+        let crash_reports = if config["crash_reports_opt_in"].as_bool().unwrap_or(false) {
+            crash_report::pending_reports()
+        } else {
+            Vec::new()
         };
         
-        Self {
+        let gamepad_binding = ButtonPress::from_config(&config);
+        let provider_failover = config["provider_failover"].as_bool().unwrap_or(false);
+        let provider_order = provider_order_from_config(&config);
+        let cache_max_mb = disk_cache::max_size_from_config(&config);
+        disk_cache::set_max_size_mb(cache_max_mb);
+        let history_retention = Retention::from_config(&config);
+        history::set_retention(history_retention);
+
+        let mut app = Self {
             config: Some(config),
-            selected_units,
+            unit_preset,
+            metar_units,
+            output_policy,
+            identifier_padding,
+            ops_profile,
+            dual_units,
+            metar_hold,
+            observation_minutes,
+            speech_rate,
             selected_tab,
+            default_tab,
+            locked,
+            egui_ctx: cc.egui_ctx.clone(),
+            recent_icaos,
+            pinned_icaos,
+            profiles: list_profiles(),
+            crash_reports,
+            gamepad_binding,
+            provider_failover,
+            provider_order,
+            cache_max_mb,
+            history_retention,
+            xplane_path_input,
             ..Default::default()
+        };
+        app.update_hotkey();
+        let ctx = cc.egui_ctx.clone();
+        save_worker::set_wake(move || ctx.request_repaint());
+        save_worker::watch_config();
+        app.reload_saved_places();
+        let ctx = cc.egui_ctx.clone();
+        app.gamepad_presses = gamepad::listen(move || ctx.request_repaint());
+        app.restore_briefing();
+        let ctx = cc.egui_ctx.clone();
+        app.scheduler = Some(Scheduler::new(SCHEDULER_WORKERS, &PROVIDER_RATE_LIMITS, move || ctx.request_repaint()));
+        app
+    }
+
+    // The save worker loads the profile after writing any queued changes;
+    // watch_config picks up the result
+    fn switch_profile(&mut self, name: &str) {
+        save_worker::switch_profile(name);
+    }
+
+    // Reload everything that comes from the config file after a profile change
+    fn finish_profile_switch(&mut self, switched: ProfileSwitch) {
+        match switched {
+            ProfileSwitch::Loaded { name, config, profiles } => {
+                self.apply_config(config);
+                self.profiles = profiles;
+                self.clear_input_fields();
+                self.clear_output_display();
+                self.notifications.success(format!("Switched to profile {}", name));
+            }
+            ProfileSwitch::Failed(name) => self.notifications.error(format!("Failed to load profile {}", name)),
+        }
+    }
+
+    // Takes over every setting that comes from the config file
+    fn apply_config(&mut self, mut config: Value) {
+        // A locked session stays locked regardless of the profile's own setting
+        self.locked = self.locked || config["locked"].as_bool().unwrap_or(false);
+        config["locked"] = Value::Bool(self.locked);
+
+        (self.unit_preset, self.metar_units) = units::units_from_config(&config);
+        self.output_policy = OutputPolicy::from_config(&config);
+        self.identifier_padding = IdentifierPadding::from_config(&config);
+        self.ops_profile = OpsProfile::from_config(&config);
+        self.default_tab = Tab::from_config(&config);
+        self.dual_units = config["dual_units"].as_bool().unwrap_or(false);
+        self.metar_hold = MetarHold::from_config(&config);
+        self.observation_minutes = metar_format::observation_minutes_from_config(&config);
+        self.speech_rate = speech_rate_from_config(&config);
+        self.gamepad_binding = ButtonPress::from_config(&config);
+        self.provider_failover = config["provider_failover"].as_bool().unwrap_or(false);
+        self.provider_order = provider_order_from_config(&config);
+        // Both start background work, so only when the setting changed
+        let cache_max_mb = disk_cache::max_size_from_config(&config);
+        if cache_max_mb != self.cache_max_mb {
+            self.cache_max_mb = cache_max_mb;
+            disk_cache::set_max_size_mb(cache_max_mb);
+            self.cache_usage.reload();
+        }
+        let history_retention = Retention::from_config(&config);
+        if history_retention != self.history_retention {
+            self.history_retention = history_retention;
+            history::set_retention(history_retention);
+            self.history_size.reload();
+        }
+        (self.recent_icaos, self.pinned_icaos) = recent_icaos(&config);
+        self.reload_saved_places();
+        self.xplane_path_input = xplane::xplane_path_from_config(&config)
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        self.config = Some(config);
+        self.update_hotkey();
+    }
+
+    // Registers the regenerate hotkey from the config, replacing any previous one
+    fn update_hotkey(&mut self) {
+        let text = self.config.as_ref()
+            .and_then(|c| c["regenerate_hotkey"].as_str())
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        self.hotkey_input = text.clone();
+        if self.regenerate_hotkey.as_ref().map(|hotkey| hotkey.text.as_str()).unwrap_or_default() == text {
+            return;
+        }
+
+        self.regenerate_hotkey = None;
+        if text.is_empty() {
+            return;
+        }
+        let ctx = self.egui_ctx.clone();
+        match RegenerateHotkey::register(&text, move || ctx.request_repaint()) {
+            Ok(hotkey) => self.regenerate_hotkey = Some(hotkey),
+            Err(e) => self.notifications.error(e),
+        }
+    }
+
+    // Regenerates the active station when the global hotkey was pressed
+    fn poll_hotkey(&mut self) {
+        if hotkey::take_pressed() {
+            self.regenerate_active_session();
+        }
+    }
+
+    // Binds the next button press while binding, otherwise regenerates the
+    // active station when the bound button is pressed
+    fn poll_gamepad(&mut self) {
+        let Some(presses) = &self.gamepad_presses else { return };
+        let presses: Vec<ButtonPress> = presses.try_iter().collect();
+        for press in presses {
+            if self.binding_gamepad {
+                self.binding_gamepad = false;
+                queue_setting("gamepad_binding", press.to_config());
+                self.notifications.success(format!("Bound button {} on {}", press.button, press.device));
+                if let Some(config) = &mut self.config {
+                    config["gamepad_binding"] = press.to_config();
+                }
+                self.gamepad_binding = Some(press);
+            } else if self.gamepad_binding.as_ref() == Some(&press) {
+                self.regenerate_active_session();
+            }
+        }
+    }
+
+    // Marks the active station stale so the auto-refresh regenerates it as
+    // soon as no other request is running
+    fn regenerate_active_session(&mut self) {
+        if let Some(session) = self.sessions.get_mut(self.active_session) {
+            session.stale = true;
+        }
+    }
+
+    // Takes over profile switches and edits made to the config file outside
+    // the app, both of which the save worker loads
+    fn watch_config(&mut self) {
+        if let Some(switched) = save_worker::take_profile_switch() {
+            self.finish_profile_switch(switched);
+        }
+        let Some(mut config) = save_worker::take_reloaded_config() else { return };
+
+        // The first-run welcome stays up until dismissed
+        let first_run = self.config.as_ref().and_then(|c| c["is_first_run"].as_bool()).unwrap_or(false);
+        config["is_first_run"] = Value::Bool(first_run);
+        self.apply_config(config);
+    }
+}
+
+// The other common unit convention to the one in use: US units for metric
+// reports, ICAO metric units otherwise
+fn alternate_units(units: &MetarUnits) -> (&'static str, MetarUnits) {
+    let preset = if units.visibility == VisibilityUnit::Meters && units.pressure == PressureUnit::Hectopascals {
+        UnitPreset::UnitedStates
+    } else {
+        UnitPreset::Icao
+    };
+    (preset.label(), preset.units().unwrap_or_default())
+}
+
+// Shows a METAR in a selectable monospace box, wrapped to the available width.
+// The copy button always copies the unwrapped single-line report.
+fn draw_metar_text(ui: &mut egui::Ui, id: &str, metar: &str) {
+    let font_id = egui::FontId::monospace(16.0);
+    let (char_width, row_height) = ui.fonts(|fonts| (fonts.glyph_width(&font_id, 'W'), fonts.row_height(&font_id)));
+    let max_chars = ((ui.available_width() - 60.0) / char_width.max(1.0)) as usize;
+    let wrapped = wrap_metar(metar, max_chars.max(20));
+
+    ui.horizontal(|ui| {
+        egui::ScrollArea::vertical()
+            .id_source(id)
+            .max_height(row_height * 4.0)
+            .show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut wrapped.as_str())
+                    .font(font_id.clone())
+                    .text_color(TEXT_COLOR)
+                    .frame(false)
+                    .desired_rows(1)
+                    .desired_width(char_width * max_chars.max(20) as f32));
+            });
+        if ui.button("Copy").on_hover_text("Copy the METAR as a single line").clicked() {
+            ui.output_mut(|o| o.copied_text = metar.to_string());
+        }
+    });
+}
+
+// Renders text as a black-on-white QR code, one pixel per module with a
+// four-module quiet zone. Returns None if the text is too long to encode.
+fn qr_code_image(text: &str) -> Option<egui::ColorImage> {
+    const QUIET_ZONE: usize = 4;
+    let code = qrcode::QrCode::new(text.as_bytes()).ok()?;
+    let width = code.width();
+    let size = width + QUIET_ZONE * 2;
+    let mut image = egui::ColorImage::new([size, size], Color32::WHITE);
+    for (index, color) in code.to_colors().into_iter().enumerate() {
+        if color == qrcode::Color::Dark {
+            let (x, y) = (index % width + QUIET_ZONE, index / width + QUIET_ZONE);
+            image.pixels[y * size + x] = Color32::BLACK;
+        }
+    }
+    Some(image)
+}
+
+// Breaks a METAR between groups so no line exceeds max_chars where possible.
+// Continuation lines are indented by five spaces, as on teletype-formatted reports.
+fn wrap_metar(metar: &str, max_chars: usize) -> String {
+    const CONTINUATION_INDENT: &str = "     ";
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for group in metar.split_whitespace() {
+        if current.is_empty() {
+            current.push_str(group);
+        } else if current.len() + 1 + group.len() <= max_chars {
+            current.push(' ');
+            current.push_str(group);
+        } else {
+            lines.push(std::mem::take(&mut current));
+            current.push_str(CONTINUATION_INDENT);
+            current.push_str(group);
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines.join("\n")
+}
+
+// Values in the unit conversion calculator, each kept in one unit and shown
+// in both. The conversions are the ones the METAR formatters use.
+struct Conversions {
+    pressure_hpa: f64,
+    temperature_c: f64,
+    wind_mps: f64,
+    visibility_m: f64,
+    height_ft: f64,
+}
+
+impl Default for Conversions {
+    fn default() -> Self {
+        Self {
+            pressure_hpa: 1013.25,
+            temperature_c: 15.0,
+            wind_mps: units::knots_to_mps(10.0),
+            visibility_m: units::statute_miles_to_meters(1.0),
+            height_ft: 1000.0,
+        }
+    }
+}
+
+impl Conversions {
+    fn draw(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("unit_conversions_grid").num_columns(4).spacing([8.0, 4.0]).show(ui, |ui| {
+            conversion_row(ui, &mut self.pressure_hpa, ("hPa", 0.1, 1), ("inHg", 0.01, 2), units::hpa_to_inhg, units::inhg_to_hpa);
+            conversion_row(ui, &mut self.temperature_c, ("°C", 0.5, 1), ("°F", 1.0, 1), units::celsius_to_fahrenheit, units::fahrenheit_to_celsius);
+            conversion_row(ui, &mut self.wind_mps, ("m/s", 0.1, 1), ("kt", 1.0, 1), units::mps_to_knots, units::knots_to_mps);
+            conversion_row(ui, &mut self.visibility_m, ("m", 100.0, 0), ("SM", 0.25, 2), units::meters_to_statute_miles, units::statute_miles_to_meters);
+            conversion_row(ui, &mut self.height_ft, ("ft", 100.0, 0), ("m", 10.0, 1), units::feet_to_meters, units::meters_to_feet);
+        });
+    }
+}
+
+// One calculator row: the stored value and its conversion, both editable.
+// Units are given as (label, drag speed, decimals).
+fn conversion_row(
+    ui: &mut egui::Ui,
+    value: &mut f64,
+    unit: (&str, f64, usize),
+    other_unit: (&str, f64, usize),
+    convert: fn(f64) -> f64,
+    convert_back: fn(f64) -> f64,
+) {
+    ui.add(egui::DragValue::new(value).speed(unit.1).max_decimals(unit.2));
+    ui.label(unit.0);
+    let mut converted = convert(*value);
+    if ui.add(egui::DragValue::new(&mut converted).speed(other_unit.1).max_decimals(other_unit.2)).changed() {
+        *value = convert_back(converted);
+    }
+    ui.label(other_unit.0);
+    ui.end_row();
+}
+
+// Red box above a METAR with a title and one wrapped line per detail
+fn draw_warning_banner(ui: &mut egui::Ui, title: &str, lines: &[String]) {
+    egui::Frame::none()
+        .fill(Color32::from_rgb(60, 0, 0))
+        .stroke(Stroke::new(1.0, Color32::RED))
+        .inner_margin(egui::style::Margin::same(6.0))
+        .show(ui, |ui| {
+            ui.label(RichText::new(title).strong().color(Color32::RED).size(16.0));
+            for line in lines {
+                ui.add(egui::Label::new(RichText::new(line).color(TEXT_COLOR).size(12.0)).wrap(true));
+            }
+        });
+}
+
+// Opens one of the folders METGen keeps its files in with the system file manager
+fn open_folder(folder: &Path) -> Result<(), String> {
+    let opener = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(opener)
+        .arg(folder)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", folder.display(), e))
+}
+
+// Opens a page in the browser, with a button to copy the link instead for
+// another browser or device
+fn link_buttons(ui: &mut egui::Ui, label: &str, url: &str) {
+    ui.horizontal(|ui| {
+        if ui.button(label).clicked() {
+            ui.ctx().open_url(egui::OpenUrl::new_tab(url));
+        }
+        if ui.button("Copy Link").clicked() {
+            ui.output_mut(|o| o.copied_text = url.to_string());
         }
+    });
+}
+
+// Runway heading in degrees from a designator such as "27L" or a heading such as "268"
+fn runway_heading(runway: &str) -> Option<u32> {
+    let digits = runway.trim().trim_end_matches(['L', 'C', 'R', 'l', 'c', 'r']);
+    let value = digits.parse::<u32>().ok()?;
+    match digits.len() {
+        1 | 2 if (1..=36).contains(&value) => Some(value * 10),
+        3 if value <= 360 => Some(value),
+        _ => None,
+    }
+}
+
+// Conventional chart colours for each flight category
+fn flight_category_color(category: FlightCategory) -> Color32 {
+    match category {
+        FlightCategory::Vfr => Color32::from_rgb(0, 200, 0),
+        FlightCategory::MarginalVfr => Color32::from_rgb(60, 120, 255),
+        FlightCategory::Ifr => Color32::RED,
+        FlightCategory::LowIfr => Color32::from_rgb(255, 0, 255),
     }
 }
 
+fn speech_rate_from_config(config: &Value) -> f64 {
+    config.get("speech_rate")
+        .and_then(|rate| rate.as_f64())
+        .unwrap_or(speech::DEFAULT_SPEECH_RATE)
+        .clamp(speech::MIN_SPEECH_RATE, speech::MAX_SPEECH_RATE)
+}
+
 impl eframe::App for MetGenApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.briefing_dirty = true;
+        self.save_briefing();
+        disk_writer::flush();
+        save_worker::flush();
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_pending_request();
+        self.poll_scheduler();
+        self.save_briefing();
+        self.poll_hotkey();
+        self.poll_gamepad();
+        self.poll_key_verification();
+        self.run_auto_refresh(ctx);
+        self.poll_speech(ctx);
+        self.watch_config();
+        for error in save_worker::take_errors() {
+            self.notifications.error(error);
+        }
+        for notice in save_worker::take_notices() {
+            self.notifications.success(notice);
+        }
+        if let Some(places) = save_worker::take_saved_places() {
+            self.saved_airports = Rc::new(places.airports);
+            self.saved_bookmarks = Rc::new(places.bookmarks);
+        }
+        self.poll_disk_tasks();
+
         // Show welcome popup on first run
         if self.config.as_ref().and_then(|c| c.get("is_first_run")).and_then(|v| v.as_bool()).unwrap_or(false) {
             egui::Window::new("Welcome to METGen!")
@@ -152,12 +1176,21 @@ impl eframe::App for MetGenApp {
                         ui.add_space(10.0);
                         ui.label(RichText::new("To get started, you'll need to configure your API keys.").color(TEXT_COLOR));
                         ui.label(RichText::new("Please enter them in the Configuration tab.").color(TEXT_COLOR));
+                        ui.label(RichText::new("Open-Meteo can be used without a key.").color(TEXT_COLOR));
                         ui.add_space(10.0);
-                        if ui.button("Got it!").clicked() {
-                            if let Some(config) = &mut self.config {
-                                config["is_first_run"] = serde_json::Value::Bool(false);
+                        ui.horizontal(|ui| {
+                            if ui.button("Help Me Get a Key").clicked() {
+                                self.show_key_helper = true;
+                                if let Some(config) = &mut self.config {
+                                    config["is_first_run"] = serde_json::Value::Bool(false);
+                                }
                             }
-                        }
+                            if ui.button("Got it!").clicked() {
+                                if let Some(config) = &mut self.config {
+                                    config["is_first_run"] = serde_json::Value::Bool(false);
+                                }
+                            }
+                        });
                     });
                 });
         }
@@ -209,14 +1242,17 @@ impl eframe::App for MetGenApp {
                                         match self.selected_tab {
                                             Tab::GenerateMetar => self.draw_generate_metar(ui),
                                             Tab::SavedAirports => self.draw_saved_airports(ui),
+                                            Tab::Parse => self.draw_parse(ui),
                                             Tab::Configuration => self.draw_configuration(ui),
+                                            Tab::Diagnostics => self.draw_diagnostics(ui),
+                                            Tab::About => self.draw_about(ui),
                                         }
                                     });
                                 });
                         }
                     );
 
-                    // Right half - Reserved for future use with proper frame
+                    // Right half - Plain-English breakdown of the report on screen
                     ui.allocate_ui_with_layout(
                         Vec2::new(half_width, content_height),
                         egui::Layout::top_down(egui::Align::LEFT),
@@ -229,7 +1265,7 @@ impl eframe::App for MetGenApp {
                                     ui.set_max_width(half_width);
                                     ui.set_min_height(content_height - 20.0); // Account for margins
                                     ui.set_max_height(content_height - 20.0);
-                                    // Reserved for future use
+                                    self.draw_report_summary(ui);
                                 });
                         }
                     );
@@ -245,7 +1281,10 @@ impl eframe::App for MetGenApp {
             .show(ctx, |ui| {
                 ui.vertical(|ui| {
                     // Display Results
-                    if let Some(existing) = &self.existing_metar.clone() {
+                    // Taken out while drawing so it isn't cloned every frame, and put
+                    // back unless a button dealt with it
+                    if let Some(existing) = self.existing_metar.take() {
+                        let mut used = false;
                         ui.group(|ui| {
                             ui.vertical(|ui| {
                                 egui::Frame::none()
@@ -253,89 +1292,62 @@ impl eframe::App for MetGenApp {
                                     .stroke(Stroke::new(1.0, CYAN_GLOW))
                                     .show(ui, |ui| {
                                         ui.vertical(|ui| {
-                                            ui.heading(RichText::new("Existing METAR Found").color(MAGENTA_GLOW));
-                                            ui.label(RichText::new(existing).color(TEXT_COLOR).size(16.0));
+                                            ui.heading(RichText::new(format!("Existing METAR Found ({})", existing.source.label())).color(MAGENTA_GLOW));
+                                            draw_metar_text(ui, "existing_metar_text", &existing.raw);
                                             
                                             ui.add_space(10.0);
                                             ui.horizontal(|ui| {
-                                                let existing = existing.clone();
                                                 if ui.button("Use Existing METAR").clicked() {
-                                                    self.generated_metar = existing;
-                                                    self.existing_metar = None;
-                                                    self.success_message = Some("Using existing METAR from NOAA".to_string());
+                                                    self.cancel_request();
+                                                    self.open_session(Report::real(existing.clone()));
+                                                    used = true;
+                                                    self.notifications.success(format!("Using existing METAR from {}", existing.source.label()));
                                                     self.clear_input_fields();
                                                 }
                                                 ui.add_space(20.0);
-                                                if ui.add(egui::Button::new(RichText::new("Generate Synthesized METAR")
+                                                let synthesize_button = egui::Button::new(RichText::new("Generate Synthesized METAR")
                                                     .color(GENERATE_BUTTON_TEXT))
-                                                    .fill(GENERATE_BUTTON_COLOR))
-                                                    .clicked() {
-                                                    if let Some((lat, lon)) = input_handler::resolve_icao_to_lat_lon(&self.input_icao) {
-                                                        self.generate_metar_with_coordinates(lat, lon);
-                                                        self.existing_metar = None;
-                                                        self.clear_input_fields();
-                                                    }
+                                                    .fill(GENERATE_BUTTON_COLOR);
+                                                if self.request_button(ui, RequestKind::Synthesize, synthesize_button) {
+                                                    self.synthesize_over_existing_metar(&existing);
                                                 }
                                             });
                                         });
                                     });
                             });
                         });
-                    } else if !self.generated_metar.is_empty() {
-                        ui.group(|ui| {
-                            ui.vertical(|ui| {
-                                egui::Frame::none()
-                                    .inner_margin(egui::style::Margin::same(8.0))
-                                    .stroke(Stroke::new(1.0, CYAN_GLOW))
-                                    .show(ui, |ui| {
-                                        ui.vertical(|ui| {
-                                            ui.heading(RichText::new("Generated METAR").color(MAGENTA_GLOW));
-                                            ui.label(RichText::new(&self.generated_metar).color(TEXT_COLOR).size(16.0));
-                                            
-                                            // Add warning statement
-                                            ui.add_space(10.0);
-                                            ui.horizontal(|ui| {
-                                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                                    ui.label(RichText::new("Not for aviation purposes").color(MAGENTA_GLOW).size(14.0));
-                                                    ui.label(RichText::new("For simulator use only.").color(CYAN_GLOW).size(14.0));
-                                                });
-                                            });
-                                            
-                                            // Show save button only for lat/lon or location-based METARs
-                                            match self.last_input_method {
-                                                InputMethod::LatLon | InputMethod::Location => {
-                                                    ui.add_space(10.0);
-                                                    ui.horizontal(|ui| {
-                                                        if ui.button("Save Airport").clicked() {
-                                                            if let Err(e) = save_user_airport(
-                                                                self.saved_icao.clone(),
-                                                                self.saved_lat,
-                                                                self.saved_lon
-                                                            ) {
-                                                                self.error_message = Some(format!("Failed to save airport: {}", e));
-                                                            } else {
-                                                                self.success_message = Some(format!("Saved airport {}", self.saved_icao));
-                                                            }
-                                                        }
-                                                    });
-                                                },
-                                                _ => {}
-                                            }
-                                        });
-                                    });
-                            });
-                        });
+                        if !used && self.existing_metar.is_none() {
+                            self.existing_metar = Some(existing);
+                        }
+                    } else if !self.sessions.is_empty() {
+                        self.draw_sessions(ui);
                     }
                     
                     // Error/Success Messages
                     ui.add_space(8.0);
-                    if let Some(error) = &self.error_message {
+                    if let Some(error) = &self.notifications.error_banner {
                         ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
                             ui.add_space(8.0);
                             ui.colored_label(Color32::RED, RichText::new(error).size(16.0));
                         });
                     }
-                    if let Some(success) = &self.success_message {
+                    let mut dismissed = None;
+                    for error in self.refresh_errors.errors() {
+                        ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
+                            ui.add_space(8.0);
+                            ui.colored_label(Color32::RED, RichText::new(error.text()).size(16.0));
+                            if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                                dismissed = Some(error.source.clone());
+                            }
+                        });
+                    }
+                    if let Some(source) = dismissed {
+                        self.refresh_errors.resolve(&source);
+                    }
+                    if self.refresh_errors.counting_down() {
+                        ctx.request_repaint_after(Duration::from_secs(1));
+                    }
+                    if let Some(success) = &self.notifications.success_banner {
                         ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
                             ui.add_space(8.0);
                             ui.colored_label(Color32::GREEN, RichText::new(success).size(16.0));
@@ -343,11 +1355,32 @@ impl eframe::App for MetGenApp {
                     }
                 });
             });
+
+        if self.show_qr_code {
+            self.draw_qr_window(ctx);
+        }
+
+        if self.widget_station.is_some() {
+            self.draw_widget_window(ctx);
+        }
+
+        if self.show_notifications {
+            self.draw_notifications_window(ctx);
+        }
+
+        if !self.crash_reports.is_empty() {
+            self.draw_crash_report_window(ctx);
+        }
+
+        if self.show_key_helper {
+            self.draw_key_helper_window(ctx);
+        }
     }
 }
 
 impl MetGenApp {
     fn draw_header(&mut self, ui: &mut egui::Ui) {
+        let header_rect = ui.max_rect();
         ui.vertical_centered(|ui| {
             ui.heading(RichText::new("METGen").color(CYAN_GLOW).size(32.0));
             ui.label(RichText::new("Synthesized METAR Generation").color(MAGENTA_GLOW).size(16.0));
@@ -357,25 +1390,120 @@ impl MetGenApp {
                     .size(14.0)
             );
         });
+
+        // Profile selector pinned to the top-right corner of the header
+        let selector_rect = egui::Rect::from_min_size(
+            egui::pos2(header_rect.right() - 190.0, header_rect.top()),
+            Vec2::new(190.0, 24.0),
+        );
+        let current_profile = active_profile();
+        let mut selected_profile = None;
+        ui.allocate_ui_at_rect(selector_rect, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Profile:").color(TEXT_COLOR));
+                egui::ComboBox::from_id_source("profile_selector")
+                    .selected_text(&current_profile)
+                    .width(110.0)
+                    .show_ui(ui, |ui| {
+                        for profile in &self.profiles {
+                            if ui.selectable_label(*profile == current_profile, profile).clicked()
+                                && *profile != current_profile
+                            {
+                                selected_profile = Some(profile.clone());
+                            }
+                        }
+                    });
+            });
+        });
+        if let Some(profile) = selected_profile {
+            self.switch_profile(&profile);
+        }
+
+        // Notification center button, left of the profile selector
+        let bell_rect = egui::Rect::from_min_size(
+            egui::pos2(header_rect.right() - 240.0, header_rect.top()),
+            Vec2::new(44.0, 24.0),
+        );
+        ui.allocate_ui_at_rect(bell_rect, |ui| {
+            let unread = self.notifications.unread();
+            let text = if unread > 0 { format!("🔔 {}", unread) } else { "🔔".to_string() };
+            let color = if unread > 0 { MAGENTA_GLOW } else { TEXT_COLOR };
+            if ui.button(RichText::new(text).color(color)).on_hover_text("Recent messages").clicked() {
+                self.show_notifications = !self.show_notifications;
+            }
+        });
+
+        // Quick ICAO box below the profile selector, generating with the current defaults from any tab
+        let quick_rect = egui::Rect::from_min_size(
+            egui::pos2(header_rect.right() - 190.0, header_rect.top() + 28.0),
+            Vec2::new(190.0, 24.0),
+        );
+        let mut quick_generate = false;
+        ui.allocate_ui_at_rect(quick_rect, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Quick ICAO:").color(TEXT_COLOR));
+                let response = ui.add(egui::TextEdit::singleline(&mut self.quick_icao)
+                    .id_source("quick_icao")
+                    .desired_width(45.0));
+                let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                let busy = self.pending_request.is_some();
+                let go = ui.add_enabled(!busy, egui::Button::new("Go")).clicked();
+                quick_generate = (entered || go) && !busy;
+            });
+        });
+        if quick_generate && !self.quick_icao.trim().is_empty() {
+            self.input_icao = self.quick_icao.trim().to_uppercase();
+            self.quick_icao.clear();
+            self.selected_tab = Tab::GenerateMetar;
+            self.generate_metar_from_icao();
+        }
+
+        // Provider health pinned to the top-left corner, one line per provider
+        let health = input_handler::provider_health();
+        if !health.is_empty() {
+            let health_rect = egui::Rect::from_min_size(header_rect.left_top(), Vec2::new(220.0, header_rect.height()));
+            ui.allocate_ui_at_rect(health_rect, |ui| {
+                ui.vertical(|ui| {
+                    ui.spacing_mut().item_spacing.y = 0.0;
+                    for provider in &health {
+                        let color = if provider.error.is_some() { Color32::RED } else { Color32::GREEN };
+                        let mut details = format!(
+                            "Last call {} s ago, {} ms",
+                            provider.checked.elapsed().as_secs(),
+                            provider.latency.as_millis()
+                        );
+                        if let Some(error) = &provider.error {
+                            details.push_str(&format!("\nError: {}", error));
+                        }
+                        if let Some(remaining) = provider.quota_remaining {
+                            details.push_str(&format!("\n{} calls remaining", remaining));
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new("●").color(color).size(10.0));
+                            ui.label(RichText::new(format!("{} {} ms", provider.provider, provider.latency.as_millis()))
+                                .color(TEXT_COLOR)
+                                .size(11.0));
+                        })
+                        .response
+                        .on_hover_text(details);
+                    }
+                });
+            });
+        }
     }
 
     fn draw_tab_bar(&mut self, ui: &mut egui::Ui) {
         let tab_height = 30.0;
         let tab_padding = Vec2::new(20.0, 5.0);
 
-        ui.horizontal(|ui| {
+        // Wraps onto a second row when the panel is too narrow for all tabs
+        ui.horizontal_wrapped(|ui| {
             ui.spacing_mut().item_spacing.x = 1.0;  // Minimal spacing between tabs
             
-            for tab in [Tab::GenerateMetar, Tab::SavedAirports, Tab::Configuration] {
+            for tab in Tab::ALL {
                 let is_selected = self.selected_tab == tab;
-                let text = match tab {
-                    Tab::GenerateMetar => "Generate METAR",
-                    Tab::SavedAirports => "Saved Airports",
-                    Tab::Configuration => "Configuration",
-                };
-
                 let button = egui::Button::new(
-                    RichText::new(text)
+                    RichText::new(tab.label())
                         .color(if is_selected { MAGENTA_GLOW } else { CYAN_GLOW })
                 )
                 .fill(if is_selected { Color32::from_rgb(40, 40, 40) } else { Color32::BLACK });
@@ -389,7 +1517,10 @@ impl MetGenApp {
                     });
 
                 if frame.inner.clicked() {
-                    self.selected_tab = tab.clone();
+                    if tab == Tab::Diagnostics {
+                        self.usage_stats.reload();
+                    }
+                    self.selected_tab = tab;
                 }
             }
         });
@@ -413,6 +1544,8 @@ impl MetGenApp {
                 ui.selectable_value(&mut self.selected_api, ApiType::Standard, "Standard API");
                 ui.add_space(20.0);
                 ui.selectable_value(&mut self.selected_api, ApiType::OneCall, "One Call API");
+                ui.add_space(20.0);
+                ui.selectable_value(&mut self.selected_api, ApiType::OpenMeteo, "Open-Meteo (no key)");
             });
             
             ui.add_space(15.0);
@@ -426,19 +1559,14 @@ impl MetGenApp {
                         ui.set_width(300.0);
                         ui.label("ICAO Lookup:");
                         ui.add_space(10.0);
-                        let icao_edit = egui::TextEdit::singleline(&mut self.input_icao)
-                            .desired_width(40.0);
-                        if ui.add(icao_edit).changed() {
-                            self.clear_output_display();
-                        }
+                        self.draw_icao_input(ui, "icao_lookup");
                         ui.add_space(10.0);
-                        if ui.add(egui::Button::new(RichText::new("Generate")
-                            .color(GENERATE_BUTTON_TEXT))
-                            .fill(GENERATE_BUTTON_COLOR))
-                            .clicked() {
+                        if self.generate_button(ui, RequestKind::IcaoLookup) {
                             self.generate_metar_from_icao();
                         }
                     });
+                    self.draw_recent_icaos(ui);
+                    self.draw_airport_search(ui);
                     
                     ui.add_space(10.0);  // Reduced from 15.0 to 10.0
                     
@@ -448,11 +1576,7 @@ impl MetGenApp {
                             ui.set_width(300.0);
                             ui.label("Custom Location ICAO:");
                             ui.add_space(10.0);
-                            let icao_edit = egui::TextEdit::singleline(&mut self.input_icao)
-                                .desired_width(40.0);
-                            if ui.add(icao_edit).changed() {
-                                self.clear_output_display();
-                            }
+                            self.draw_icao_input(ui, "icao_lat_lon");
                         });
                         ui.horizontal(|ui| {
                             ui.set_width(300.0);
@@ -460,7 +1584,12 @@ impl MetGenApp {
                             ui.add_space(10.0);
                             let lat_edit = egui::TextEdit::singleline(&mut self.input_lat)
                                 .desired_width(80.0);
-                            if ui.add(lat_edit).changed() {
+                            let lat_response = ui.add(lat_edit);
+                            if lat_response.changed() {
+                                // A pasted "lat, lon" pair fills in both fields
+                                if lat_response.has_focus() && ui.input(|i| i.events.iter().any(|e| matches!(e, egui::Event::Paste(_)))) {
+                                    self.split_coordinate_pair();
+                                }
                                 self.clear_output_display();
                             }
                             ui.add_space(10.0);
@@ -473,12 +1602,9 @@ impl MetGenApp {
                             }
                         });
                         ui.horizontal(|ui| {
-                            if ui.add(egui::Button::new(RichText::new("Generate")
-                                .color(GENERATE_BUTTON_TEXT))
-                                .fill(GENERATE_BUTTON_COLOR))
-                                .clicked() {
+                            if self.generate_button(ui, RequestKind::LatLon) {
                                 if self.input_icao.is_empty() {
-                                    self.error_message = Some("Please enter an ICAO code for the location".to_string());
+                                    self.notifications.error("Please enter an ICAO code for the location".to_string());
                                 } else {
                                     self.generate_metar_from_coords();
                                 }
@@ -494,11 +1620,7 @@ impl MetGenApp {
                             ui.set_width(300.0);
                             ui.label("Custom Location ICAO:");
                             ui.add_space(10.0);
-                            let icao_edit = egui::TextEdit::singleline(&mut self.input_icao)
-                                .desired_width(40.0);
-                            if ui.add(icao_edit).changed() {
-                                self.clear_output_display();
-                            }
+                            self.draw_icao_input(ui, "icao_location");
                         });
                         ui.horizontal(|ui| {
                             ui.set_width(300.0);
@@ -507,38 +1629,276 @@ impl MetGenApp {
                             let location_edit = egui::TextEdit::singleline(&mut self.input_location)
                                 .desired_width(120.0)
                                 .min_size(Vec2::new(120.0, 0.0));
-                            if ui.add(location_edit).changed() {
+                            if ui.add(location_edit)
+                                .on_hover_text("A place name, a Google Maps or OpenStreetMap link, or a plus code")
+                                .changed()
+                            {
                                 self.clear_output_display();
                             }
                         });
                         ui.horizontal(|ui| {
-                            if ui.add(egui::Button::new(RichText::new("Generate")
-                                .color(GENERATE_BUTTON_TEXT))
-                                .fill(GENERATE_BUTTON_COLOR))
-                                .clicked() {
+                            if self.generate_button(ui, RequestKind::Location) {
                                 if self.input_icao.is_empty() {
-                                    self.error_message = Some("Please enter an ICAO code for the location".to_string());
+                                    self.notifications.error("Please enter an ICAO code for the location".to_string());
                                 } else {
                                     self.generate_metar_from_location();
                                 }
                             }
                         });
                     });
+
+                    ui.add_space(5.0);
+
+                    // Flight Route: every station of a flight in one request
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.set_width(300.0);
+                            ui.label("Flight Route:");
+                            ui.add_space(10.0);
+                            ui.add(egui::TextEdit::singleline(&mut self.route_input)
+                                .hint_text("KSEA KPDX KEUG")
+                                .desired_width(180.0))
+                                .on_hover_text("Departure, alternates and destination, separated by spaces or commas");
+                        });
+                        ui.horizontal(|ui| {
+                            let button = egui::Button::new(RichText::new("Generate Route").color(GENERATE_BUTTON_TEXT))
+                                .fill(GENERATE_BUTTON_COLOR);
+                            if self.request_button(ui, RequestKind::Route, button) {
+                                self.generate_route();
+                            }
+                            if !self.route.is_empty() && ui.button("Save Route Briefing...").clicked() {
+                                self.save_route_briefing();
+                            }
+                        });
+                        if !self.route.is_empty() {
+                            self.draw_route(ui);
+                        }
+                    });
                 });
             });
         });
     }
 
+    // The last route's stations with their current reports. Clicking a station
+    // shows it in the output panel.
+    fn draw_route(&mut self, ui: &mut egui::Ui) {
+        let mut selected = None;
+        egui::ScrollArea::vertical()
+            .id_source("route_scroll")
+            .max_height(160.0)
+            .show(ui, |ui| {
+                for station in &self.route {
+                    let session = self.sessions.iter().position(|session| session.icao == station.icao);
+                    ui.horizontal_wrapped(|ui| {
+                        let label = RichText::new(&station.icao).color(CYAN_GLOW).monospace();
+                        if ui.add_enabled(session.is_some(), egui::SelectableLabel::new(false, label)).clicked() {
+                            selected = session;
+                        }
+                        match (&station.error, session) {
+                            (Some(error), _) => ui.colored_label(Color32::RED, error),
+                            (None, Some(index)) => ui.label(RichText::new(&self.sessions[index].metar).color(TEXT_COLOR).monospace().size(12.0)),
+                            (None, None) => ui.label(RichText::new("Closed").color(TEXT_COLOR)),
+                        };
+                    });
+                }
+            });
+        if let Some(index) = selected {
+            self.active_session = index;
+            self.briefing_dirty = true;
+        }
+    }
+
+    // ICAO text field with a suggestion popup of pinned/recent, saved and database airports
+    fn draw_icao_input(&mut self, ui: &mut egui::Ui, id: &str) {
+        let response = ui.add(egui::TextEdit::singleline(&mut self.input_icao)
+            .id_source(id)
+            .desired_width(40.0));
+        if response.changed() {
+            self.clear_output_display();
+        }
+
+        let popup_id = ui.make_persistent_id(format!("{}_suggestions", id));
+        if response.changed() && !self.input_icao.trim().is_empty() {
+            ui.memory_mut(|memory| memory.open_popup(popup_id));
+        }
+        if !ui.memory(|memory| memory.is_popup_open(popup_id)) {
+            return;
+        }
+
+        let suggestions = self.icao_suggestions(&self.input_icao.clone());
+        if suggestions.is_empty() {
+            ui.memory_mut(|memory| memory.close_popup());
+            return;
+        }
+
+        let mut chosen = None;
+        egui::popup_below_widget(ui, popup_id, &response, |ui| {
+            ui.set_min_width(80.0);
+            for suggestion in &suggestions {
+                if ui.selectable_label(false, suggestion).clicked() {
+                    chosen = Some(suggestion.clone());
+                }
+            }
+        });
+        if let Some(icao) = chosen {
+            self.input_icao = icao;
+            self.clear_output_display();
+        }
+    }
+
+    // Search box for finding an airport by name, city, IATA or ICAO code. Picking
+    // a result puts its ICAO code in the ICAO fields.
+    fn draw_airport_search(&mut self, ui: &mut egui::Ui) {
+        const MAX_RESULTS: usize = 10;
+        let db = airport_db::get();
+        let hint = if db.has_names() { "Search airports: name, city, IATA or ICAO" } else { "Search airports by ICAO" };
+        let response = ui.horizontal(|ui| {
+            ui.label(RichText::new("Find:").color(TEXT_COLOR).size(12.0));
+            let edit = egui::TextEdit::singleline(&mut self.airport_search).hint_text(hint).desired_width(260.0);
+            if db.has_names() {
+                ui.add(edit)
+            } else {
+                ui.add(edit).on_hover_text(
+                    "The bundled database has no airport names. Put OurAirports' airports.csv in the data folder to search by name, city and IATA code"
+                )
+            }
+        }).inner;
+
+        let popup_id = ui.make_persistent_id("airport_search_results");
+        if response.changed() {
+            self.airport_search_results = db
+                .search(&self.airport_search, MAX_RESULTS)
+                .into_iter()
+                .map(|airport| (airport.icao.clone(), airport.label()))
+                .collect();
+            if self.airport_search_results.is_empty() {
+                ui.memory_mut(|memory| memory.close_popup());
+            } else {
+                ui.memory_mut(|memory| memory.open_popup(popup_id));
+            }
+        }
+
+        let mut chosen = None;
+        egui::popup_below_widget(ui, popup_id, &response, |ui| {
+            ui.set_min_width(260.0);
+            for (icao, label) in &self.airport_search_results {
+                if ui.selectable_label(false, label).clicked() {
+                    chosen = Some(icao.clone());
+                }
+            }
+        });
+        if let Some(icao) = chosen {
+            self.input_icao = icao;
+            self.airport_search.clear();
+            self.airport_search_results.clear();
+            self.clear_output_display();
+        }
+    }
+
+    // Matches for a partially typed ICAO: pinned and recent first, then saved airports,
+    // then the airport database
+    fn icao_suggestions(&mut self, typed: &str) -> Vec<String> {
+        const MAX_SUGGESTIONS: usize = 8;
+        let prefix = typed.trim().to_uppercase();
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        let saved = self.saved_airports();
+        let mut suggestions: Vec<String> = Vec::new();
+        let candidates = self.pinned_icaos.iter()
+            .chain(self.recent_icaos.iter())
+            .chain(saved.iter().map(|airport| &airport.icao))
+            .chain(airport_db::get().airports().iter().map(|airport| &airport.icao));
+        for icao in candidates {
+            if icao.starts_with(&prefix) && *icao != prefix && !suggestions.contains(icao) {
+                suggestions.push(icao.clone());
+                if suggestions.len() == MAX_SUGGESTIONS {
+                    break;
+                }
+            }
+        }
+        suggestions
+    }
+
+    // Quick-pick buttons for pinned and recently used ICAOs. Right-click to pin or unpin.
+    fn draw_recent_icaos(&mut self, ui: &mut egui::Ui) {
+        if self.recent_icaos.is_empty() && self.pinned_icaos.is_empty() {
+            return;
+        }
+
+        let mut entries: Vec<String> = self.pinned_icaos.clone();
+        entries.extend(self.recent_icaos.iter().filter(|icao| !self.pinned_icaos.contains(icao)).cloned());
+        entries.truncate(6);
+
+        let mut chosen = None;
+        let mut toggle_pin = None;
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Recent:").color(TEXT_COLOR).size(12.0));
+            for icao in &entries {
+                let pinned = self.pinned_icaos.contains(icao);
+                let label = if pinned { format!("📌{}", icao) } else { icao.clone() };
+                let response = ui.small_button(label);
+                if response.clicked() {
+                    chosen = Some(icao.clone());
+                }
+                response.context_menu(|ui| {
+                    if ui.button(if pinned { "Unpin" } else { "Pin" }).clicked() {
+                        toggle_pin = Some((icao.clone(), !pinned));
+                        ui.close_menu();
+                    }
+                });
+            }
+        });
+
+        if let Some(icao) = chosen {
+            self.input_icao = icao;
+            self.clear_output_display();
+        }
+        if let Some((icao, pinned)) = toggle_pin {
+            self.update_recent_icaos(move |config| set_icao_pinned(config, &icao, pinned));
+        }
+    }
+
+    fn remember_icao(&mut self, icao: &str) {
+        if icao.is_empty() {
+            return;
+        }
+        let icao = icao.to_string();
+        self.update_recent_icaos(move |config| add_recent_icao(config, &icao));
+    }
+
+    // Applies a change of the recent or pinned ICAOs in memory and queues it for the config file
+    fn update_recent_icaos(&mut self, change: impl Fn(&mut Value) + Send + 'static) {
+        if let Some(config) = self.config.as_mut() {
+            change(config);
+            (self.recent_icaos, self.pinned_icaos) = recent_icaos(config);
+        }
+        save_worker::queue(change);
+    }
+
     // Add helper function to clear output display
     fn clear_output_display(&mut self) {
-        self.generated_metar.clear();
-        self.error_message = None;
-        self.success_message = None;
+        self.notifications.clear_banners();
         self.existing_metar = None;
     }
 
+    // The active profile's saved airports; empty until the save worker has read them
+    fn saved_airports(&self) -> Rc<Vec<UserAirport>> {
+        self.saved_airports.clone()
+    }
+
+    fn saved_bookmarks(&self) -> Rc<Vec<Bookmark>> {
+        self.saved_bookmarks.clone()
+    }
+
+    // Has the save worker read the saved airports and bookmarks again
+    fn reload_saved_places(&mut self) {
+        save_worker::load_saved_places();
+    }
+
     fn draw_saved_airports(&mut self, ui: &mut egui::Ui) {
-        let airports = get_user_airports();
+        let airports = self.saved_airports();
         let available_height = ui.available_height();
 
         ui.vertical(|ui| {
@@ -552,337 +1912,3163 @@ impl MetGenApp {
                 ui.selectable_value(&mut self.selected_api, ApiType::Standard, "Standard API");
                 ui.add_space(20.0);
                 ui.selectable_value(&mut self.selected_api, ApiType::OneCall, "One Call API");
+                ui.add_space(20.0);
+                ui.selectable_value(&mut self.selected_api, ApiType::OpenMeteo, "Open-Meteo (no key)");
                 
                 // Push title to right edge
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.heading(RichText::new("Saved Airports").color(CYAN_GLOW));
+                    ui.add_space(20.0);
+                    if ui.add_enabled(!airports.is_empty(), egui::Button::new("Export to Little Navmap"))
+                        .on_hover_text("Save the saved airports as a Little Navmap userpoints CSV")
+                        .clicked()
+                    {
+                        self.export_little_navmap(&airports);
+                    }
+                    if !self.locked {
+                        ui.add_space(10.0);
+                        if ui.button("Import GPX/KML").on_hover_text("Add waypoints from a GPX or KML file as saved airports").clicked() {
+                            self.import_waypoints(&airports);
+                        }
+                    }
                 });
             });
             
             ui.add_space(15.0);
 
-            if airports.is_empty() {
-                ui.label("No saved airports found");
-            } else {
-                egui::ScrollArea::vertical()
-                    .max_height(available_height - 100.0)  // Account for header and API selection
-                    .show(ui, |ui| {
-                        for airport in airports {
-                            ui.group(|ui| {
-                                ui.horizontal(|ui| {
-                                    ui.label(RichText::new(&airport.icao).color(TEXT_COLOR));
-                                    ui.label(format!("(Lat: {:.4}, Lon: {:.4})", 
-                                        airport.latitude, airport.longitude));
-                                    
-                                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                        // Delete button with red color and trashcan icon
+            egui::ScrollArea::vertical()
+                .max_height(available_height - 100.0)  // Account for header and API selection
+                .show(ui, |ui| {
+                    if airports.is_empty() {
+                        ui.label("No saved airports found");
+                    }
+                    let mut moved = None;
+                    for (index, airport) in airports.iter().enumerate() {
+                        let airport_icao = airport.icao.clone();
+                        let row = ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                // Drag handle for reordering the list
+                                ui.dnd_drag_source(egui::Id::new(("saved_airport_drag", index)), (index, airport.icao.clone()), |ui| {
+                                    ui.label(RichText::new("☰").color(CYAN_GLOW));
+                                }).response.on_hover_text("Drag to reorder");
+                                ui.label(RichText::new(&airport.icao).color(TEXT_COLOR));
+                                ui.label(format!("(Lat: {:.4}, Lon: {:.4})", 
+                                    airport.latitude, airport.longitude));
+                                // Station name, saved when the field loses focus
+                                let name = self.airport_name_edits
+                                    .entry(airport.icao.clone())
+                                    .or_insert_with(|| airport.name.clone().unwrap_or_default());
+                                let name_edit = egui::TextEdit::singleline(name)
+                                    .desired_width(180.0)
+                                    .hint_text("Station name (optional)");
+                                let response = ui.add_enabled(!self.locked, name_edit);
+                                if response.lost_focus() && name.trim() != airport.name.as_deref().unwrap_or_default() {
+                                    let (icao, new_name) = (airport.icao.clone(), name.clone());
+                                    save_worker::queue_storage(move || {
+                                        set_user_airport_name(&icao, &new_name)
+                                            .map(|()| None)
+                                            .map_err(|e| format!("Failed to save station name: {}", e))
+                                    });
+                                    let name = Some(name.trim().to_string()).filter(|name| !name.is_empty());
+                                    for session in self.sessions.iter_mut().filter(|session| session.icao == airport.icao) {
+                                        session.name = name.clone();
+                                        self.briefing_dirty = true;
+                                    }
+                                }
+                                
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    // Delete button with red color and trashcan icon (hidden when locked)
+                                    if !self.locked {
                                         let delete_button = egui::Button::new(RichText::new("🗑").color(Color32::RED))
                                             .fill(Color32::from_rgb(40, 0, 0));
                                         if ui.add(delete_button).clicked() {
-                                            if let Err(e) = delete_user_airport(&airport.icao) {
-                                                self.error_message = Some(format!("Failed to delete airport: {}", e));
-                                            } else {
-                                                self.success_message = Some(format!("Deleted airport {}", airport.icao));
-                                            }
-                                        }
-                                        if ui.add(egui::Button::new(RichText::new("Generate")
-                                            .color(GENERATE_BUTTON_TEXT))
-                                            .fill(GENERATE_BUTTON_COLOR))
-                                            .clicked() {
-                                            self.generate_metar_for_saved_airport(&airport);
+                                            let icao = airport.icao.clone();
+                                            save_worker::queue_storage(move || match delete_user_airport(&icao) {
+                                                Ok(()) => Ok(Some(format!("Deleted airport {}", icao))),
+                                                Err(e) => Err(format!("Failed to delete airport: {}", e)),
+                                            });
                                         }
-                                    });
+                                    }
+                                    if self.generate_button(ui, RequestKind::SavedAirport(airport.icao.clone())) {
+                                        self.generate_metar_for_saved_airport(airport);
+                                    }
                                 });
                             });
-                            ui.add_space(5.0);
+                        }).response;
+
+                        // Mark the drop position and take the dropped row
+                        if let Some(dragged) = row.dnd_hover_payload::<(usize, String)>() {
+                            let from = dragged.0;
+                            if from != index {
+                                let y = if from < index { row.rect.bottom() } else { row.rect.top() };
+                                ui.painter().hline(row.rect.x_range(), y, Stroke::new(2.0, MAGENTA_GLOW));
+                            }
                         }
-                    });
-            }
+                        if let Some(dragged) = row.dnd_release_payload::<(usize, String)>() {
+                            moved = Some((dragged.1.clone(), airport_icao));
+                        }
+                        ui.add_space(5.0);
+                    }
+
+                    if let Some((icao, target_icao)) = moved {
+                        if icao != target_icao {
+                            save_worker::queue_storage(move || {
+                                move_user_airport(&icao, &target_icao)
+                                    .map(|()| None)
+                                    .map_err(|e| format!("Failed to reorder airports: {}", e))
+                            });
+                        }
+                    }
+
+                    ui.add_space(15.0);
+                    self.draw_bookmarks(ui);
+                });
         });
     }
 
-    fn draw_configuration(&mut self, ui: &mut egui::Ui) {
-        let available_height = ui.available_height();
+    // Bookmarks section below the saved airports: points of interest that aren't airports
+    fn draw_bookmarks(&mut self, ui: &mut egui::Ui) {
+        ui.heading(RichText::new("Bookmarks").color(CYAN_GLOW));
+        ui.label(RichText::new(
+            "Seaplane bases, helipads and landmarks. They are never looked up as ICAO codes; the identifier only labels the METAR"
+        ).color(TEXT_COLOR).size(12.0));
+        ui.add_space(10.0);
 
-        ui.vertical(|ui| {
-            ui.set_min_height(available_height);
-            ui.set_max_height(available_height);
-            
-            ui.heading(RichText::new("Configuration").color(CYAN_GLOW));
-            ui.add_space(15.0);
-            
-            // API Keys Configuration
-            ui.group(|ui| {
-                ui.vertical(|ui| {
-                    ui.heading(RichText::new("API Keys").color(MAGENTA_GLOW));
-                    ui.add_space(10.0);
-                    
-                    if let Some(config) = &mut self.config {
-                        // Standard API Key
-                        ui.horizontal(|ui| {
-                            ui.add_space(40.0);
-                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
-                                ui.set_min_width(100.0);  // Reduced from 120.0
-                                ui.label(RichText::new("Standard API Key:").size(14.0));
-                            });
-                            let mut api_key = config["decrypted_api_key"].as_str().unwrap_or("").to_string();
-                            let api_edit = egui::TextEdit::singleline(&mut api_key)
-                                .desired_width(600.0)
-                                .hint_text("32 characters required");
-                            if ui.add(api_edit).changed() {
-                                // Limit to 32 characters
-                                if api_key.len() > 32 {
-                                    api_key.truncate(32);
-                                }
-                                // Show error if less than 32 characters
-                                if api_key.len() < 32 {
-                                    self.error_message = Some(format!("Standard API Key must be exactly 32 characters (currently {})", api_key.len()));
-                                } else {
-                                    self.error_message = None;
-                                }
-                                // Read current config to preserve all data
-                                if let Ok(contents) = std::fs::read_to_string("config.json") {
-                                    if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(&contents) {
-                                        json["api_key"] = serde_json::Value::String(crate::config::encrypt_key(&api_key));
-                                        if let Ok(config_str) = serde_json::to_string_pretty(&json) {
-                                            if let Err(e) = std::fs::write("config.json", config_str) {
-                                                self.error_message = Some(format!("Failed to save configuration: {}", e));
-                                            }
-                                        }
-                                        config["decrypted_api_key"] = serde_json::Value::String(api_key);
-                                    }
-                                }
-                            }
-                        });
-                        
-                        // OneCall API Key
-                        ui.horizontal(|ui| {
-                            ui.add_space(40.0);
-                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
-                                ui.set_min_width(100.0);  // Reduced from 120.0
-                                ui.label(RichText::new("OneCall API Key:").size(14.0));
-                            });
-                            let mut one_call_key = config["decrypted_one_call_api_key"].as_str().unwrap_or("").to_string();
-                            let one_call_edit = egui::TextEdit::singleline(&mut one_call_key)
-                                .desired_width(600.0)
-                                .hint_text("32 characters required");
-                            if ui.add(one_call_edit).changed() {
-                                // Limit to 32 characters
-                                if one_call_key.len() > 32 {
-                                    one_call_key.truncate(32);
-                                }
-                                // Show error if less than 32 characters
-                                if one_call_key.len() < 32 {
-                                    self.error_message = Some(format!("OneCall API Key must be exactly 32 characters (currently {})", one_call_key.len()));
-                                } else {
-                                    self.error_message = None;
-                                }
-                                // Read current config to preserve all data
-                                if let Ok(contents) = std::fs::read_to_string("config.json") {
-                                    if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(&contents) {
-                                        json["one_call_api_key"] = serde_json::Value::String(crate::config::encrypt_key(&one_call_key));
-                                        if let Ok(config_str) = serde_json::to_string_pretty(&json) {
-                                            if let Err(e) = std::fs::write("config.json", config_str) {
-                                                self.error_message = Some(format!("Failed to save configuration: {}", e));
-                                            }
-                                        }
-                                        config["decrypted_one_call_api_key"] = serde_json::Value::String(one_call_key);
-                                    }
-                                }
-                            }
-                        });
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.bookmark_name)
+                .hint_text("Name")
+                .desired_width(160.0));
+            egui::ComboBox::from_id_source("bookmark_kind")
+                .selected_text(self.bookmark_kind.label())
+                .width(110.0)
+                .show_ui(ui, |ui| {
+                    for kind in BookmarkKind::ALL {
+                        ui.selectable_value(&mut self.bookmark_kind, kind, kind.label());
                     }
                 });
-            });
-            
-            ui.add_space(15.0);
-            
-            // Units Selection
+            ui.add(egui::TextEdit::singleline(&mut self.bookmark_coordinates)
+                .hint_text("Lat, lon")
+                .desired_width(150.0));
+            ui.add(egui::TextEdit::singleline(&mut self.bookmark_identifier)
+                .hint_text("Ident (optional)")
+                .desired_width(90.0));
+            if ui.button("Add Bookmark").clicked() {
+                self.add_bookmark();
+            }
+        });
+        ui.add_space(10.0);
+
+        let bookmarks = self.saved_bookmarks();
+        if bookmarks.is_empty() {
+            ui.label("No bookmarks yet");
+        }
+        for bookmark in bookmarks.iter() {
             ui.group(|ui| {
-                ui.vertical(|ui| {
-                    ui.heading(RichText::new("Units").color(MAGENTA_GLOW));
-                    ui.add_space(10.0);
-                    ui.horizontal(|ui| {
-                        ui.add_space(40.0);  // Same left margin as other elements
-                        let prev_units = self.selected_units;
-                        ui.selectable_value(&mut self.selected_units, Units::Metric, "Metric");
-                        ui.add_space(20.0);
-                        ui.selectable_value(&mut self.selected_units, Units::Imperial, "Imperial");
-                        
-                        // If units changed, update config.json
-                        if prev_units != self.selected_units {
-                            if let Ok(contents) = std::fs::read_to_string("config.json") {
-                                if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(&contents) {
-                                    // Update only the units
-                                    json["units"] = serde_json::Value::String(match self.selected_units {
-                                        Units::Metric => "metric",
-                                        Units::Imperial => "imperial",
-                                    }.to_string());
-                                    // Write back to file
-                                    if let Ok(config_str) = serde_json::to_string_pretty(&json) {
-                                        if let Err(e) = std::fs::write("config.json", config_str) {
-                                            self.error_message = Some(format!("Failed to save configuration: {}", e));
-                                        }
-                                    }
-                                }
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(&bookmark.identifier).color(TEXT_COLOR));
+                    ui.label(&bookmark.name);
+                    ui.label(RichText::new(bookmark.kind.label()).color(MAGENTA_GLOW).size(12.0));
+                    ui.label(format!("(Lat: {:.4}, Lon: {:.4})", bookmark.latitude, bookmark.longitude));
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if !self.locked {
+                            let delete_button = egui::Button::new(RichText::new("🗑").color(Color32::RED))
+                                .fill(Color32::from_rgb(40, 0, 0));
+                            if ui.add(delete_button).clicked() {
+                                let (identifier, name) = (bookmark.identifier.clone(), bookmark.name.clone());
+                                save_worker::queue_storage(move || match bookmarks::delete_bookmark(&identifier) {
+                                    Ok(()) => Ok(Some(format!("Deleted bookmark {}", name))),
+                                    Err(e) => Err(format!("Failed to delete bookmark: {}", e)),
+                                });
                             }
                         }
+                        if self.generate_button(ui, RequestKind::Bookmark(bookmark.identifier.clone())) {
+                            self.generate_metar_for_bookmark(bookmark);
+                        }
                     });
                 });
             });
-        });
+            ui.add_space(5.0);
+        }
     }
 
-    fn generate_metar_from_icao(&mut self) {
-        self.error_message = None;
-        self.success_message = None;
-        self.existing_metar = None;
-        
-        if self.input_icao.is_empty() {
-            self.error_message = Some("Please enter an ICAO code".to_string());
-            return;
-        }
+    fn add_bookmark(&mut self) {
+        self.notifications.clear_banners();
 
-        // Check for existing METAR
-        if let Some(existing_metar) = input_handler::poll_noaa_metar(&self.input_icao) {
-            self.existing_metar = Some(existing_metar);
-            self.success_message = Some("Found existing METAR. Please choose an option with the buttons.".to_string());
+        let Some((lat, lon)) = parse_coordinate_pair(&self.bookmark_coordinates)
+            .and_then(|(lat, lon)| input_handler::validate_lat_lon(lat, lon))
+        else {
+            self.notifications.error("Please enter the bookmark position as latitude, longitude".to_string());
             return;
-        }
+        };
 
-        // No existing METAR, generate one
-        if let Some((lat, lon)) = input_handler::resolve_icao_to_lat_lon(&self.input_icao) {
-            self.last_input_method = InputMethod::Icao;
-            self.generate_metar_with_coordinates(lat, lon);
-            self.clear_input_fields();
-        } else {
-            self.error_message = Some(format!("Could not resolve ICAO code: {}", self.input_icao));
+        // Checked against the lists on screen so a mistake keeps the form filled in;
+        // the worker checks again against what is stored
+        let (airports, saved_bookmarks) = (self.saved_airports(), self.saved_bookmarks());
+        let checked = bookmarks::new_bookmark(
+            &self.bookmark_name,
+            self.bookmark_kind,
+            (lat, lon),
+            &self.bookmark_identifier,
+            &airports,
+            &saved_bookmarks,
+        );
+        match checked {
+            Ok(bookmark) => {
+                save_worker::queue_storage(move || {
+                    let Bookmark { name, kind, latitude, longitude, identifier } = bookmark;
+                    bookmarks::add_bookmark(&name, kind, (latitude, longitude), &identifier)
+                        .map(|bookmark| Some(format!("Bookmarked {} as {}", bookmark.name, bookmark.identifier)))
+                });
+                self.bookmark_name.clear();
+                self.bookmark_coordinates.clear();
+                self.bookmark_identifier.clear();
+            }
+            Err(e) => self.notifications.error(e),
         }
     }
 
-    fn generate_metar_from_coords(&mut self) {
-        self.error_message = None;
-        self.success_message = None;
-        
-        if self.input_lat.is_empty() || self.input_lon.is_empty() {
-            self.error_message = Some("Please enter both latitude and longitude".to_string());
-            return;
-        }
+    fn import_waypoints(&mut self, airports: &[UserAirport]) {
+        self.notifications.clear_banners();
 
-        let lat = match self.input_lat.parse::<f64>() {
-            Ok(lat) => lat,
-            Err(_) => {
-                self.error_message = Some("Invalid latitude format".to_string());
-                return;
-            }
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Waypoints", &["gpx", "kml"])
+            .pick_file()
+        else {
+            return;
         };
 
-        let lon = match self.input_lon.parse::<f64>() {
-            Ok(lon) => lon,
-            Err(_) => {
-                self.error_message = Some("Invalid longitude format".to_string());
+        let waypoints = match waypoints::read_waypoint_file(&path) {
+            Ok(waypoints) => waypoints,
+            Err(e) => {
+                self.notifications.error(e);
                 return;
             }
         };
 
-        if let Some((lat, lon)) = input_handler::validate_lat_lon(lat, lon) {
-            self.last_input_method = InputMethod::LatLon;
-            self.saved_lat = lat;
-            self.saved_lon = lon;
-            self.saved_icao = self.input_icao.to_uppercase();
-            self.generate_metar_with_coordinates(lat, lon);
-            self.clear_input_fields();
-        } else {
-            self.error_message = Some("Invalid latitude/longitude values".to_string());
-        }
+        let taken: Vec<String> = airports.iter().map(|airport| airport.icao.clone()).collect();
+        let imported = waypoints::waypoints_to_airports(waypoints, &taken);
+        save_worker::queue_storage(move || match save_user_airports(&imported) {
+            Ok(added) => Ok(Some(format!("Imported {} waypoint(s) as saved airports", added))),
+            Err(e) => Err(format!("Failed to import waypoints: {}", e)),
+        });
     }
 
-    fn generate_metar_from_location(&mut self) {
-        self.error_message = None;
-        self.success_message = None;
+    fn export_little_navmap(&mut self, airports: &[UserAirport]) {
+        self.notifications.clear_banners();
+
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Little Navmap userpoints", &["csv"])
+            .set_file_name("metgen_userpoints.csv")
+            .save_file()
+        else {
+            return;
+        };
+
+        match waypoints::export_little_navmap(&path, airports) {
+            Ok(()) => {
+                self.notifications.success(format!("Exported {} airport(s) to {}", airports.len(), path.display()));
+            }
+            Err(e) => self.notifications.error(e),
+        }
+    }
+
+    fn draw_configuration(&mut self, ui: &mut egui::Ui) {
+        let available_height = ui.available_height();
+
+        ui.vertical(|ui| {
+            ui.set_min_height(available_height);
+            ui.set_max_height(available_height);
+            
+            ui.heading(RichText::new("Configuration").color(CYAN_GLOW));
+            ui.add_space(15.0);
+            
+            egui::ScrollArea::vertical()
+                .id_source("configuration_scroll")
+                .show(ui, |ui| {
+                    // API Keys Configuration
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("API Keys").color(MAGENTA_GLOW));
+                            if crate::config::keys_from_env() {
+                                ui.label(RichText::new("Keys set via METGEN_OWM_KEY / METGEN_ONECALL_KEY override the saved keys").color(TEXT_COLOR).size(12.0));
+                            }
+                            if self.locked {
+                                ui.label(RichText::new("Configuration is locked. API keys cannot be edited.").color(TEXT_COLOR).size(12.0));
+                            }
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                if ui.button("Key Setup Guide").clicked() {
+                                    self.show_key_helper = true;
+                                }
+                                ui.label(RichText::new("Step-by-step help creating and activating OpenWeather keys").color(TEXT_COLOR).size(12.0));
+                            });
+                            ui.add_space(5.0);
+                    
+                            if let Some(config) = &mut self.config {
+                                // Standard API Key
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                                        ui.set_min_width(100.0);  // Reduced from 120.0
+                                        ui.label(RichText::new("Standard API Key:").size(14.0));
+                                    });
+                                    let mut api_key = config["decrypted_api_key"].as_str().unwrap_or("").to_string();
+                                    let api_edit = egui::TextEdit::singleline(&mut api_key)
+                                        .desired_width(600.0)
+                                        .hint_text("32 characters required")
+                                        .password(self.locked);
+                                    if ui.add_enabled(!self.locked, api_edit).changed() {
+                                        // Limit to 32 characters
+                                        if api_key.len() > 32 {
+                                            api_key.truncate(32);
+                                        }
+                                        // Show error if less than 32 characters
+                                        if api_key.len() < 32 {
+                                            self.notifications.error_banner = Some(format!("Standard API Key must be exactly 32 characters (currently {})", api_key.len()));
+                                        } else {
+                                            self.notifications.error_banner = None;
+                                        }
+                                        // Written to the config file once complete (or cleared), not on every keystroke
+                                        if api_key.is_empty() || api_key.len() == 32 {
+                                            queue_setting("api_key", Value::String(crate::config::encrypt_key(&api_key)));
+                                        }
+                                        config["decrypted_api_key"] = Value::String(api_key);
+                                    }
+                                });
+                        
+                                // OneCall API Key
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                                        ui.set_min_width(100.0);  // Reduced from 120.0
+                                        ui.label(RichText::new("OneCall API Key:").size(14.0));
+                                    });
+                                    let mut one_call_key = config["decrypted_one_call_api_key"].as_str().unwrap_or("").to_string();
+                                    let one_call_edit = egui::TextEdit::singleline(&mut one_call_key)
+                                        .desired_width(600.0)
+                                        .hint_text("32 characters required")
+                                        .password(self.locked);
+                                    if ui.add_enabled(!self.locked, one_call_edit).changed() {
+                                        // Limit to 32 characters
+                                        if one_call_key.len() > 32 {
+                                            one_call_key.truncate(32);
+                                        }
+                                        // Show error if less than 32 characters
+                                        if one_call_key.len() < 32 {
+                                            self.notifications.error_banner = Some(format!("OneCall API Key must be exactly 32 characters (currently {})", one_call_key.len()));
+                                        } else {
+                                            self.notifications.error_banner = None;
+                                        }
+                                        // Written to the config file once complete (or cleared), not on every keystroke
+                                        if one_call_key.is_empty() || one_call_key.len() == 32 {
+                                            queue_setting("one_call_api_key", Value::String(crate::config::encrypt_key(&one_call_key)));
+                                        }
+                                        config["decrypted_one_call_api_key"] = Value::String(one_call_key);
+                                    }
+                                });
+
+                                // Alternate real-METAR sources
+                                for (label, field, hint) in ALTERNATE_SOURCE_KEY_FIELDS {
+                                    let decrypted_field = format!("decrypted_{}", field);
+                                    ui.horizontal(|ui| {
+                                        ui.add_space(40.0);
+                                        ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                                            ui.set_min_width(100.0);
+                                            ui.label(RichText::new(label).size(14.0));
+                                        });
+                                        let mut key = config[decrypted_field.as_str()].as_str().unwrap_or("").to_string();
+                                        let key_edit = egui::TextEdit::singleline(&mut key)
+                                            .desired_width(600.0)
+                                            .hint_text(hint)
+                                            .password(self.locked);
+                                        if ui.add_enabled(!self.locked, key_edit).changed() {
+                                            let key = key.trim().to_string();
+                                            queue_setting(field, Value::String(crate::config::encrypt_key(&key)));
+                                            config[decrypted_field.as_str()] = Value::String(key);
+                                        }
+                                    });
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    ui.label(RichText::new(
+                                        "Real METARs and station positions are looked up with CheckWX, then AVWX, when NOAA has no report or can't be reached"
+                                    ).color(TEXT_COLOR).size(12.0));
+                                });
+                            }
+                        });
+                    });
+            
+                    ui.add_space(15.0);
+
+                    // Startup
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Startup").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Start on:");
+                                let previous = self.default_tab;
+                                egui::ComboBox::from_id_source("default_tab")
+                                    .selected_text(self.default_tab.label())
+                                    .width(170.0)
+                                    .show_ui(ui, |ui| {
+                                        for tab in Tab::ALL {
+                                            ui.selectable_value(&mut self.default_tab, tab, tab.label());
+                                        }
+                                    });
+                                if previous != self.default_tab {
+                                    queue_setting("default_tab", serde_json::json!(self.default_tab.key()));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new("Tab shown when METGen opens; the first run opens Configuration")
+                                    .color(TEXT_COLOR)
+                                    .size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+            
+                    // Units Selection
+                    let mut units_changed = false;
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Units").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);  // Same left margin as other elements
+                                ui.label("Preset:");
+                                egui::ComboBox::from_id_source("unit_preset")
+                                    .selected_text(self.unit_preset.label())
+                                    .width(170.0)
+                                    .show_ui(ui, |ui| {
+                                        for preset in UnitPreset::ALL {
+                                            if ui.selectable_value(&mut self.unit_preset, preset, preset.label()).changed() {
+                                                units_changed = true;
+                                            }
+                                        }
+                                    });
+                            });
+
+                            // Individual elements can only be changed under the Custom preset
+                            match self.unit_preset.units() {
+                                Some(units) => self.metar_units = units,
+                                None => {
+                                    let units = &mut self.metar_units;
+                                    ui.horizontal(|ui| {
+                                        ui.add_space(40.0);
+                                        ui.label("Visibility:");
+                                        units_changed |= ui.selectable_value(&mut units.visibility, VisibilityUnit::Meters, "Meters").changed();
+                                        units_changed |= ui.selectable_value(&mut units.visibility, VisibilityUnit::StatuteMiles, "Statute miles").changed();
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.add_space(40.0);
+                                        ui.label("Pressure:");
+                                        units_changed |= ui.selectable_value(&mut units.pressure, PressureUnit::Hectopascals, "hPa (Q)").changed();
+                                        units_changed |= ui.selectable_value(&mut units.pressure, PressureUnit::InchesOfMercury, "inHg (A)").changed();
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.add_space(40.0);
+                                        ui.label("Wind:");
+                                        units_changed |= ui.selectable_value(&mut units.wind, WindUnit::Knots, "Knots").changed();
+                                        units_changed |= ui.selectable_value(&mut units.wind, WindUnit::MetersPerSecond, "m/s").changed();
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.add_space(40.0);
+                                        units_changed |= ui.checkbox(&mut units.sea_level_pressure_remark, "Sea level pressure in remarks (SLP)").changed();
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.add_space(40.0);
+                                        ui.label("Decoded temperature:");
+                                        units_changed |= ui.selectable_value(&mut units.temperature, TemperatureUnit::Celsius, "°C").changed();
+                                        units_changed |= ui.selectable_value(&mut units.temperature, TemperatureUnit::Fahrenheit, "°F").changed();
+                                    });
+                                }
+                            }
+
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                let (label, _) = alternate_units(&self.metar_units);
+                                if ui.checkbox(&mut self.dual_units, format!("Also show reports in {} units", label)).changed() {
+                                    queue_setting("dual_units", serde_json::json!(self.dual_units));
+                                }
+                            });
+
+                            // If units changed, update the profile config file
+                            if units_changed {
+                                let (preset, units) = (self.unit_preset, self.metar_units);
+                                save_worker::queue(move |config| units::store_units(config, preset, &units));
+                            }
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // METAR hold
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Issuance").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            let (mut auto_refresh, mut refresh_minutes) = self.default_auto_refresh();
+                            let previous = (auto_refresh, refresh_minutes);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.checkbox(&mut auto_refresh, "Auto-refresh new stations every");
+                                egui::ComboBox::from_id_source("default_refresh_interval")
+                                    .selected_text(format!("{} min", refresh_minutes))
+                                    .width(70.0)
+                                    .show_ui(ui, |ui| {
+                                        for minutes in REFRESH_INTERVALS {
+                                            ui.selectable_value(&mut refresh_minutes, minutes, format!("{} min", minutes));
+                                        }
+                                    });
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Reports are regenerated in the background and the output panel updates, so the weather evolves during long sessions. Each station's setting can be changed below its METAR"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                            if previous != (auto_refresh, refresh_minutes) {
+                                if let Some(config) = self.config.as_mut() {
+                                    config["auto_refresh"] = serde_json::json!(auto_refresh);
+                                    config["auto_refresh_minutes"] = serde_json::json!(refresh_minutes);
+                                }
+                                queue_setting("auto_refresh", serde_json::json!(auto_refresh));
+                                queue_setting("auto_refresh_minutes", serde_json::json!(refresh_minutes));
+                            }
+                            ui.add_space(5.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Hold reports:");
+                                let previous = self.metar_hold;
+                                egui::ComboBox::from_id_source("metar_hold")
+                                    .selected_text(self.metar_hold.label())
+                                    .width(170.0)
+                                    .show_ui(ui, |ui| {
+                                        for hold in MetarHold::ALL {
+                                            ui.selectable_value(&mut self.metar_hold, hold, hold.label());
+                                        }
+                                    });
+                                if previous != self.metar_hold {
+                                    queue_setting("metar_hold", serde_json::json!(self.metar_hold.key()));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Auto-refresh keeps a report until the next routine issuance time, like a real station"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                            ui.add_space(5.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Observation time:");
+                                let selected = OBSERVATION_TIME_PRESETS
+                                    .iter()
+                                    .find(|(_, minutes)| *minutes == self.observation_minutes.as_slice())
+                                    .map_or("Custom", |(label, _)| label);
+                                let previous = self.observation_minutes.clone();
+                                egui::ComboBox::from_id_source("observation_minutes")
+                                    .selected_text(selected)
+                                    .width(170.0)
+                                    .show_ui(ui, |ui| {
+                                        for (label, minutes) in OBSERVATION_TIME_PRESETS {
+                                            if ui.selectable_label(self.observation_minutes == minutes, label).clicked() {
+                                                self.observation_minutes = minutes.to_vec();
+                                            }
+                                        }
+                                    });
+                                if previous != self.observation_minutes {
+                                    queue_setting("observation_minutes", serde_json::json!(self.observation_minutes));
+                                }
+                            });
+                            let mut use_observation_time = self.config.as_ref()
+                                .and_then(|config| config["use_observation_time"].as_bool())
+                                .unwrap_or(false);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                if ui.checkbox(&mut use_observation_time, "Use the provider's observation time")
+                                    .on_hover_text("Stamp reports with the time the provider observed the conditions instead of the current time")
+                                    .changed()
+                                {
+                                    if let Some(config) = self.config.as_mut() {
+                                        config["use_observation_time"] = serde_json::json!(use_observation_time);
+                                    }
+                                    queue_setting("use_observation_time", serde_json::json!(use_observation_time));
+                                }
+                            });
+                            let mut interpolate = self.config.as_ref()
+                                .and_then(|config| config["interpolate_temperature"].as_bool())
+                                .unwrap_or(false);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                if ui.checkbox(&mut interpolate, "Interpolate temperature between updates (One Call)")
+                                    .on_hover_text("Follow the hourly forecast from the provider's last observation, so frequent auto-refreshes change gradually")
+                                    .changed()
+                                {
+                                    if let Some(config) = self.config.as_mut() {
+                                        config["interpolate_temperature"] = serde_json::json!(interpolate);
+                                    }
+                                    queue_setting("interpolate_temperature", serde_json::json!(interpolate));
+                                }
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Output compatibility
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Output Compatibility").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Mode:");
+                                let previous = self.output_policy;
+                                egui::ComboBox::from_id_source("output_policy")
+                                    .selected_text(self.output_policy.label())
+                                    .width(170.0)
+                                    .show_ui(ui, |ui| {
+                                        for policy in OutputPolicy::ALL {
+                                            ui.selectable_value(&mut self.output_policy, policy, policy.label());
+                                        }
+                                    });
+                                if previous != self.output_policy {
+                                    queue_setting("output_policy", serde_json::json!(self.output_policy.key()));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(match self.output_policy {
+                                    OutputPolicy::StrictIcao => "ICAO Annex 3 groups only, no remarks",
+                                    OutputPolicy::FaaStyle => "US-style report with remarks and forecast",
+                                    OutputPolicy::FsxSafe => "Main body only, for older simulators",
+                                }).color(TEXT_COLOR).size(12.0));
+                            });
+                            ui.add_space(5.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Short identifiers:");
+                                let previous = self.identifier_padding;
+                                egui::ComboBox::from_id_source("identifier_padding")
+                                    .selected_text(self.identifier_padding.label())
+                                    .width(170.0)
+                                    .show_ui(ui, |ui| {
+                                        for padding in IdentifierPadding::ALL {
+                                            ui.selectable_value(&mut self.identifier_padding, padding, padding.label());
+                                        }
+                                    });
+                                if previous != self.identifier_padding {
+                                    queue_setting("identifier_padding", serde_json::json!(self.identifier_padding.key()));
+                                }
+                            });
+                            ui.add_space(5.0);
+                            let mut include_remarks = self.config.as_ref()
+                                .and_then(|config| config["include_remarks"].as_bool())
+                                .unwrap_or(true);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                if ui.checkbox(&mut include_remarks, "Include remarks (RMK)").changed() {
+                                    if let Some(config) = self.config.as_mut() {
+                                        config["include_remarks"] = serde_json::json!(include_remarks);
+                                    }
+                                    queue_setting("include_remarks", serde_json::json!(include_remarks));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Peak wind above 25 kt (PK WND 28032/53), sea level pressure (SLP, see Units) and temperature/dew point in tenths (T01720106). Strict ICAO and FSX-safe output never have remarks"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                            ui.add_space(5.0);
+                            let mut station_type = self.config.as_ref()
+                                .map(StationType::from_config)
+                                .unwrap_or_default();
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Station type:");
+                                let previous = station_type;
+                                egui::ComboBox::from_id_source("station_type")
+                                    .selected_text(station_type.label())
+                                    .width(170.0)
+                                    .show_ui(ui, |ui| {
+                                        for kind in StationType::ALL {
+                                            ui.selectable_value(&mut station_type, kind, kind.label());
+                                        }
+                                    });
+                                if previous != station_type {
+                                    if let Some(config) = self.config.as_mut() {
+                                        config["station_type"] = serde_json::json!(station_type.key());
+                                    }
+                                    queue_setting("station_type", serde_json::json!(station_type.key()));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "AO1 reports only mist, fog and haze; AO2 adds precipitation type but cannot tell drizzle from rain. Both add their remark. Manual drops AUTO and reports SKC"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Estimates for values the provider leaves out
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Missing Data").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            let mut estimate_visibility = self.config.as_ref()
+                                .and_then(|config| config["estimate_visibility"].as_bool())
+                                .unwrap_or(true);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                if ui.checkbox(&mut estimate_visibility, "Estimate visibility when the provider leaves it out").changed() {
+                                    if let Some(config) = self.config.as_mut() {
+                                        config["estimate_visibility"] = serde_json::json!(estimate_visibility);
+                                    }
+                                    queue_setting("estimate_visibility", serde_json::json!(estimate_visibility));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "From the reported weather and humidity, e.g. fog 500 m, heavy rain 4000 m, 10 km when nothing reduces it. Otherwise the group is ////"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                            let mut estimate_cloud_base = self.config.as_ref()
+                                .and_then(|config| config["estimate_cloud_base"].as_bool())
+                                .unwrap_or(true);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                if ui.checkbox(&mut estimate_cloud_base, "Estimate cloud heights").changed() {
+                                    if let Some(config) = self.config.as_mut() {
+                                        config["estimate_cloud_base"] = serde_json::json!(estimate_cloud_base);
+                                    }
+                                    queue_setting("estimate_cloud_base", serde_json::json!(estimate_cloud_base));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Providers give cloud cover only. Layers get a base of 400 ft per °C of temperature/dew point spread (100 to 12,000 ft), e.g. BKN025, which most sims need"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Provider failover
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Provider Failover").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                if ui.checkbox(&mut self.provider_failover, "Try other providers when generation fails").changed() {
+                                    queue_setting("provider_failover", Value::Bool(self.provider_failover));
+                                }
+                            });
+                            let mut moved = None;
+                            let count = self.provider_order.len();
+                            for (position, api) in self.provider_order.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    ui.label(format!("{}. {}", position + 1, api.label()));
+                                    if ui.add_enabled(position > 0, egui::Button::new("Up")).clicked() {
+                                        moved = Some((position, position - 1));
+                                    }
+                                    if ui.add_enabled(position + 1 < count, egui::Button::new("Down")).clicked() {
+                                        moved = Some((position, position + 1));
+                                    }
+                                });
+                            }
+                            if let Some((from, to)) = moved {
+                                self.provider_order.swap(from, to);
+                                let keys: Vec<&str> = self.provider_order.iter().map(|api| api.key()).collect();
+                                queue_setting("provider_order", serde_json::json!(keys));
+                            }
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "The selected provider is tried first, then the others in this order"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // One Call response sections
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("One Call Data").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            let mut excluded = one_call_metar::excluded_parts_from_config(self.config.as_ref().unwrap_or(&Value::Null));
+                            let previous = excluded.clone();
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Request:");
+                                for part in one_call_metar::OPTIONAL_PARTS {
+                                    let mut requested = !excluded.iter().any(|excluded| excluded == part);
+                                    if ui.checkbox(&mut requested, part).changed() {
+                                        if requested {
+                                            excluded.retain(|excluded| excluded != part);
+                                        } else {
+                                            excluded.push(part.to_string());
+                                        }
+                                    }
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Current conditions and the hourly forecast are always requested; leave the rest off to save payload"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                            if previous != excluded {
+                                let value = serde_json::json!(excluded);
+                                if let Some(config) = self.config.as_mut() {
+                                    config["one_call_exclude"] = value.clone();
+                                }
+                                queue_setting("one_call_exclude", value);
+                            }
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Sea conditions from the Open-Meteo marine API
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Marine Data").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            let mut enabled = self.config.as_ref()
+                                .and_then(|config| config["marine_data"].as_bool())
+                                .unwrap_or(false);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                if ui.checkbox(&mut enabled, "Add sea conditions for coastal and water locations").changed() {
+                                    if let Some(config) = self.config.as_mut() {
+                                        config["marine_data"] = serde_json::json!(enabled);
+                                    }
+                                    queue_setting("marine_data", serde_json::json!(enabled));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Wave height, swell and water temperature from Open-Meteo are added to the remarks, e.g. W18/H12 SWELL08/270. Inland positions are unaffected"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Volcanic Ash").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            let mut mode = self.config.as_ref().map(AshMode::from_config).unwrap_or_default();
+                            let previous = mode;
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Advisories:");
+                                egui::ComboBox::from_id_source("volcanic_ash")
+                                    .selected_text(mode.label())
+                                    .width(150.0)
+                                    .show_ui(ui, |ui| {
+                                        for option in AshMode::ALL {
+                                            ui.selectable_value(&mut mode, option, option.label());
+                                        }
+                                    });
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Checks synthesized reports against volcanic ash SIGMETs and One Call alerts. Warn and add VA also puts VA and a VA ADVISORY remark in affected reports"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                            if previous != mode {
+                                let value = serde_json::json!(mode.key());
+                                if let Some(config) = self.config.as_mut() {
+                                    config["volcanic_ash"] = value.clone();
+                                }
+                                queue_setting("volcanic_ash", value);
+                            }
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Severe Weather").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            let mut settings = self.severe_weather_settings();
+                            let previous = settings;
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.checkbox(&mut settings.banner, "Show a warning banner above severe reports");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.checkbox(&mut settings.chime, "Play a chime when a station turns severe");
+                                if ui.small_button("Test").clicked() {
+                                    if let Err(e) = severe_weather::chime() {
+                                        self.notifications.error(e);
+                                    }
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Thunderstorms (TS, VCTS), funnel clouds and tornadoes (FC, +FC), wind or gusts of 64 kt and above, and One Call alerts for severe storms"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                            for (key, enabled, was) in [
+                                ("severe_weather_banner", settings.banner, previous.banner),
+                                ("severe_weather_chime", settings.chime, previous.chime),
+                            ] {
+                                if enabled != was {
+                                    if let Some(config) = self.config.as_mut() {
+                                        config[key] = serde_json::json!(enabled);
+                                    }
+                                    queue_setting(key, serde_json::json!(enabled));
+                                }
+                            }
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("X-Plane").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("X-Plane folder:");
+                                ui.add(egui::TextEdit::singleline(&mut self.xplane_path_input)
+                                    .hint_text("e.g. C:\\X-Plane 12")
+                                    .desired_width(260.0));
+                                if ui.button("Browse...").clicked() {
+                                    if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                                        self.xplane_path_input = folder.display().to_string();
+                                    }
+                                }
+                                if ui.button("Apply").clicked() {
+                                    let path = self.xplane_path_input.trim().to_string();
+                                    if let Some(config) = &mut self.config {
+                                        config["xplane_path"] = Value::String(path.clone());
+                                    }
+                                    queue_setting("xplane_path", Value::String(path));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Export > Write X-Plane METAR.rwx copies the reports here. Turn off real weather downloads in X-Plane so it reads the file. Leave empty to only write METAR.rwx next to the config."
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Read aloud speed
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Read Aloud").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Speed:");
+                                let slider = egui::Slider::new(&mut self.speech_rate, speech::MIN_SPEECH_RATE..=speech::MAX_SPEECH_RATE)
+                                    .step_by(0.1)
+                                    .suffix("x");
+                                let response = ui.add(slider);
+                                // Only write the file once the slider is let go
+                                if response.drag_released() || (response.changed() && !response.dragged()) {
+                                    queue_setting("speech_rate", serde_json::json!(self.speech_rate));
+                                }
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // NOAA prefetch
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("NOAA Prefetch").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Region:");
+                                let selected = noaa_cache::REGIONS
+                                    .get(self.prefetch_region)
+                                    .map_or("Custom area", |(name, _)| name);
+                                egui::ComboBox::from_id_source("prefetch_region")
+                                    .selected_text(selected)
+                                    .width(170.0)
+                                    .show_ui(ui, |ui| {
+                                        for (index, (name, _)) in noaa_cache::REGIONS.iter().enumerate() {
+                                            ui.selectable_value(&mut self.prefetch_region, index, *name);
+                                        }
+                                        ui.selectable_value(&mut self.prefetch_region, noaa_cache::REGIONS.len(), "Custom area");
+                                    });
+                                let button = egui::Button::new("Download");
+                                if self.request_button(ui, RequestKind::Prefetch, button) {
+                                    self.prefetch_region();
+                                }
+                            });
+                            if self.prefetch_region >= noaa_cache::REGIONS.len() {
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    ui.label("Area:");
+                                    ui.add(egui::TextEdit::singleline(&mut self.prefetch_area)
+                                        .hint_text("south,west,north,east")
+                                        .desired_width(200.0));
+                                });
+                            }
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Downloads all current METARs in the region at once. Station lookups then use them instead of asking NOAA each time."
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Disk cache
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Cache").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            let usage = self.cache_usage.get(&self.egui_ctx, disk_cache::usage).copied();
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(match usage {
+                                    Some(usage) => format!("Using {:.1} MB of {} MB", usage as f64 / (1024.0 * 1024.0), self.cache_max_mb),
+                                    None => format!("Measuring... (limit {} MB)", self.cache_max_mb),
+                                });
+                                if ui.button("Refresh").clicked() {
+                                    self.cache_usage.reload();
+                                }
+                                if ui.button("Clear Cache").clicked() {
+                                    self.start_disk_task(|| {
+                                        disk_cache::clear()
+                                            .map(|()| "Cache cleared".to_string())
+                                            .map_err(|e| format!("Failed to clear the cache: {}", e))
+                                    });
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Size limit:");
+                                let previous = self.cache_max_mb;
+                                egui::ComboBox::from_id_source("cache_max_mb")
+                                    .selected_text(format!("{} MB", self.cache_max_mb))
+                                    .width(90.0)
+                                    .show_ui(ui, |ui| {
+                                        for limit in CACHE_SIZE_LIMITS {
+                                            ui.selectable_value(&mut self.cache_max_mb, limit, format!("{} MB", limit));
+                                        }
+                                    });
+                                if previous != self.cache_max_mb {
+                                    disk_cache::set_max_size_mb(self.cache_max_mb);
+                                    self.cache_usage.reload();
+                                    queue_setting("cache_max_mb", serde_json::json!(self.cache_max_mb));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Prefetched METARs, airport and place coordinates; the oldest entries are removed past the limit"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // History retention
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("History").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            let size = self.history_size.get(&self.egui_ctx, history::size).copied();
+                            let previous = self.history_retention;
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(match size {
+                                    Some(size) => format!("Using {:.1} MB", size as f64 / (1024.0 * 1024.0)),
+                                    None => "Measuring...".to_string(),
+                                });
+                                if ui.button("Prune Now").clicked() {
+                                    let retention = self.history_retention;
+                                    self.start_disk_task(move || match history::prune(retention) {
+                                        Ok(removed) => Ok(format!("Removed {} reports from the history", removed)),
+                                        Err(e) => Err(format!("Failed to prune the history: {}", e)),
+                                    });
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Keep at most:");
+                                let entries_label = |limit: Option<usize>| match limit {
+                                    Some(limit) => format!("{} reports", limit),
+                                    None => "Unlimited".to_string(),
+                                };
+                                egui::ComboBox::from_id_source("history_max_entries")
+                                    .selected_text(entries_label(self.history_retention.max_entries))
+                                    .width(120.0)
+                                    .show_ui(ui, |ui| {
+                                        for limit in HISTORY_ENTRY_LIMITS {
+                                            ui.selectable_value(&mut self.history_retention.max_entries, limit, entries_label(limit));
+                                        }
+                                    });
+                                ui.label("for");
+                                let days_label = |limit: Option<u32>| match limit {
+                                    Some(days) if days % 365 == 0 => format!("{} year{}", days / 365, if days == 365 { "" } else { "s" }),
+                                    Some(days) => format!("{} days", days),
+                                    None => "Forever".to_string(),
+                                };
+                                egui::ComboBox::from_id_source("history_max_days")
+                                    .selected_text(days_label(self.history_retention.max_days))
+                                    .width(90.0)
+                                    .show_ui(ui, |ui| {
+                                        for limit in HISTORY_DAY_LIMITS {
+                                            ui.selectable_value(&mut self.history_retention.max_days, limit, days_label(limit));
+                                        }
+                                    });
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.checkbox(&mut self.history_retention.auto_prune, "Prune automatically in the background");
+                            });
+                            if previous != self.history_retention {
+                                let retention = self.history_retention;
+                                save_worker::queue(move |config| {
+                                    config["history_max_entries"] = serde_json::json!(retention.max_entries.unwrap_or(0));
+                                    config["history_max_days"] = serde_json::json!(retention.max_days.unwrap_or(0));
+                                    config["history_auto_prune"] = Value::Bool(retention.auto_prune);
+                                });
+                                history::set_retention(retention);
+                                self.history_size.reload();
+                                self.usage_stats.reload();
+                            }
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Reports kept for the Diagnostics tab; older ones are removed at startup and every few hundred reports"
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Global hotkey
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Global Hotkey").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label("Regenerate:");
+                                ui.add(egui::TextEdit::singleline(&mut self.hotkey_input)
+                                    .hint_text("e.g. Ctrl+Shift+F9")
+                                    .desired_width(140.0));
+                                if ui.button("Apply").clicked() {
+                                    let text = self.hotkey_input.trim().to_string();
+                                    if let Some(config) = &mut self.config {
+                                        config["regenerate_hotkey"] = Value::String(text.clone());
+                                    }
+                                    self.update_hotkey();
+                                    queue_setting("regenerate_hotkey", Value::String(text));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Regenerates the current station even while the sim has focus. Leave empty to disable."
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Gamepad button
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Gamepad Button").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            if !gamepad::SUPPORTED {
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    ui.label(RichText::new(
+                                        "This build has no gamepad support. Build with --features gamepad to enable it."
+                                    ).color(TEXT_COLOR).size(12.0));
+                                });
+                            } else if self.gamepad_presses.is_none() {
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    ui.label(RichText::new("No gamepad input is available on this system.").color(TEXT_COLOR).size(12.0));
+                                });
+                            } else {
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    match &self.gamepad_binding {
+                                        Some(binding) => ui.label(format!("Regenerate: button {} on {}", binding.button, binding.device)),
+                                        None => ui.label("Regenerate: not bound"),
+                                    };
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    if self.binding_gamepad {
+                                        ui.spinner();
+                                        ui.label("Press a button on your joystick or gamepad...");
+                                        if ui.button("Cancel").clicked() {
+                                            self.binding_gamepad = false;
+                                        }
+                                    } else {
+                                        if ui.button("Bind Button").clicked() {
+                                            self.binding_gamepad = true;
+                                        }
+                                        if self.gamepad_binding.is_some() && ui.button("Clear").clicked() {
+                                            self.gamepad_binding = None;
+                                            if let Some(config) = &mut self.config {
+                                                config["gamepad_binding"] = Value::Null;
+                                            }
+                                            queue_setting("gamepad_binding", Value::Null);
+                                        }
+                                    }
+                                });
+                            }
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Crash reports
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Crash Reports").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                let mut opt_in = self.config.as_ref()
+                                    .and_then(|c| c["crash_reports_opt_in"].as_bool())
+                                    .unwrap_or(false);
+                                if ui.checkbox(&mut opt_in, "Offer to submit crash reports").changed() {
+                                    if let Some(config) = &mut self.config {
+                                        config["crash_reports_opt_in"] = Value::Bool(opt_in);
+                                    }
+                                    queue_setting("crash_reports_opt_in", Value::Bool(opt_in));
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(RichText::new(
+                                    "Crash reports are always saved in the crash_reports folder. They are only sent when you choose Submit."
+                                ).color(TEXT_COLOR).size(12.0));
+                            });
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    // Profiles
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Profiles").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(40.0);
+                                ui.label(format!("Active profile: {}", active_profile()));
+                            });
+                            if !self.locked {
+                                ui.horizontal(|ui| {
+                                    ui.add_space(40.0);
+                                    ui.label("New profile:");
+                                    ui.add(egui::TextEdit::singleline(&mut self.new_profile_name)
+                                        .desired_width(120.0)
+                                        .hint_text("name"));
+                                    if ui.button("Create").clicked() {
+                                        let name = self.new_profile_name.trim().to_string();
+                                        if !is_valid_profile_name(&name) {
+                                            self.notifications.error("Profile names may only use letters, digits, '-' and '_'".to_string());
+                                        } else if self.profiles.contains(&name) {
+                                            self.notifications.error(format!("Profile {} already exists", name));
+                                        } else {
+                                            self.new_profile_name.clear();
+                                            self.switch_profile(&name);
+                                        }
+                                    }
+                                });
+                            }
+                        });
+                    });
+                });
+        });
+    }
+
+    // Decodes a pasted METAR or TAF, with a minimums check and runway wind components
+    fn draw_parse(&mut self, ui: &mut egui::Ui) {
+        ui.vertical(|ui| {
+            ui.heading(RichText::new("Parse").color(CYAN_GLOW));
+            ui.add_space(10.0);
+            egui::CollapsingHeader::new(RichText::new("Unit Conversions").color(MAGENTA_GLOW))
+                .id_source("unit_conversions")
+                .show(ui, |ui| self.conversions.draw(ui));
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.label("Operation:");
+                let previous = self.ops_profile;
+                egui::ComboBox::from_id_source("ops_profile")
+                    .selected_text(self.ops_profile.label())
+                    .show_ui(ui, |ui| {
+                        for profile in OpsProfile::ALL {
+                            ui.selectable_value(&mut self.ops_profile, profile, profile.label());
+                        }
+                    });
+                if previous != self.ops_profile {
+                    queue_setting("ops_profile", serde_json::json!(self.ops_profile.key()));
+                }
+                if self.ops_profile == OpsProfile::Rotor {
+                    ui.label("Field elevation (ft):");
+                    ui.add(egui::TextEdit::singleline(&mut self.field_elevation).desired_width(60.0));
+                }
+            });
+            ui.add_space(10.0);
+            ui.add(egui::TextEdit::multiline(&mut self.parse_input)
+                .font(egui::TextStyle::Monospace)
+                .hint_text("Paste a METAR or TAF")
+                .desired_rows(3)
+                .desired_width(f32::INFINITY));
+            ui.add_space(10.0);
+
+            let input = self.parse_input.trim().to_uppercase();
+            if input.is_empty() {
+                return;
+            }
+            let decoded = decode_cached(&mut self.decoded_reports, &input);
+
+            egui::ScrollArea::vertical()
+                .id_source("parse_scroll")
+                .show(ui, |ui| {
+                    ui.group(|ui| {
+                        if let Some(icon) = metar_decoder::condition_icon(&decoded) {
+                            ui.label(RichText::new(icon).size(32.0));
+                        }
+                        egui::Grid::new("parse_decoded").num_columns(2).spacing([12.0, 4.0]).show(ui, |ui| {
+                            for (element, text) in metar_decoder::describe(&decoded, self.metar_units.temperature) {
+                                ui.label(RichText::new(element).color(MAGENTA_GLOW));
+                                let text = if self.ops_profile.emphasizes(element) {
+                                    RichText::new(text).strong().color(CYAN_GLOW)
+                                } else {
+                                    RichText::new(text).color(TEXT_COLOR)
+                                };
+                                ui.add(egui::Label::new(text).wrap(true));
+                                ui.end_row();
+                            }
+                            let elevation = self.field_elevation.trim().parse::<f64>().ok();
+                            for (element, text) in ops_profile::advisories(&decoded, self.ops_profile, elevation) {
+                                ui.label(RichText::new(element).color(MAGENTA_GLOW));
+                                ui.add(egui::Label::new(RichText::new(text).strong().color(CYAN_GLOW)).wrap(true));
+                                ui.end_row();
+                            }
+                            if let Some(category) = flight_category(&input) {
+                                ui.label(RichText::new("Category").color(MAGENTA_GLOW));
+                                ui.label(RichText::new(category.label()).strong().color(flight_category_color(category)));
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                    ui.add_space(10.0);
+
+                    // Minimums check
+                    let metric = self.metar_units.visibility == VisibilityUnit::Meters;
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Minimums").color(MAGENTA_GLOW));
+                            ui.horizontal(|ui| {
+                                ui.label("Ceiling (ft):");
+                                ui.add(egui::TextEdit::singleline(&mut self.minimum_ceiling).desired_width(60.0));
+                                ui.label(if metric { "Visibility (m):" } else { "Visibility (SM):" });
+                                ui.add(egui::TextEdit::singleline(&mut self.minimum_visibility).desired_width(60.0));
+                            });
+
+                            let minimum_ceiling = self.minimum_ceiling.trim().parse::<u32>().ok();
+                            let minimum_visibility = self.minimum_visibility.trim().parse::<f64>().ok()
+                                .map(|value| if metric { value } else { units::statute_miles_to_meters(value) });
+                            if minimum_ceiling.is_none() && minimum_visibility.is_none() {
+                                return;
+                            }
+
+                            let mut shortfalls = Vec::new();
+                            if let (Some(minimum), Some(ceiling)) = (minimum_ceiling, decoded.ceiling_ft()) {
+                                if ceiling < minimum {
+                                    shortfalls.push(format!("ceiling {} ft is below {} ft", ceiling, minimum));
+                                }
+                            }
+                            if let (Some(minimum), Some(visibility)) = (minimum_visibility, decoded.visibility_m) {
+                                if visibility < minimum {
+                                    shortfalls.push(if metric {
+                                        format!("visibility {:.0} m is below {:.0} m", visibility, minimum)
+                                    } else {
+                                        format!(
+                                            "visibility {:.2} SM is below {:.2} SM",
+                                            units::meters_to_statute_miles(visibility),
+                                            units::meters_to_statute_miles(minimum)
+                                        )
+                                    });
+                                }
+                            }
+                            if shortfalls.is_empty() {
+                                ui.colored_label(Color32::GREEN, "At or above minimums");
+                            } else {
+                                ui.colored_label(Color32::RED, format!("Below minimums: {}", shortfalls.join(", ")));
+                            }
+                        });
+                    });
+
+                    ui.add_space(10.0);
+
+                    // Runway wind components
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Crosswind").color(MAGENTA_GLOW));
+                            ui.horizontal(|ui| {
+                                ui.label("Runway:");
+                                ui.add(egui::TextEdit::singleline(&mut self.runway)
+                                    .hint_text("e.g. 27L or 268")
+                                    .desired_width(80.0));
+                            });
+
+                            let (Some(heading), Some(wind)) = (runway_heading(&self.runway), decoded.wind.as_ref()) else {
+                                return;
+                            };
+                            let Some(direction) = wind.direction else {
+                                ui.label(format!(
+                                    "Wind direction is variable; the crosswind can reach {:.0} kt",
+                                    wind.gust_kt.unwrap_or(wind.speed_kt)
+                                ));
+                                return;
+                            };
+                            let component_text = |speed: f64| {
+                                let (headwind, crosswind) = metar_decoder::wind_components(direction, speed, heading);
+                                format!(
+                                    "{} {:.0} kt, crosswind {:.0} kt from the {}",
+                                    if headwind >= -0.5 { "headwind" } else { "tailwind" },
+                                    headwind.abs(),
+                                    crosswind.abs(),
+                                    if crosswind >= 0.0 { "right" } else { "left" }
+                                )
+                            };
+                            ui.label(format!("Runway heading {:03}°: {}", heading, component_text(wind.speed_kt)));
+                            if let Some(gust) = wind.gust_kt {
+                                ui.label(format!("In gusts: {}", component_text(gust)));
+                            }
+                        });
+                    });
+                });
+        });
+    }
+
+    // Usage statistics from the local history. Nothing here is sent anywhere.
+    fn draw_about(&mut self, ui: &mut egui::Ui) {
+        ui.vertical(|ui| {
+            ui.heading(RichText::new("About").color(CYAN_GLOW));
+            ui.add_space(15.0);
+
+            egui::ScrollArea::vertical()
+                .id_source("about_scroll")
+                .show(ui, |ui| {
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Build").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            egui::Grid::new("build_info").num_columns(2).spacing([20.0, 4.0]).show(ui, |ui| {
+                                ui.label("Version:");
+                                ui.label(RichText::new(env!("CARGO_PKG_VERSION")).color(CYAN_GLOW));
+                                ui.end_row();
+                                ui.label("Target:");
+                                ui.label(RichText::new(env!("METGEN_BUILD_TARGET")).color(CYAN_GLOW));
+                                ui.end_row();
+                                ui.label("Profile:");
+                                ui.label(RichText::new(env!("METGEN_BUILD_PROFILE")).color(CYAN_GLOW));
+                                ui.end_row();
+                                ui.label("Features:");
+                                ui.label(RichText::new(if gamepad::SUPPORTED { "gamepad" } else { "none" }).color(CYAN_GLOW));
+                                ui.end_row();
+                            });
+                            ui.add_space(5.0);
+                            ui.horizontal(|ui| {
+                                if ui.button("Source Code").clicked() {
+                                    ui.ctx().open_url(egui::OpenUrl::new_tab(env!("CARGO_PKG_REPOSITORY")));
+                                }
+                                if ui.button("Open Config Folder").clicked() {
+                                    if let Err(e) = open_folder(paths::config_dir()) {
+                                        self.notifications.error(e);
+                                    }
+                                }
+                                if paths::data_dir() != paths::config_dir() && ui.button("Open Data Folder").clicked() {
+                                    if let Err(e) = open_folder(paths::data_dir()) {
+                                        self.notifications.error(e);
+                                    }
+                                }
+                            });
+                            ui.label(RichText::new(format!(
+                                "Configuration and profiles: {}\nHistory, caches and crash reports: {}",
+                                paths::config_dir().display(),
+                                paths::data_dir().display()
+                            )).color(TEXT_COLOR).size(12.0));
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("Data Sources").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            for (source, credit, url) in DATA_CREDITS {
+                                ui.horizontal(|ui| {
+                                    ui.hyperlink_to(RichText::new(source).color(CYAN_GLOW), url);
+                                    ui.label(RichText::new(credit).color(TEXT_COLOR).size(12.0));
+                                });
+                            }
+                        });
+                    });
+
+                    ui.add_space(15.0);
+
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("License").color(MAGENTA_GLOW));
+                            ui.add_space(10.0);
+                            ui.label(RichText::new(
+                                "METGen is free software under the GNU Affero General Public License v3. \
+                                 You may redistribute and modify it under its terms; it comes with no warranty."
+                            ).color(TEXT_COLOR));
+                            ui.add_space(5.0);
+                            egui::CollapsingHeader::new("Full License Text")
+                                .id_source("license_text")
+                                .show(ui, |ui| {
+                                    ui.add(egui::Label::new(RichText::new(LICENSE_TEXT).monospace().size(11.0)).wrap(true));
+                                });
+                        });
+                    });
+                });
+        });
+    }
+
+    fn draw_diagnostics(&mut self, ui: &mut egui::Ui) {
+        let stats = self.usage_stats.get(&self.egui_ctx, || history::usage_stats(&history::load_history())).cloned();
+        let empty = if stats.is_some() { "No reports yet" } else { "Reading the history..." };
+        let stats = stats.unwrap_or_default();
+        let mut refresh = false;
+
+        ui.vertical(|ui| {
+            ui.heading(RichText::new("Diagnostics").color(CYAN_GLOW));
+            ui.label(RichText::new("Computed from the local history only; nothing is sent anywhere.").color(TEXT_COLOR).size(12.0));
+            ui.add_space(15.0);
+
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    ui.heading(RichText::new("Usage").color(MAGENTA_GLOW));
+                    ui.add_space(10.0);
+                    ui.label(format!("METARs generated: {}", stats.total));
+                    ui.label(format!("Stored in: {}", storage::get().description()));
+                    if let Some(since) = stats.since {
+                        ui.label(format!("Since: {}", since.format("%Y-%m-%d")));
+                    }
+                    ui.label(format!("API calls saved by cache: {}", stats.cache_hits));
+                });
+            });
+
+            ui.add_space(15.0);
+
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    ui.heading(RichText::new("Providers").color(MAGENTA_GLOW));
+                    ui.add_space(10.0);
+                    if stats.providers.is_empty() {
+                        ui.label(empty);
+                    }
+                    for (provider, count) in &stats.providers {
+                        ui.label(format!("{}: {}", provider, count));
+                    }
+                });
+            });
+
+            ui.add_space(15.0);
+
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    ui.heading(RichText::new("Most Generated Airports").color(MAGENTA_GLOW));
+                    ui.add_space(10.0);
+                    if stats.top_airports.is_empty() {
+                        ui.label(empty);
+                    }
+                    for (icao, count) in &stats.top_airports {
+                        ui.label(format!("{}: {}", icao, count));
+                    }
+                });
+            });
+
+            ui.add_space(15.0);
+
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    ui.heading(RichText::new("Report History").color(MAGENTA_GLOW));
+                    ui.add_space(10.0);
+                    let period_label = |days: Option<u32>| match days {
+                        Some(1) => "Last day".to_string(),
+                        Some(days) => format!("Last {} days", days),
+                        None => "All time".to_string(),
+                    };
+                    ui.horizontal(|ui| {
+                        ui.label("ICAO:");
+                        ui.add(egui::TextEdit::singleline(&mut self.report_search_icao)
+                            .hint_text("All stations")
+                            .desired_width(80.0));
+                        egui::ComboBox::from_id_source("report_search_days")
+                            .selected_text(period_label(self.report_search_days))
+                            .width(110.0)
+                            .show_ui(ui, |ui| {
+                                for days in REPORT_SEARCH_PERIODS {
+                                    ui.selectable_value(&mut self.report_search_days, days, period_label(days));
+                                }
+                            });
+                        if ui.button("Search").clicked() {
+                            let icao = self.report_search_icao.trim().to_uppercase();
+                            let query = ReportQuery {
+                                icao: Some(icao).filter(|icao| !icao.is_empty()),
+                                since: self.report_search_days.map(|days| Utc::now() - chrono::Duration::days(days as i64)),
+                                limit: Some(REPORT_SEARCH_LIMIT),
+                            };
+                            self.report_search_results.start(&self.egui_ctx, move || history::search(&query));
+                        }
+                    });
+                    if self.report_search_results.is_pending() {
+                        ui.add_space(5.0);
+                        ui.label("Searching...");
+                    }
+                    if let Some(results) = self.report_search_results.poll() {
+                        ui.add_space(5.0);
+                        if results.is_empty() {
+                            ui.label("No matching reports");
+                        } else if results.len() == REPORT_SEARCH_LIMIT {
+                            ui.label(RichText::new(format!("Newest {} matching reports", REPORT_SEARCH_LIMIT)).color(TEXT_COLOR).size(12.0));
+                        }
+                        for entry in results {
+                            ui.label(RichText::new(format!(
+                                "{}  {}  {}",
+                                entry.time.format("%Y-%m-%d %H:%MZ"),
+                                entry.provider,
+                                entry.metar
+                            )).monospace().size(12.0));
+                        }
+                    }
+                });
+            });
+
+            ui.add_space(15.0);
+
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    ui.heading(RichText::new("Space Weather").color(MAGENTA_GLOW));
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if self.request_button(ui, RequestKind::SpaceWeather, egui::Button::new("Check")) {
+                            self.check_space_weather();
+                        }
+                        ui.label(RichText::new("Fetched from NOAA SWPC only when you press Check").color(TEXT_COLOR).size(12.0));
+                    });
+                    if let Some(space_weather) = &self.space_weather {
+                        ui.add_space(5.0);
+                        let color = match space_weather.geomagnetic {
+                            0 => Color32::GREEN,
+                            1 | 2 => Color32::YELLOW,
+                            _ => Color32::RED,
+                        };
+                        ui.label(RichText::new(format!("Geomagnetic storm: {}", space_weather.geomagnetic_label())).color(color));
+                        ui.label(format!(
+                            "Solar radiation S{}, radio blackout R{}",
+                            space_weather.radiation, space_weather.radio_blackout
+                        ));
+                        if let Some(observed) = &space_weather.observed {
+                            ui.label(RichText::new(format!("Observed {} UTC", observed)).color(TEXT_COLOR).size(12.0));
+                        }
+                        match space_weather.navigation_note() {
+                            Some(note) => ui.colored_label(Color32::YELLOW, note),
+                            None => ui.label("No GPS or ADS-B degradation expected"),
+                        };
+                    }
+                });
+            });
+
+            ui.add_space(10.0);
+            refresh = ui.button("Refresh").clicked();
+        });
+
+        if refresh {
+            self.usage_stats.reload();
+        }
+    }
+
+    fn generate_metar_from_icao(&mut self) {
+        self.notifications.clear_banners();
+        self.existing_metar = None;
+        
+        if self.input_icao.is_empty() {
+            self.notifications.error("Please enter an ICAO code".to_string());
+            return;
+        }
+
+        let icao = self.input_icao.to_uppercase();
+        let settings = self.generation_settings();
+        self.start_request(RequestKind::IcaoLookup, move || {
+            // Check for existing METAR
+            // A prefetched report saves the request
+            let existing_metar = noaa_cache::cached_metar(&icao)
+                .or_else(|| input_handler::poll_metar(&icao, &settings.alternate_sources));
+            if let Some(existing_metar) = existing_metar {
+                return RequestOutcome::ExistingMetar(existing_metar);
+            }
+
+            // No existing METAR, generate one
+            match input_handler::resolve_icao_to_lat_lon(&icao, &settings.alternate_sources) {
+                Some((lat, lon)) => report(&settings, icao, lat, lon, InputMethod::Icao),
+                None => RequestOutcome::Failed(format!("Could not resolve ICAO code: {}", icao)),
+            }
+        });
+    }
+
+    // Fetches or synthesizes a report for every station of the route, one after another
+    fn generate_route(&mut self) {
+        self.notifications.clear_banners();
+        self.existing_metar = None;
+
+        let stations = parse_route(&self.route_input);
+        if stations.is_empty() {
+            self.notifications.error("Please enter the ICAO codes of the route".to_string());
+            return;
+        }
+
+        let settings = self.generation_settings();
+        self.start_request(RequestKind::Route, move || {
+            RequestOutcome::Route(
+                stations
+                    .into_iter()
+                    .map(|icao| (icao.clone(), route_report(&settings, icao)))
+                    .collect(),
+            )
+        });
+    }
+
+    // Downloads every current METAR in the chosen region into the NOAA cache
+    fn prefetch_region(&mut self) {
+        self.notifications.clear_banners();
+
+        let bounds = match noaa_cache::REGIONS.get(self.prefetch_region) {
+            Some((_, bounds)) => *bounds,
+            None => match noaa_cache::parse_bounding_box(&self.prefetch_area) {
+                Some(bounds) => bounds,
+                None => {
+                    self.notifications.error("Please enter the area as south,west,north,east in degrees".to_string());
+                    return;
+                }
+            },
+        };
+
+        self.start_request(RequestKind::Prefetch, move || {
+            match input_handler::fetch_noaa_metars_in_area(bounds) {
+                Some(metars) => match noaa_cache::store(&metars) {
+                    Ok(cached) => RequestOutcome::Prefetched { downloaded: metars.len(), cached },
+                    Err(e) => RequestOutcome::Failed(e),
+                },
+                None => RequestOutcome::Failed("Failed to download METARs from NOAA".to_string()),
+            }
+        });
+    }
+
+    fn check_space_weather(&mut self) {
+        self.notifications.error_banner = None;
+        self.start_request(RequestKind::SpaceWeather, || match space_weather::fetch() {
+            Some(space_weather) => RequestOutcome::SpaceWeather(space_weather),
+            None => RequestOutcome::Failed("Failed to get space weather from NOAA SWPC".to_string()),
+        });
+    }
+
+    fn generate_metar_from_coords(&mut self) {
+        self.notifications.clear_banners();
+        
+        if self.input_lon.trim().is_empty() {
+            self.split_coordinate_pair();
+        }
+        if self.input_lat.is_empty() || self.input_lon.is_empty() {
+            self.notifications.error("Please enter both latitude and longitude".to_string());
+            return;
+        }
+
+        let lat = match parse_coordinate(&self.input_lat, Axis::Latitude) {
+            Some(lat) => lat,
+            None => {
+                self.notifications.error("Invalid latitude format (e.g. 48.3537, 48,3537 or 48°21'13\"N)".to_string());
+                return;
+            }
+        };
+
+        let lon = match parse_coordinate(&self.input_lon, Axis::Longitude) {
+            Some(lon) => lon,
+            None => {
+                self.notifications.error("Invalid longitude format (e.g. -122.31, -122,31 or 122°18'36\"W)".to_string());
+                return;
+            }
+        };
+
+        if let Some((lat, lon)) = input_handler::validate_lat_lon(lat, lon) {
+            let icao = self.input_icao.to_uppercase();
+            let settings = self.generation_settings();
+            self.start_request(RequestKind::LatLon, move || {
+                report(&settings, icao, lat, lon, InputMethod::LatLon)
+            });
+        } else {
+            self.notifications.error("Invalid latitude/longitude values".to_string());
+        }
+    }
+
+    fn generate_metar_from_location(&mut self) {
+        self.notifications.clear_banners();
         
         if self.input_location.is_empty() {
-            self.error_message = Some("Please enter a location".to_string());
+            self.notifications.error("Please enter a location".to_string());
+            return;
+        }
+
+        let icao = self.input_icao.to_uppercase();
+        let location = self.input_location.clone();
+        let settings = self.generation_settings();
+
+        // Map links, plus codes and plain coordinates don't need geocoding
+        if let Some((lat, lon)) = parse_map_location(&location).or_else(|| parse_coordinate_pair(&location)) {
+            self.start_request(RequestKind::Location, move || {
+                report(&settings, icao, lat, lon, InputMethod::Location)
+            });
+            return;
+        }
+
+        self.start_request(RequestKind::Location, move || {
+            match input_handler::resolve_freeform_input(&location, &settings.api_key) {
+                Some((lat, lon)) => report(&settings, icao, lat, lon, InputMethod::Location),
+                None => RequestOutcome::Failed(format!("Could not resolve location: {}", location)),
+            }
+        });
+    }
+
+    fn generate_metar_for_saved_airport(&mut self, airport: &UserAirport) {
+        self.notifications.clear_banners();
+        let icao = airport.icao.clone();
+        let (lat, lon) = (airport.latitude, airport.longitude);
+        let settings = self.generation_settings();
+        self.start_request(RequestKind::SavedAirport(icao.clone()), move || {
+            report(&settings, icao, lat, lon, InputMethod::Icao)
+        });
+    }
+
+    fn generate_metar_for_bookmark(&mut self, bookmark: &Bookmark) {
+        self.notifications.clear_banners();
+        let identifier = bookmark.identifier.clone();
+        let (lat, lon) = (bookmark.latitude, bookmark.longitude);
+        let settings = self.generation_settings();
+        self.start_request(RequestKind::Bookmark(identifier.clone()), move || {
+            report(&settings, identifier, lat, lon, InputMethod::Bookmark)
+        });
+    }
+
+    // Synthesizes a METAR for a station that already has a real report,
+    // falling back to the station position reported with it
+    fn synthesize_over_existing_metar(&mut self, existing: &RealMetar) {
+        self.notifications.clear_banners();
+        let icao = existing.icao.clone();
+        let fallback = existing.coordinates;
+        let settings = self.generation_settings();
+        self.start_request(RequestKind::Synthesize, move || {
+            match input_handler::resolve_icao_to_lat_lon(&icao, &settings.alternate_sources).or(fallback) {
+                Some((lat, lon)) => report(&settings, icao, lat, lon, InputMethod::Icao),
+                None => RequestOutcome::Failed(format!("Could not determine the location of {}", icao)),
+            }
+        });
+    }
+
+    // Splits a combined "lat, lon" entry in the latitude field across both fields
+    fn split_coordinate_pair(&mut self) {
+        if let Some((lat, lon)) = parse_coordinate_pair(&self.input_lat) {
+            self.input_lat = format!("{:.6}", lat);
+            self.input_lon = format!("{:.6}", lon);
+        }
+    }
+
+    // Add helper function to clear input fields
+    fn clear_input_fields(&mut self) {
+        self.input_icao.clear();
+        self.input_lat.clear();
+        self.input_lon.clear();
+        self.input_location.clear();
+    }
+
+    // Snapshot of everything a background request needs to synthesize a METAR
+    fn generation_settings(&self) -> GenerationSettings {
+        let key = |name: &str| {
+            self.config.as_ref()
+                .and_then(|config| config[name].as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        GenerationSettings {
+            api: self.selected_api,
+            api_key: key("decrypted_api_key"),
+            one_call_api_key: key("decrypted_one_call_api_key"),
+            units: self.metar_units,
+            policy: self.output_policy,
+            format: FormatOptions {
+                observation_minutes: self.observation_minutes.clone(),
+                ..FormatOptions::from_config(self.config.as_ref().unwrap_or(&Value::Null))
+            },
+            one_call_exclude: one_call_metar::excluded_parts_from_config(self.config.as_ref().unwrap_or(&Value::Null)),
+            language: input_handler::owm_language(self.config.as_ref().and_then(|config| config["owm_language"].as_str())),
+            failover: if self.provider_failover { self.provider_order.clone() } else { Vec::new() },
+            identifier_padding: self.identifier_padding,
+            marine_data: self.config.as_ref().and_then(|config| config["marine_data"].as_bool()).unwrap_or(false),
+            volcanic_ash: self.config.as_ref().map(AshMode::from_config).unwrap_or_default(),
+            interpolate_temperature: self.config.as_ref()
+                .and_then(|config| config["interpolate_temperature"].as_bool())
+                .unwrap_or(false),
+            station_type: self.config.as_ref().map(StationType::from_config).unwrap_or_default(),
+            alternate_sources: self.config.as_ref().map(AlternateSources::from_config).unwrap_or_default(),
+        }
+    }
+
+    // Runs a request on a worker thread. Only one request runs at a time; the
+    // outcome is picked up by poll_pending_request on a later frame.
+    fn start_request(&mut self, kind: RequestKind, request: impl FnOnce() -> RequestOutcome + Send + 'static) {
+        if self.pending_request.is_some() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let ctx = self.egui_ctx.clone();
+        std::thread::spawn(move || {
+            // The receiver is gone if the request was cancelled
+            let _ = sender.send(request());
+            ctx.request_repaint();
+        });
+
+        self.pending_request = Some(PendingRequest {
+            kind,
+            started: Instant::now(),
+            receiver,
+        });
+    }
+
+    fn cancel_request(&mut self) {
+        if self.pending_request.take().is_some() {
+            self.notifications.success("Request cancelled".to_string());
+        }
+    }
+
+    // Runs a prune or cache clear in the background; its message is shown by
+    // poll_disk_tasks
+    fn start_disk_task(&mut self, task: impl FnOnce() -> Result<String, String> + Send + 'static) {
+        let receiver = spawn_background(&self.egui_ctx, task);
+        self.disk_tasks.push(receiver);
+    }
+
+    fn poll_disk_tasks(&mut self) {
+        let mut finished = Vec::new();
+        self.disk_tasks.retain(|task| match task.try_recv() {
+            Ok(result) => {
+                finished.push(result);
+                false
+            }
+            Err(mpsc::TryRecvError::Empty) => true,
+            Err(mpsc::TryRecvError::Disconnected) => false,
+        });
+        for result in finished {
+            match result {
+                Ok(message) => self.notifications.success(message),
+                Err(e) => self.notifications.error(e),
+            }
+            // Either can change the sizes and the history statistics
+            self.cache_usage.reload();
+            self.history_size.reload();
+            self.usage_stats.reload();
+        }
+    }
+
+    fn poll_pending_request(&mut self) {
+        let Some(pending) = &self.pending_request else { return };
+        let outcome = match pending.receiver.try_recv() {
+            Ok(outcome) => outcome,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => RequestOutcome::Failed("Request failed unexpectedly".to_string()),
+        };
+        self.pending_request = None;
+        self.apply_outcome(outcome);
+    }
+
+    fn poll_scheduler(&mut self) {
+        let finished = self.scheduler.as_ref().map(|scheduler| scheduler.finished()).unwrap_or_default();
+        for (_, outcome) in finished {
+            self.apply_outcome(outcome);
+        }
+    }
+
+    fn apply_outcome(&mut self, outcome: RequestOutcome) {
+        match outcome {
+            RequestOutcome::ExistingMetar(existing_metar) => {
+                self.existing_metar = Some(existing_metar);
+                self.notifications.success("Found existing METAR. Please choose an option with the buttons.".to_string());
+            }
+            RequestOutcome::Report(report) => {
+                self.existing_metar = None;
+                // Only typed identifiers can disagree with their coordinates
+                let region_warning = match (report.input_method, report.coordinates) {
+                    (InputMethod::LatLon | InputMethod::Location, Some((lat, lon))) => {
+                        icao_region::mismatch_warning(&report.icao, lat, lon)
+                    }
+                    _ => None,
+                };
+                let (api, fallback) = (report.api, report.fallback);
+                let severe = newly_severe(self.session_metar(&report.icao), &report.metar);
+                self.open_session(report);
+                if severe {
+                    self.chime();
+                }
+                self.notifications.success(match (api, fallback) {
+                    (Some(api), Some(fallback)) => format!(
+                        "METAR generated with the {} API after the {} API failed",
+                        fallback.label(), api.label()
+                    ),
+                    _ => "METAR generated successfully".to_string(),
+                });
+                if let Some(warning) = region_warning {
+                    self.notifications.error(warning);
+                }
+                self.clear_input_fields();
+            }
+            RequestOutcome::Refreshed { icao, fallback, metar_source, result } => {
+                let mut severe = false;
+                if let Some(session) = self.sessions.iter_mut().find(|session| session.icao == icao) {
+                    session.last_generated = Instant::now();
+                    session.stale = false;
+                    match result {
+                        Ok((metar, observed_at)) => {
+                            severe = newly_severe(Some(&session.metar), &metar);
+                            self.refresh_errors.resolve(&icao);
+                            record_history(&icao, provider_name(fallback.or(session.api), metar_source), &metar);
+                            session.metar = metar;
+                            session.observed_at = observed_at;
+                            session.fallback = fallback;
+                            session.metar_source = metar_source;
+                            self.briefing_dirty = true;
+                        }
+                        Err(e) => {
+                            let retry_in = session.auto_refresh.then(|| Duration::from_secs(session.refresh_minutes * 60));
+                            if self.refresh_errors.push(&icao, e.clone(), retry_in) {
+                                self.notifications.record(Severity::Error, format!("{}: {}", icao, e));
+                            }
+                        }
+                    }
+                }
+                if severe {
+                    self.chime();
+                }
+            }
+            RequestOutcome::Route(stations) => {
+                self.existing_metar = None;
+                self.route.clear();
+                // One chime for the whole route
+                let mut severe = false;
+                for (icao, outcome) in stations {
+                    let error = match outcome {
+                        RequestOutcome::Report(report) => {
+                            severe |= newly_severe(self.session_metar(&report.icao), &report.metar);
+                            self.open_session(report);
+                            None
+                        }
+                        RequestOutcome::Failed(e) => Some(e),
+                        // Route requests only produce reports and failures
+                        _ => None,
+                    };
+                    self.route.push(RouteStation { icao, error });
+                }
+                // Start the output panel at the departure
+                if let Some(first) = self.route.first() {
+                    if let Some(index) = self.sessions.iter().position(|session| session.icao == first.icao) {
+                        self.active_session = index;
+                    }
+                }
+                let failed = self.route.iter().filter(|station| station.error.is_some()).count();
+                if failed == 0 {
+                    self.notifications.success(format!("Generated METARs for {} route stations", self.route.len()));
+                } else {
+                    self.notifications.error(format!("{} of {} route stations failed", failed, self.route.len()));
+                }
+                if severe {
+                    self.chime();
+                }
+            }
+            RequestOutcome::Prefetched { downloaded, cached } => {
+                self.notifications.success(format!(
+                    "Downloaded {} METARs from NOAA ({} stations cached)",
+                    downloaded, cached
+                ));
+            }
+            RequestOutcome::SpaceWeather(space_weather) => self.space_weather = Some(space_weather),
+            RequestOutcome::Failed(e) => self.notifications.error(e),
+        }
+    }
+
+    // Draws a request button, replaced by a spinner, elapsed time and Cancel
+    // while its request is running. Returns true when clicked.
+    fn request_button(&mut self, ui: &mut egui::Ui, kind: RequestKind, button: egui::Button) -> bool {
+        if let Some(pending) = &self.pending_request {
+            if pending.kind == kind {
+                let elapsed = pending.started.elapsed().as_secs_f32();
+                ui.spinner();
+                ui.label(RichText::new(format!("{:.1}s", elapsed)).color(TEXT_COLOR));
+                if ui.small_button("Cancel").clicked() {
+                    self.cancel_request();
+                }
+                return false;
+            }
+        }
+        ui.add_enabled(self.pending_request.is_none(), button).clicked()
+    }
+
+    fn generate_button(&mut self, ui: &mut egui::Ui, kind: RequestKind) -> bool {
+        let button = egui::Button::new(RichText::new("Generate").color(GENERATE_BUTTON_TEXT))
+            .fill(GENERATE_BUTTON_COLOR);
+        self.request_button(ui, kind, button)
+    }
+
+    // Auto-refresh setting and interval new stations start with, from
+    // "auto_refresh" (off by default) and "auto_refresh_minutes" in the config
+    fn default_auto_refresh(&self) -> (bool, u64) {
+        let config = self.config.as_ref().unwrap_or(&Value::Null);
+        (
+            config["auto_refresh"].as_bool().unwrap_or(false),
+            config["auto_refresh_minutes"].as_u64().filter(|&minutes| minutes > 0).unwrap_or(REFRESH_INTERVALS[1]),
+        )
+    }
+
+    // Current report of a station's session, if it has one
+    fn session_metar(&self, icao: &str) -> Option<&str> {
+        self.sessions.iter().find(|session| session.icao == icao).map(|session| session.metar.as_str())
+    }
+
+    fn severe_weather_settings(&self) -> SevereWeatherSettings {
+        SevereWeatherSettings::from_config(self.config.as_ref().unwrap_or(&Value::Null))
+    }
+
+    // Plays the severe weather chime, if enabled
+    fn chime(&mut self) {
+        if self.severe_weather_settings().chime {
+            if let Err(e) = severe_weather::chime() {
+                self.notifications.error(e);
+            }
+        }
+    }
+
+    // Shows a report in the session for its station, opening a new session if needed
+    fn open_session(&mut self, report: Report) {
+        let Report { icao, coordinates, api, fallback, metar_source, input_method, metar, observed_at } = report;
+        // Bookmark identifiers aren't ICAO codes, so they stay out of the recent ICAO list
+        let name = if input_method == InputMethod::Bookmark {
+            self.saved_bookmarks()
+                .iter()
+                .find(|bookmark| bookmark.identifier == icao)
+                .map(|bookmark| bookmark.name.clone())
+        } else {
+            self.remember_icao(&icao);
+            self.saved_airports()
+                .iter()
+                .find(|airport| airport.icao.eq_ignore_ascii_case(&icao))
+                .and_then(|airport| airport.name.clone())
+        };
+        record_history(&icao, provider_name(fallback.or(api), metar_source), &metar);
+        self.briefing_dirty = true;
+        if let Some(index) = self.sessions.iter().position(|session| session.icao == icao) {
+            let session = &mut self.sessions[index];
+            session.coordinates = coordinates.or(session.coordinates);
+            session.api = api;
+            session.fallback = fallback;
+            session.metar_source = metar_source;
+            session.name = name;
+            session.input_method = input_method;
+            session.metar = metar;
+            session.observed_at = observed_at;
+            session.last_generated = Instant::now();
+            session.stale = false;
+            self.active_session = index;
+        } else {
+            let (auto_refresh, refresh_minutes) = self.default_auto_refresh();
+            self.sessions.push(StationSession {
+                icao,
+                name,
+                coordinates,
+                api,
+                metar_source,
+                input_method,
+                metar,
+                observed_at,
+                auto_refresh,
+                refresh_minutes,
+                last_generated: Instant::now(),
+                stale: false,
+                fallback,
+            });
+            self.active_session = self.sessions.len() - 1;
+        }
+    }
+
+    // Queues a regeneration of a session's report with its own provider, or a
+    // re-poll of the real METAR sources for existing METARs
+    fn refresh_session(&mut self, index: usize, priority: Priority) {
+        let Some(session) = self.sessions.get(index) else { return };
+        let Some(scheduler) = &self.scheduler else { return };
+        let icao = session.icao.clone();
+        let source = session.api.zip(session.coordinates);
+        let provider = session.api.map_or(input_handler::NOAA, ApiType::provider);
+        let mut settings = self.generation_settings();
+        scheduler.schedule(&session.icao, provider, priority, move || {
+            let mut fallback = None;
+            let mut metar_source = None;
+            let result = match source {
+                Some((api, (lat, lon))) => {
+                    settings.api = api;
+                    settings.synthesize(&icao, lat, lon).map(|synthesized| {
+                        fallback = (synthesized.api != api).then_some(synthesized.api);
+                        (synthesized.metar, synthesized.observed_at)
+                    })
+                }
+                None => input_handler::poll_metar(&icao, &settings.alternate_sources)
+                    .map(|existing| {
+                        let report = Report::real(existing);
+                        metar_source = report.metar_source;
+                        (report.metar, report.observed_at)
+                    })
+                    .ok_or_else(|| format!("No METAR available for {}", icao)),
+            };
+            RequestOutcome::Refreshed { icao, fallback, metar_source, result }
+        });
+    }
+
+    // Starts a refresh for the first session that is due, when no other request is running
+    fn run_auto_refresh(&mut self, ctx: &egui::Context) {
+        // The scheduler ignores stations that already have a refresh queued
+        for index in 0..self.sessions.len() {
+            let session = &self.sessions[index];
+            if session.refresh_due(self.metar_hold) {
+                let priority = if session.stale {
+                    Priority::Immediate
+                } else if index == self.active_session {
+                    Priority::Refresh
+                } else {
+                    Priority::Batch
+                };
+                self.refresh_session(index, priority);
+            }
+        }
+
+        if self.sessions.iter().any(|session| session.auto_refresh) {
+            ctx.request_repaint_after(Duration::from_secs(30));
+        }
+    }
+
+    // Starts reading the session's METAR aloud, or stops a readback in progress
+    fn toggle_read_aloud(&mut self, index: usize) {
+        if let Some(mut speech) = self.speech.take() {
+            let _ = speech.kill();
+            let _ = speech.wait();
             return;
         }
 
-        if let Some(config) = &self.config {
-            if let Some((lat, lon)) = input_handler::resolve_freeform_input(
-                &self.input_location,
-                config["decrypted_api_key"].as_str().unwrap(),
-            ) {
-                self.last_input_method = InputMethod::Location;
-                self.saved_lat = lat;
-                self.saved_lon = lon;
-                self.saved_icao = self.input_icao.to_uppercase();
-                self.generate_metar_with_coordinates(lat, lon);
-                self.clear_input_fields();
+        let text = speech::spoken_metar(&self.sessions[index].metar);
+        match speech::speak(&text, self.speech_rate) {
+            Ok(child) => self.speech = Some(child),
+            Err(e) => self.notifications.error(e),
+        }
+    }
+
+    fn poll_speech(&mut self, ctx: &egui::Context) {
+        if let Some(speech) = self.speech.as_mut() {
+            if !matches!(speech.try_wait(), Ok(None)) {
+                self.speech = None;
             } else {
-                self.error_message = Some(format!("Could not resolve location: {}", self.input_location));
+                ctx.request_repaint_after(Duration::from_millis(250));
             }
         }
     }
 
-    fn generate_metar_for_saved_airport(&mut self, airport: &UserAirport) {
-        self.error_message = None;
-        self.success_message = None;
-        self.input_icao = airport.icao.clone();
-        self.last_input_method = InputMethod::Icao;
-        self.generate_metar_with_coordinates(airport.latitude, airport.longitude);
-        self.clear_input_fields();
+    fn draw_qr_window(&mut self, ctx: &egui::Context) {
+        let Some(session) = self.sessions.get(self.active_session) else {
+            self.show_qr_code = false;
+            return;
+        };
+
+        if self.qr_code.as_ref().is_none_or(|(text, _)| *text != session.metar) {
+            self.qr_code = qr_code_image(&session.metar).map(|image| {
+                let texture = ctx.load_texture("metar_qr_code", image, egui::TextureOptions::NEAREST);
+                (session.metar.clone(), texture)
+            });
+        }
+
+        let mut open = self.show_qr_code;
+        egui::Window::new(format!("{} QR Code", session.icao))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                match &self.qr_code {
+                    Some((_, texture)) => {
+                        ui.image((texture.id(), Vec2::splat(300.0)));
+                    }
+                    None => {
+                        ui.colored_label(Color32::RED, "This METAR is too long to show as a QR code");
+                    }
+                }
+            });
+        self.show_qr_code = open;
     }
 
-    // Add helper function to clear input fields
-    fn clear_input_fields(&mut self) {
-        self.input_icao.clear();
-        self.input_lat.clear();
-        self.input_lon.clear();
-        self.input_location.clear();
+    // Offers the oldest pending crash report for review and submission. Nothing
+    // is sent from here; submitting opens a prefilled issue in the browser.
+    fn draw_crash_report_window(&mut self, ctx: &egui::Context) {
+        let path = self.crash_reports[0].clone();
+        let report = match std::fs::read_to_string(&path) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Failed to read crash report {}: {}", path.display(), e);
+                self.crash_reports.remove(0);
+                return;
+            }
+        };
+
+        let mut handled = false;
+        egui::Window::new("METGen closed unexpectedly")
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new("A crash report was saved last time METGen closed unexpectedly:").color(TEXT_COLOR));
+                ui.label(RichText::new(path.display().to_string()).color(CYAN_GLOW));
+                ui.add_space(5.0);
+                ui.label("Please review it below. Submitting opens a prefilled issue that you can edit before posting.");
+                ui.add_space(5.0);
+                egui::ScrollArea::vertical()
+                    .id_source("crash_report_scroll")
+                    .max_height(250.0)
+                    .show(ui, |ui| {
+                        ui.add(egui::Label::new(RichText::new(&report).monospace().size(12.0)).wrap(true));
+                    });
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Submit Report").clicked() {
+                        match crash_report::issue_url(&report) {
+                            Some(url) => ctx.open_url(egui::OpenUrl::new_tab(url)),
+                            None => self.notifications.error("Failed to prepare the crash report for submission".to_string()),
+                        }
+                        handled = true;
+                    }
+                    if ui.button("Dismiss").clicked() {
+                        handled = true;
+                    }
+                });
+            });
+
+        if handled {
+            crash_report::mark_handled(&path);
+            self.crash_reports.remove(0);
+        }
     }
 
-    fn generate_metar_with_coordinates(&mut self, lat: f64, lon: f64) {
-        if let Some(config) = &self.config {
-            let api_key = match self.selected_api {
-                ApiType::Standard => config["decrypted_api_key"].as_str(),
-                ApiType::OneCall => config["decrypted_one_call_api_key"].as_str(),
-            };
+    // Saves an API key the same way the Configuration tab does
+    fn save_api_key(&mut self, api_key: &str, one_call: bool) {
+        let (field, decrypted_field) = if one_call {
+            ("one_call_api_key", "decrypted_one_call_api_key")
+        } else {
+            ("api_key", "decrypted_api_key")
+        };
+        queue_setting(field, Value::String(crate::config::encrypt_key(api_key)));
+        if let Some(config) = self.config.as_mut() {
+            config[decrypted_field] = Value::String(api_key.to_string());
+        }
+    }
+
+    fn poll_key_verification(&mut self) {
+        let Some(verification) = self.key_verification.as_mut() else {
+            return;
+        };
+        while let Ok(check) = verification.receiver.try_recv() {
+            verification.attempts += 1;
+            if matches!(check, KeyCheck::Active) {
+                self.notifications.success(format!("Your {} is active", verification.key_name()));
+            }
+            verification.last_check = Some(check);
+        }
+    }
+
+    // Guided setup of OpenWeather keys: links to each step, saving a pasted
+    // key, and checking it until OpenWeather activates it
+    fn draw_key_helper_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_key_helper;
+        egui::Window::new("API Key Setup")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.heading(RichText::new("1. Create a free OpenWeather account").color(MAGENTA_GLOW));
+                ui.label(RichText::new("Only an email address is needed. The free tier is plenty for METGen.").color(TEXT_COLOR));
+                link_buttons(ui, "Open Sign-up Page", OWM_SIGN_UP_URL);
+                ui.add_space(10.0);
+
+                ui.heading(RichText::new("2. Copy your API key").color(MAGENTA_GLOW));
+                ui.label(RichText::new(
+                    "After you confirm your email, a default key is listed on the API keys page."
+                ).color(TEXT_COLOR));
+                link_buttons(ui, "Open API Keys Page", OWM_API_KEYS_URL);
+                ui.add_space(10.0);
+
+                ui.heading(RichText::new("3. Paste it here").color(MAGENTA_GLOW));
+                ui.add(egui::TextEdit::singleline(&mut self.key_helper_input)
+                    .desired_width(320.0)
+                    .hint_text("32 characters"));
+                // Keys copied from a web page often pick up spaces or line breaks
+                let api_key: String = self.key_helper_input.chars().filter(|c| !c.is_whitespace()).collect();
+                let valid = api_key.len() == 32 && api_key.chars().all(|c| c.is_ascii_alphanumeric());
+                if !api_key.is_empty() && !valid {
+                    ui.label(RichText::new(format!("Keys are 32 letters and digits (this one has {} characters)", api_key.len()))
+                        .color(Color32::RED).size(12.0));
+                }
+                if self.locked {
+                    ui.label(RichText::new("Configuration is locked. API keys cannot be edited.").color(TEXT_COLOR).size(12.0));
+                }
+                let mut save_as = None;
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(valid && !self.locked, egui::Button::new("Save as Standard Key")).clicked() {
+                        save_as = Some(false);
+                    }
+                    if ui.add_enabled(valid && !self.locked, egui::Button::new("Save as One Call Key")).clicked() {
+                        save_as = Some(true);
+                    }
+                });
+                ui.add_space(10.0);
+
+                ui.heading(RichText::new("Optional: One Call 3.0").color(MAGENTA_GLOW));
+                ui.label(RichText::new(
+                    "Subscribe to \"One Call by Call\" on the One Call 3.0 page. The first 1,000 calls a day are free; \
+                     set a daily limit of 1,000 calls in your billing plan to never be charged. The same key can be used for both."
+                ).color(TEXT_COLOR));
+                link_buttons(ui, "Open One Call 3.0 Page", OWM_ONE_CALL_URL);
+
+                if let Some(one_call) = save_as {
+                    self.save_api_key(&api_key, one_call);
+                    self.key_helper_input = api_key;
+                    self.key_verification = Some(KeyVerification::start(self.key_helper_input.clone(), one_call, ctx.clone()));
+                }
+
+                let mut stop = false;
+                if let Some(verification) = &self.key_verification {
+                    ui.add_space(10.0);
+                    ui.separator();
+                    let status = match &verification.last_check {
+                        None => (TEXT_COLOR, format!("Checking your {}...", verification.key_name())),
+                        Some(KeyCheck::Active) => (Color32::GREEN, format!("Your {} is active. You're all set!", verification.key_name())),
+                        Some(KeyCheck::Rejected(message)) => (TEXT_COLOR, format!(
+                            "Not active yet: {}. New keys can take a couple of hours to activate, and One Call keys also need \
+                             the subscription. Checked {} time(s), checking again every minute.",
+                            message.trim_end_matches('.'), verification.attempts
+                        )),
+                        Some(KeyCheck::Failed(message)) => (TEXT_COLOR, format!(
+                            "{}. Checked {} time(s), retrying every minute.", message, verification.attempts
+                        )),
+                    };
+                    ui.label(RichText::new(status.1).color(status.0));
+                    let finished = matches!(verification.last_check, Some(KeyCheck::Active))
+                        || verification.attempts >= KEY_CHECK_ATTEMPTS;
+                    if !finished && ui.button("Stop Checking").clicked() {
+                        stop = true;
+                    }
+                }
+                if stop {
+                    self.key_verification = None;
+                }
+            });
+        self.show_key_helper = open;
+    }
 
-            if let Some(key) = api_key {
-                let units = match self.selected_units {
-                    Units::Metric => "metric",
-                    Units::Imperial => "imperial",
+    // Brings back the stations that were open when the app was last closed
+    fn restore_briefing(&mut self) {
+        let saved = briefing::load();
+        let now = Utc::now();
+        self.sessions = saved
+            .stations
+            .into_iter()
+            .map(|station| {
+                let api = |key: Option<String>| {
+                    key.and_then(|key| ApiType::ALL.into_iter().find(|api| api.key() == key))
                 };
+                let age = (now - station.generated).to_std().unwrap_or_default();
+                let (fallback, api) = (api(station.fallback), api(station.provider));
+                // Briefings saved before sources were recorded only had NOAA reports
+                let metar_source = api.is_none().then(|| {
+                    station.source.as_deref().and_then(MetarSource::from_key).unwrap_or(MetarSource::Noaa)
+                });
+                StationSession {
+                    icao: station.icao,
+                    name: station.name,
+                    coordinates: station.coordinates,
+                    api,
+                    metar_source,
+                    fallback,
+                    input_method: InputMethod::ALL
+                        .into_iter()
+                        .find(|method| method.key() == station.input_method)
+                        .unwrap_or_default(),
+                    metar: station.metar,
+                    observed_at: station.observed,
+                    auto_refresh: station.auto_refresh,
+                    refresh_minutes: station.refresh_minutes,
+                    last_generated: Instant::now().checked_sub(age).unwrap_or_else(Instant::now),
+                    stale: false,
+                }
+            })
+            .collect();
+        self.active_session = saved.active.min(self.sessions.len().saturating_sub(1));
+    }
 
-                let result = match self.selected_api {
-                    ApiType::Standard => {
-                        metar_generator::generate_metar(&self.input_icao, lat, lon, key, units)
-                    },
-                    ApiType::OneCall => {
-                        if let Some(weather_data) = one_call_metar::fetch_weather_data(lat, lon, key) {
-                            let parsed = one_call_metar::parse_weather_data(&weather_data);
-                            Some(one_call_metar::generate_metar(&self.input_icao, &parsed, units))
-                        } else {
-                            None
+    fn save_briefing(&mut self) {
+        if !self.briefing_dirty {
+            return;
+        }
+        self.briefing_dirty = false;
+        let briefing = self.briefing();
+        disk_writer::write(move || {
+            if let Err(e) = briefing::save(&briefing) {
+                eprintln!("{}", e);
+            }
+        });
+    }
+
+    // The open stations, with wall-clock generation times
+    fn briefing(&self) -> Briefing {
+        let now = Utc::now();
+        Briefing {
+            active: self.active_session,
+            stations: self
+                .sessions
+                .iter()
+                .map(|session| BriefingStation {
+                    icao: session.icao.clone(),
+                    name: session.name.clone(),
+                    coordinates: session.coordinates,
+                    provider: session.api.map(|api| api.key().to_string()),
+                    fallback: session.fallback.map(|api| api.key().to_string()),
+                    source: session.metar_source.map(|source| source.key().to_string()),
+                    input_method: session.input_method.key().to_string(),
+                    metar: session.metar.clone(),
+                    observed: session.observed_at,
+                    generated: now - chrono::Duration::from_std(session.last_generated.elapsed()).unwrap_or_default(),
+                    auto_refresh: session.auto_refresh,
+                    refresh_minutes: session.refresh_minutes,
+                })
+                .collect(),
+        }
+    }
+
+    // Copies the briefing to the clipboard, or writes it to a file the user picks
+    fn export_briefing(&mut self, ctx: &egui::Context, format: ExportFormat, to_file: bool) {
+        let text = briefing::export(&self.briefing(), format, Utc::now());
+        if !to_file {
+            ctx.output_mut(|o| o.copied_text = text);
+            self.notifications.success(format!("Briefing copied as {}", format.label()));
+            return;
+        }
+
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.label(), &[format.extension()])
+            .set_file_name(format!("metgen_briefing.{}", format.extension()))
+            .save_file()
+        else {
+            return;
+        };
+        match std::fs::write(&path, text) {
+            Ok(()) => self.notifications.success(format!("Briefing saved to {}", path.display())),
+            Err(e) => self.notifications.error(format!("Failed to save briefing: {}", e)),
+        }
+    }
+
+    // Saves a station's report as an MSFS weather preset file the user picks
+    fn export_msfs(&mut self, index: usize) {
+        let Some(session) = self.sessions.get(index) else { return };
+        let name = format!("METGen {} {}", session.icao, Utc::now().format("%d%H%MZ"));
+        let preset = msfs::format_wpr(&name, &metar_decoder::decode_metar(&session.metar));
+
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("MSFS weather preset", &["WPR"])
+            .set_file_name(format!("METGen_{}.WPR", session.icao))
+            .save_file()
+        else {
+            return;
+        };
+        match std::fs::write(&path, preset) {
+            Ok(()) => self.notifications.success(format!("Weather preset saved to {}", path.display())),
+            Err(e) => self.notifications.error(format!("Failed to save weather preset: {}", e)),
+        }
+    }
+
+    // Saves the last route's stations, in route order, as a plain text briefing
+    fn save_route_briefing(&mut self) {
+        let mut briefing = self.briefing();
+        let mut stations = std::mem::take(&mut briefing.stations);
+        briefing.stations = self
+            .route
+            .iter()
+            .filter_map(|route_station| {
+                let index = stations.iter().position(|station| station.icao == route_station.icao)?;
+                Some(stations.swap_remove(index))
+            })
+            .collect();
+        if briefing.stations.is_empty() {
+            self.notifications.error("The route's stations have been closed".to_string());
+            return;
+        }
+
+        let format = ExportFormat::PlainText;
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.label(), &[format.extension()])
+            .set_file_name(format!("metgen_route.{}", format.extension()))
+            .save_file()
+        else {
+            return;
+        };
+        match std::fs::write(&path, briefing::export(&briefing, format, Utc::now())) {
+            Ok(()) => self.notifications.success(format!("Route briefing saved to {}", path.display())),
+            Err(e) => self.notifications.error(format!("Failed to save route briefing: {}", e)),
+        }
+    }
+
+    // Decodes the report on screen, the real METAR on offer or the current
+    // station's, into a summary sentence and one row per element
+    fn draw_report_summary(&mut self, ui: &mut egui::Ui) {
+        ui.heading(RichText::new("Decoded Report").color(CYAN_GLOW));
+        ui.add_space(10.0);
+
+        let metar = match (&self.existing_metar, self.sessions.get(self.active_session)) {
+            (Some(existing), _) => existing.raw.as_str(),
+            (None, Some(session)) => session.metar.as_str(),
+            (None, None) => {
+                ui.label(RichText::new("Generate or fetch a METAR to see it decoded here.").color(TEXT_COLOR));
+                return;
+            }
+        };
+        let decoded = decode_cached(&mut self.decoded_reports, metar);
+
+        egui::ScrollArea::vertical()
+            .id_source("summary_scroll")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(icon) = metar_decoder::condition_icon(&decoded) {
+                        ui.label(RichText::new(icon).size(32.0));
+                    }
+                    ui.add(egui::Label::new(
+                        RichText::new(metar_decoder::summary(&decoded, self.metar_units.temperature)).color(TEXT_COLOR).size(16.0)
+                    ).wrap(true));
+                });
+                ui.add_space(10.0);
+                ui.group(|ui| {
+                    egui::Grid::new("summary_decoded").num_columns(2).spacing([12.0, 4.0]).show(ui, |ui| {
+                        for (element, text) in metar_decoder::describe(&decoded, self.metar_units.temperature) {
+                            ui.label(RichText::new(element).color(MAGENTA_GLOW));
+                            ui.add(egui::Label::new(RichText::new(text).color(TEXT_COLOR)).wrap(true));
+                            ui.end_row();
                         }
-                    },
-                };
+                        if let Some(category) = flight_category(metar) {
+                            ui.label(RichText::new("Category").color(MAGENTA_GLOW));
+                            ui.label(RichText::new(category.label()).strong().color(flight_category_color(category)));
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+    }
 
-                match result {
-                    Some(metar) => {
-                        self.generated_metar = metar;
-                        self.success_message = Some("METAR generated successfully".to_string());
-                    },
-                    None => {
-                        self.error_message = Some("Failed to generate METAR".to_string());
+    // Recent error and success messages, newest first
+    fn draw_notifications_window(&mut self, ctx: &egui::Context) {
+        self.notifications.mark_read();
+        let mut open = true;
+        let mut clear = false;
+        egui::Window::new("Notifications")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    let mut empty = true;
+                    for notification in self.notifications.history() {
+                        empty = false;
+                        let color = match notification.severity {
+                            Severity::Error => Color32::RED,
+                            Severity::Success => Color32::GREEN,
+                        };
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(RichText::new(notification.time.format("%H:%M:%S").to_string()).color(TEXT_COLOR).size(12.0));
+                            ui.label(RichText::new(&notification.message).color(color));
+                        });
                     }
+                    if empty {
+                        ui.label(RichText::new("No messages yet").color(TEXT_COLOR));
+                    }
+                });
+                ui.add_space(6.0);
+                if ui.button("Clear").clicked() {
+                    clear = true;
                 }
-            } else {
-                self.error_message = Some("API key not found in configuration".to_string());
+            });
+        if clear {
+            self.notifications.clear_history();
+        }
+        self.show_notifications = open;
+    }
+
+    // Writes the open stations to METAR.rwx, copied into the X-Plane folder if one is configured
+    fn export_xplane(&mut self) {
+        let xplane_path = self.config.as_ref().and_then(xplane::xplane_path_from_config);
+        match xplane::export(&self.briefing(), xplane_path.as_deref()) {
+            Ok(written) => {
+                let files: Vec<String> = written.iter().map(|path| path.display().to_string()).collect();
+                self.notifications.success(format!(
+                    "Wrote {} station(s) to {}",
+                    self.sessions.len(),
+                    files.join(" and ")
+                ));
             }
-        } else {
-            self.error_message = Some("Configuration not loaded".to_string());
+            Err(e) => self.notifications.error(e),
+        }
+    }
+
+    // Regenerates every station whose report is older than its refresh interval
+    fn refresh_all(&mut self) {
+        let due: Vec<usize> = (0..self.sessions.len())
+            .filter(|&index| {
+                let session = &self.sessions[index];
+                session.last_generated.elapsed() >= Duration::from_secs(session.refresh_minutes * 60)
+            })
+            .collect();
+        if due.is_empty() {
+            self.notifications.success("All stations are up to date".to_string());
+            return;
+        }
+        for &index in &due {
+            let priority = if index == self.active_session { Priority::Immediate } else { Priority::Batch };
+            self.refresh_session(index, priority);
+        }
+        self.notifications.success(format!("Refreshing {} station(s)", due.len()));
+    }
+
+    // Compact always-on-top window with one station's METAR and flight category,
+    // meant for a second monitor. It follows the station's session, including
+    // its auto-refresh.
+    fn draw_widget_window(&mut self, ctx: &egui::Context) {
+        let Some(icao) = self.widget_station.clone() else { return };
+        let Some(index) = self.sessions.iter().position(|session| session.icao == icao) else {
+            // The session was closed
+            self.widget_station = None;
+            return;
+        };
+
+        let mut selected = icao.clone();
+        let mut close = false;
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("metar_widget"),
+            egui::ViewportBuilder::default()
+                .with_title("METGen Widget")
+                .with_inner_size([480.0, 140.0])
+                .with_always_on_top(),
+            |ctx, _class| {
+                egui::CentralPanel::default()
+                    .frame(egui::Frame::none().fill(BACKGROUND).inner_margin(egui::style::Margin::same(8.0)))
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_source("widget_station")
+                                .selected_text(&icao)
+                                .width(80.0)
+                                .show_ui(ui, |ui| {
+                                    for station in self.sessions.iter().map(|session| &session.icao) {
+                                        ui.selectable_value(&mut selected, station.clone(), station);
+                                    }
+                                });
+                            if let Some(icon) = metar_decoder::condition_icon(&decode_cached(&mut self.decoded_reports, &self.sessions[index].metar)) {
+                                ui.label(RichText::new(icon).size(18.0));
+                            }
+                            match flight_category(&self.sessions[index].metar) {
+                                Some(category) => {
+                                    ui.label(RichText::new(category.label()).strong().color(flight_category_color(category)));
+                                }
+                                None => {
+                                    ui.label(RichText::new("---").color(TEXT_COLOR));
+                                }
+                            }
+                            let session = &mut self.sessions[index];
+                            if ui.checkbox(&mut session.auto_refresh, "Auto-refresh").changed() {
+                                self.briefing_dirty = true;
+                            }
+                            ui.label(RichText::new(format!(
+                                "{} min ago",
+                                session.last_generated.elapsed().as_secs() / 60
+                            )).color(TEXT_COLOR).size(12.0));
+                        });
+                        ui.add_space(4.0);
+                        if let Some(name) = &self.sessions[index].name {
+                            ui.label(RichText::new(name).color(MAGENTA_GLOW).size(13.0));
+                        }
+                        ui.add(egui::Label::new(
+                            RichText::new(&self.sessions[index].metar).monospace().size(15.0).color(CYAN_GLOW)
+                        ).wrap(true));
+                    });
+
+                close = ctx.input(|input| input.viewport().close_requested());
+            },
+        );
+
+        if close {
+            self.widget_station = None;
+        } else if selected != icao {
+            self.widget_station = Some(selected);
+        }
+    }
+
+    fn draw_sessions(&mut self, ui: &mut egui::Ui) {
+        // Station tabs
+        let mut selected = self.active_session;
+        let mut closed = None;
+        let mut refresh_all = false;
+        let mut export = None;
+        let mut export_xplane = false;
+        ui.horizontal(|ui| {
+            for (index, session) in self.sessions.iter().enumerate() {
+                let is_selected = index == selected;
+                let decoded = decode_cached(&mut self.decoded_reports, &session.metar);
+                let text = match metar_decoder::condition_icon(&decoded) {
+                    Some(icon) => format!("{} {}", icon, session.icao),
+                    None => session.icao.clone(),
+                };
+                let label = RichText::new(text)
+                    .color(if is_selected { MAGENTA_GLOW } else { CYAN_GLOW });
+                if ui.selectable_label(is_selected, label).clicked() {
+                    selected = index;
+                }
+                if ui.small_button("x").on_hover_text("Close station").clicked() {
+                    closed = Some(index);
+                }
+                ui.add_space(6.0);
+            }
+            if self.sessions.len() > 1
+                && ui.button("Refresh All").on_hover_text("Regenerate every station older than its refresh interval").clicked()
+            {
+                refresh_all = true;
+            }
+            ui.menu_button("Export", |ui| {
+                for format in ExportFormat::ALL {
+                    if ui.button(format!("Copy as {}", format.label())).clicked() {
+                        export = Some((format, false));
+                        ui.close_menu();
+                    }
+                    if ui.button(format!("Save as {}...", format.label())).clicked() {
+                        export = Some((format, true));
+                        ui.close_menu();
+                    }
+                }
+                ui.separator();
+                if ui.button("Write X-Plane METAR.rwx")
+                    .on_hover_text("Write the reports as X-Plane's real weather file, and copy it to the X-Plane folder if one is set")
+                    .clicked()
+                {
+                    export_xplane = true;
+                    ui.close_menu();
+                }
+            });
+        });
+        if refresh_all {
+            self.refresh_all();
+        }
+        if let Some((format, to_file)) = export {
+            self.export_briefing(ui.ctx(), format, to_file);
+        }
+        if export_xplane {
+            self.export_xplane();
+        }
+        if selected != self.active_session {
+            self.briefing_dirty = true;
+        }
+        self.active_session = selected;
+        if let Some(index) = closed {
+            let session = self.sessions.remove(index);
+            self.refresh_errors.resolve(&session.icao);
+            self.briefing_dirty = true;
+            if index < self.active_session || self.active_session >= self.sessions.len() {
+                self.active_session = self.active_session.saturating_sub(1);
+            }
+            if self.sessions.is_empty() {
+                return;
+            }
+        }
+
+        let index = self.active_session;
+        let mut refresh = false;
+        let mut cancel = false;
+        let mut save = None;
+        let mut read_aloud = false;
+        let mut export_msfs = false;
+        let hold = self.metar_hold;
+        let refresh_started = self.scheduler.as_ref()
+            .and_then(|scheduler| scheduler.busy_since(&self.sessions[index].icao));
+        let refresh_settings = (self.sessions[index].auto_refresh, self.sessions[index].refresh_minutes);
+        let severe_settings = self.severe_weather_settings();
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                egui::Frame::none()
+                    .inner_margin(egui::style::Margin::same(8.0))
+                    .stroke(Stroke::new(1.0, CYAN_GLOW))
+                    .show(ui, |ui| {
+                        ui.vertical(|ui| {
+                            let session = &mut self.sessions[index];
+                            let heading = match session.metar_source {
+                                Some(source) => format!("{} METAR", source.label()),
+                                None => "Generated METAR".to_string(),
+                            };
+                            ui.horizontal(|ui| {
+                                ui.heading(RichText::new(heading).color(MAGENTA_GLOW));
+                                if let Some(fallback) = session.fallback {
+                                    ui.label(RichText::new(format!("via {} (failover)", fallback.label())).color(TEXT_COLOR).size(12.0));
+                                }
+                            });
+                            if let Some(name) = &session.name {
+                                ui.label(RichText::new(name).color(TEXT_COLOR).size(14.0));
+                            }
+                            let advisories = session.coordinates
+                                .map(|(lat, lon)| volcanic_ash::advisories(lat, lon))
+                                .unwrap_or_default();
+                            if !advisories.is_empty() || volcanic_ash::reported(&session.metar) {
+                                draw_warning_banner(ui, "⚠ VOLCANIC ASH", &advisories);
+                            }
+                            if severe_settings.banner {
+                                let mut severe = severe_weather::phenomena(&decode_cached(&mut self.decoded_reports, &session.metar));
+                                if let Some((lat, lon)) = session.coordinates {
+                                    severe.extend(severe_weather::alerts(lat, lon));
+                                }
+                                if !severe.is_empty() {
+                                    severe.push(SEVERE_WEATHER_ADVICE.to_string());
+                                    draw_warning_banner(ui, "⚠ SEVERE WEATHER", &severe);
+                                }
+                            }
+                            draw_metar_text(ui, "generated_metar_text", &session.metar);
+                            let (freshness, stale_data) = session.freshness();
+                            if stale_data {
+                                ui.colored_label(Color32::YELLOW, format!(
+                                    "⚠ {} - the provider's data is more than {} minutes old",
+                                    freshness, STALE_DATA_MINUTES
+                                ));
+                            } else {
+                                ui.label(RichText::new(freshness).color(TEXT_COLOR).size(12.0));
+                            }
+                            if self.dual_units {
+                                let (label, units) = alternate_units(&self.metar_units);
+                                ui.label(RichText::new(label).color(TEXT_COLOR).size(12.0));
+                                draw_metar_text(ui, "alternate_metar_text", &metar_format::convert_units(&session.metar, &units));
+                            }
+
+                            ui.add_space(6.0);
+                            ui.horizontal(|ui| {
+                                // Per-station provider and refresh settings
+                                if let Some(api) = session.api.as_mut() {
+                                    let previous = *api;
+                                    ui.selectable_value(api, ApiType::Standard, "Standard");
+                                    ui.selectable_value(api, ApiType::OneCall, "One Call");
+                                    ui.selectable_value(api, ApiType::OpenMeteo, "Open-Meteo");
+                                    if previous != *api {
+                                        session.stale = true;
+                                    }
+                                    ui.add_space(10.0);
+                                }
+                                ui.checkbox(&mut session.auto_refresh, "Auto-refresh");
+                                egui::ComboBox::from_id_source("session_refresh_interval")
+                                    .selected_text(format!("{} min", session.refresh_minutes))
+                                    .width(70.0)
+                                    .show_ui(ui, |ui| {
+                                        for minutes in REFRESH_INTERVALS {
+                                            ui.selectable_value(&mut session.refresh_minutes, minutes, format!("{} min", minutes));
+                                        }
+                                    });
+                                if let Some(started) = refresh_started {
+                                    ui.spinner();
+                                    ui.label(RichText::new(format!("{:.1}s", started.elapsed().as_secs_f32())).color(TEXT_COLOR));
+                                    cancel = ui.small_button("Cancel").clicked();
+                                } else if ui.button("Refresh").clicked() {
+                                    refresh = true;
+                                }
+                                ui.label(RichText::new(format!(
+                                    "updated {} min ago",
+                                    session.last_generated.elapsed().as_secs() / 60
+                                )).color(TEXT_COLOR).size(12.0));
+                                if let Some(until) = session.held_until(hold).filter(|_| session.auto_refresh) {
+                                    ui.label(RichText::new(format!("held until {}Z", until.format("%H%M"))).color(TEXT_COLOR).size(12.0));
+                                }
+
+                                // Add warning statement
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    ui.label(RichText::new("Not for aviation purposes").color(MAGENTA_GLOW).size(14.0));
+                                    ui.label(RichText::new("For simulator use only.").color(CYAN_GLOW).size(14.0));
+                                });
+                            });
+
+                            ui.add_space(6.0);
+                            ui.horizontal(|ui| {
+                                let speech_label = if self.speech.is_some() { "Stop Reading" } else { "Read Aloud" };
+                                if ui.button(speech_label).on_hover_text("Read the METAR aloud").clicked() {
+                                    read_aloud = true;
+                                }
+                                let qr_label = if self.show_qr_code { "Hide QR Code" } else { "Show QR Code" };
+                                if ui.button(qr_label).on_hover_text("Show the METAR as a QR code to scan with a tablet").clicked() {
+                                    self.show_qr_code = !self.show_qr_code;
+                                }
+                                if ui.button("Widget").on_hover_text("Show this station in a small always-on-top window").clicked() {
+                                    self.widget_station = Some(session.icao.clone());
+                                }
+                                if ui.button("Export for MSFS")
+                                    .on_hover_text("Save the conditions as a Microsoft Flight Simulator custom weather preset (.WPR)")
+                                    .clicked()
+                                {
+                                    export_msfs = true;
+                                }
+
+                                // Show save button only for lat/lon or location-based METARs
+                                if let (InputMethod::LatLon | InputMethod::Location, Some((lat, lon))) =
+                                    (session.input_method, session.coordinates)
+                                {
+                                    if ui.button("Save Airport").clicked() {
+                                        save = Some((session.icao.clone(), lat, lon));
+                                    }
+                                }
+                            });
+                        });
+                    });
+            });
+        });
+
+        if read_aloud {
+            self.toggle_read_aloud(index);
+        }
+        if export_msfs {
+            self.export_msfs(index);
+        }
+        if refresh_settings != (self.sessions[index].auto_refresh, self.sessions[index].refresh_minutes) {
+            self.briefing_dirty = true;
+        }
+        if refresh {
+            self.refresh_session(index, Priority::Immediate);
+        }
+        if cancel {
+            if let Some(scheduler) = &self.scheduler {
+                scheduler.cancel(&self.sessions[index].icao);
+            }
+            self.sessions[index].stale = false;
+        }
+        if let Some((icao, lat, lon)) = save {
+            save_worker::queue_storage(move || match save_user_airport(icao.clone(), lat, lon) {
+                Ok(()) => Ok(Some(format!("Saved airport {}", icao))),
+                Err(e) => Err(format!("Failed to save airport: {}", e)),
+            });
         }
     }
 }
 
-// ... existing code ... 
\ No newline at end of file
+// ... existing code ... 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn routes_split_on_any_separator_without_repeats() {
+        assert_eq!(parse_route("ksea KPDX, keug"), ["KSEA", "KPDX", "KEUG"]);
+        assert_eq!(parse_route("KSEA-KPDX DCT KSEA"), ["KSEA", "KPDX"]);
+        assert!(parse_route(" , - ").is_empty());
+    }
+
+    #[test]
+    fn long_metars_wrap_between_groups() {
+        let metar = "METAR KJFK 121251Z 31015G25KT 10SM FEW050 M02/M10 A3012";
+        assert_eq!(wrap_metar(metar, 30), "METAR KJFK 121251Z 31015G25KT\n     10SM FEW050 M02/M10 A3012");
+        assert_eq!(wrap_metar(metar, 80), metar);
+    }
+
+    #[test]
+    fn runway_headings_from_designators_and_degrees() {
+        assert_eq!(runway_heading("27L"), Some(270));
+        assert_eq!(runway_heading("9"), Some(90));
+        assert_eq!(runway_heading("268"), Some(268));
+        assert_eq!(runway_heading("37"), None);
+        assert_eq!(runway_heading("00"), None);
+        assert_eq!(runway_heading("361"), None);
+        assert_eq!(runway_heading("RWY"), None);
+    }
+
+    #[test]
+    fn default_tab_from_config() {
+        for tab in Tab::ALL {
+            assert!(Tab::from_config(&serde_json::json!({ "default_tab": tab.key() })) == tab);
+        }
+        assert!(Tab::from_config(&serde_json::json!({ "default_tab": "settings" })) == Tab::GenerateMetar);
+        assert!(Tab::from_config(&Value::Null) == Tab::GenerateMetar);
+    }
+
+    #[test]
+    fn held_reports_change_at_the_next_issuance() {
+        let time = |hour, minute, second| Utc.with_ymd_and_hms(2024, 3, 12, hour, minute, second).unwrap();
+        assert_eq!(MetarHold::Off.next_issuance(time(12, 46, 30)), None);
+        assert_eq!(MetarHold::HalfHour.next_issuance(time(12, 10, 0)), Some(time(12, 30, 0)));
+        assert_eq!(MetarHold::HalfHour.next_issuance(time(12, 30, 0)), Some(time(13, 0, 0)));
+        assert_eq!(MetarHold::Hour.next_issuance(time(23, 46, 30)), Some(Utc.with_ymd_and_hms(2024, 3, 13, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn history_names_existing_metars_after_their_source() {
+        assert_eq!(provider_name(None, Some(MetarSource::Avwx)), "AVWX");
+        assert_eq!(provider_name(None, None), "NOAA");
+        assert_eq!(provider_name(Some(ApiType::OpenMeteo), None), "Open-Meteo");
+    }
+}
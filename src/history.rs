@@ -0,0 +1,225 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Local history of the METARs shown by the app, kept by the storage module.
+// It never leaves this machine; the Diagnostics tab summarises it. Old entries
+// are pruned according to the retention settings so years of auto-refresh
+// don't grow the history without bound.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use crate::disk_cache;
+use crate::storage::{self, ReportQuery};
+
+// Reports recorded between automatic prunes
+const PRUNE_INTERVAL: usize = 500;
+// Number of airports listed in the usage statistics
+const TOP_AIRPORT_COUNT: usize = 5;
+
+#[derive(Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub time: DateTime<Utc>,
+    pub icao: String,
+    pub provider: String,
+    pub metar: String,
+    /// Provider requests answered from the cache since the report before it
+    #[serde(default)]
+    pub cache_hits: u64,
+}
+
+/// How much history is kept. None means no limit.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Retention {
+    pub max_entries: Option<usize>,
+    pub max_days: Option<u32>,
+    // Prune at startup and every PRUNE_INTERVAL reports
+    pub auto_prune: bool,
+}
+
+impl Retention {
+    // A year of reports, and no more than a few MB of them
+    const DEFAULT: Retention = Retention { max_entries: Some(50_000), max_days: Some(365), auto_prune: true };
+
+    /// Retention from "history_max_entries" and "history_max_days" (0 for no
+    /// limit) and "history_auto_prune" in the config.
+    pub fn from_config(config: &Value) -> Retention {
+        let limit = |key: &str, default: Option<u64>| match config[key].as_u64() {
+            Some(0) => None,
+            Some(limit) => Some(limit),
+            None => default,
+        };
+        Retention {
+            max_entries: limit("history_max_entries", Retention::DEFAULT.max_entries.map(|n| n as u64))
+                .map(|n| n as usize),
+            max_days: limit("history_max_days", Retention::DEFAULT.max_days.map(u64::from))
+                .map(|days| days.min(u32::MAX as u64) as u32),
+            auto_prune: config["history_auto_prune"].as_bool().unwrap_or(Retention::DEFAULT.auto_prune),
+        }
+    }
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention::DEFAULT
+    }
+}
+
+static RETENTION: Mutex<Retention> = Mutex::new(Retention::DEFAULT);
+static RECORDED: AtomicUsize = AtomicUsize::new(0);
+static PRUNING: AtomicBool = AtomicBool::new(false);
+
+/// Sets the retention used by automatic pruning, and prunes in the background
+/// right away if it is enabled.
+pub fn set_retention(retention: Retention) {
+    if let Ok(mut current) = RETENTION.lock() {
+        *current = retention;
+    }
+    if retention.auto_prune {
+        prune_in_background();
+    }
+}
+
+fn retention() -> Retention {
+    RETENTION.lock().map(|retention| *retention).unwrap_or_default()
+}
+
+/// Adds a report to the history.
+pub fn record(icao: &str, provider: &str, metar: &str) {
+    let entry = HistoryEntry {
+        time: Utc::now(),
+        icao: icao.to_string(),
+        provider: provider.to_string(),
+        metar: metar.to_string(),
+        cache_hits: disk_cache::take_hits(),
+    };
+    if let Err(e) = storage::get().record_report(&entry) {
+        eprintln!("Failed to write history: {}", e);
+    }
+
+    if (RECORDED.fetch_add(1, Ordering::Relaxed) + 1).is_multiple_of(PRUNE_INTERVAL) && retention().auto_prune {
+        prune_in_background();
+    }
+}
+
+/// Removes reports older or beyond the retention limits. Returns the number removed.
+pub fn prune(retention: Retention) -> Result<usize, String> {
+    storage::get().prune_reports(retention)
+}
+
+// Prunes on a background thread with the current retention, unless a prune is already running
+fn prune_in_background() {
+    if PRUNING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    thread::spawn(|| {
+        if let Err(e) = prune(retention()) {
+            eprintln!("Failed to prune history: {}", e);
+        }
+        PRUNING.store(false, Ordering::Release);
+    });
+}
+
+/// Space the history takes on disk in bytes.
+pub fn size() -> u64 {
+    storage::get().size()
+}
+
+/// The whole history, newest first.
+pub fn load_history() -> Vec<HistoryEntry> {
+    storage::get().reports(&ReportQuery::default())
+}
+
+/// Reports matching the query, newest first.
+pub fn search(query: &ReportQuery) -> Vec<HistoryEntry> {
+    storage::get().reports(query)
+}
+
+#[derive(Clone, Default)]
+pub struct UsageStats {
+    pub total: usize,
+    pub since: Option<DateTime<Utc>>,
+    // Report counts, most used first
+    pub providers: Vec<(String, usize)>,
+    pub top_airports: Vec<(String, usize)>,
+    // Provider requests answered from the cache, which is the API calls saved
+    pub cache_hits: u64,
+}
+
+pub fn usage_stats(history: &[HistoryEntry]) -> UsageStats {
+    let mut providers: HashMap<&str, usize> = HashMap::new();
+    let mut airports: HashMap<&str, usize> = HashMap::new();
+    for entry in history {
+        *providers.entry(&entry.provider).or_default() += 1;
+        *airports.entry(&entry.icao).or_default() += 1;
+    }
+
+    let mut top_airports = sorted_counts(airports);
+    top_airports.truncate(TOP_AIRPORT_COUNT);
+    UsageStats {
+        total: history.len(),
+        since: history.iter().map(|entry| entry.time).min(),
+        providers: sorted_counts(providers),
+        top_airports,
+        cache_hits: history.iter().map(|entry| entry.cache_hits).sum(),
+    }
+}
+
+// Highest count first, ties in alphabetical order
+fn sorted_counts(counts: HashMap<&str, usize>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(name, count)| (name.to_string(), count))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(icao: &str, provider: &str, cache_hits: u64) -> HistoryEntry {
+        HistoryEntry {
+            time: Utc::now(),
+            icao: icao.to_string(),
+            provider: provider.to_string(),
+            metar: String::new(),
+            cache_hits,
+        }
+    }
+
+    #[test]
+    fn usage_stats_count_the_whole_history() {
+        let history = [entry("KSEA", "NOAA", 2), entry("KPDX", "Open-Meteo", 0), entry("KSEA", "Open-Meteo", 1)];
+        let stats = usage_stats(&history);
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.cache_hits, 3);
+        assert_eq!(stats.providers, [("Open-Meteo".to_string(), 2), ("NOAA".to_string(), 1)]);
+        assert_eq!(stats.top_airports, [("KSEA".to_string(), 2), ("KPDX".to_string(), 1)]);
+    }
+
+    #[test]
+    fn entries_written_before_cache_hits_read_as_none() {
+        let line = r#"{"time":"2024-03-12T12:00:00Z","icao":"KSEA","provider":"NOAA","metar":"KSEA 121200Z"}"#;
+        let entry: HistoryEntry = serde_json::from_str(line).unwrap();
+        assert_eq!(entry.cache_hits, 0);
+    }
+}
@@ -0,0 +1,74 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// System-wide hotkey that regenerates the current station while another
+// program, typically the sim, has focus
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set by the hotkey event handler, which runs outside the GUI update
+static PRESSED: AtomicBool = AtomicBool::new(false);
+
+pub struct RegenerateHotkey {
+    // The hotkey stays registered for as long as the manager is alive
+    manager: GlobalHotKeyManager,
+    hotkey: HotKey,
+    pub text: String,
+}
+
+impl RegenerateHotkey {
+    /// Registers a hotkey written like "Ctrl+Shift+F9". `wake` is called on every
+    /// press so the GUI can update even when it is not focused.
+    pub fn register(text: &str, wake: impl Fn() + Send + Sync + 'static) -> Result<Self, String> {
+        let hotkey: HotKey = text
+            .parse()
+            .map_err(|e| format!("Invalid hotkey {}: {}", text, e))?;
+        let manager = GlobalHotKeyManager::new()
+            .map_err(|e| format!("Failed to set up global hotkeys: {}", e))?;
+        manager
+            .register(hotkey)
+            .map_err(|e| format!("Failed to register hotkey {}: {}", text, e))?;
+
+        let id = hotkey.id();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            if event.id() == id && event.state() == HotKeyState::Pressed {
+                PRESSED.store(true, Ordering::Relaxed);
+                wake();
+            }
+        }));
+
+        Ok(Self {
+            manager,
+            hotkey,
+            text: text.to_string(),
+        })
+    }
+}
+
+impl Drop for RegenerateHotkey {
+    fn drop(&mut self) {
+        if let Err(e) = self.manager.unregister(self.hotkey) {
+            eprintln!("Failed to unregister hotkey {}: {}", self.text, e);
+        }
+    }
+}
+
+/// Whether the hotkey was pressed since the last call.
+pub fn take_pressed() -> bool {
+    PRESSED.swap(false, Ordering::Relaxed)
+}
@@ -0,0 +1,110 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// The first letter of an ICAO location indicator names the region the station
+// is in (K for the contiguous US, E for northern Europe, ...). When a report is
+// labelled with an identifier typed next to coordinates, a prefix from the
+// wrong part of the world is almost always a mix-up, e.g. a US code left in the
+// box while generating for a European airfield.
+
+use crate::pipe::UNNAMED_IDENTIFIER;
+
+// Latitude and longitude bounds as (south, north, west, east)
+type Bounds = (f64, f64, f64, f64);
+
+// Rough bounds per prefix, generous enough that stations near a border don't
+// trip the check. Letters without an entry (I, J, Q, X) are not assigned to a
+// region and are never flagged.
+const REGIONS: &[(char, &str, &[Bounds])] = &[
+    ('A', "the western South Pacific", &[(-12.0, 1.0, 140.0, 172.0)]),
+    ('B', "Greenland, Iceland or Kosovo", &[(59.0, 84.0, -74.0, -10.0), (62.0, 68.0, -26.0, -12.0), (41.5, 43.5, 19.5, 22.0)]),
+    ('C', "Canada", &[(41.0, 84.0, -142.0, -52.0)]),
+    ('D', "West Africa", &[(4.0, 38.0, -9.0, 16.0)]),
+    ('E', "northern Europe", &[(47.0, 81.0, -25.0, 32.0)]),
+    ('F', "central and southern Africa", &[(-47.0, 24.0, 8.0, 64.0)]),
+    ('G', "northwest Africa", &[(4.0, 36.0, -26.0, 5.0)]),
+    ('H', "northeast and east Africa", &[(-12.0, 34.0, 9.0, 52.0)]),
+    ('K', "the contiguous United States", &[(24.0, 50.0, -125.0, -66.0)]),
+    ('L', "southern Europe, Israel or Turkey", &[(27.0, 52.0, -32.0, 45.0)]),
+    ('M', "Mexico, Central America or the Caribbean", &[(7.0, 33.0, -118.0, -59.0)]),
+    ('N', "the South Pacific or New Zealand", &[(-53.0, 5.0, 160.0, 180.0), (-53.0, 5.0, -180.0, -130.0)]),
+    ('O', "the Middle East, Pakistan or Afghanistan", &[(12.0, 40.0, 34.0, 78.0)]),
+    ('P', "Alaska, Hawaii or the North Pacific", &[(51.0, 72.0, -180.0, -129.0), (51.0, 56.0, 172.0, 180.0), (18.0, 29.0, -179.0, -154.0), (0.0, 30.0, 130.0, 180.0)]),
+    ('R', "Japan, Korea, Taiwan or the Philippines", &[(4.0, 46.0, 116.0, 154.0)]),
+    ('S', "South America", &[(-56.0, 13.0, -92.0, -34.0)]),
+    ('T', "the eastern Caribbean", &[(10.0, 20.0, -71.0, -59.0)]),
+    ('U', "Russia and neighbouring states", &[(35.0, 82.0, 19.0, 180.0), (64.0, 72.0, -180.0, -168.0)]),
+    ('V', "South and Southeast Asia", &[(-1.0, 36.0, 66.0, 115.0)]),
+    ('W', "Indonesia, Malaysia or Singapore", &[(-11.0, 8.0, 94.0, 142.0)]),
+    ('Y', "Australia", &[(-44.0, -9.0, 112.0, 169.0)]),
+    ('Z', "China, Mongolia or North Korea", &[(18.0, 54.0, 73.0, 135.0)]),
+];
+
+fn region(icao: &str) -> Option<(&'static str, &'static [Bounds])> {
+    let prefix = icao.chars().next()?.to_ascii_uppercase();
+    REGIONS.iter().find(|(letter, _, _)| *letter == prefix).map(|(_, name, bounds)| (*name, *bounds))
+}
+
+/// A warning when `icao` belongs to a different region than the coordinates,
+/// e.g. "KJFK is an ICAO code for the contiguous United States, but 48.3537, 11.7861 is outside it".
+/// Returns `None` if the prefix matches or can't be checked.
+pub fn mismatch_warning(icao: &str, lat: f64, lon: f64) -> Option<String> {
+    if icao.len() != 4 || !icao.chars().all(|c| c.is_ascii_alphanumeric()) || icao.eq_ignore_ascii_case(UNNAMED_IDENTIFIER) {
+        return None;
+    }
+    let (name, bounds) = region(icao)?;
+    let inside = bounds.iter().any(|&(south, north, west, east)| {
+        (south..=north).contains(&lat) && (west..=east).contains(&lon)
+    });
+    (!inside).then(|| format!(
+        "{} is an ICAO code for {}, but {:.4}, {:.4} is outside it. Check the identifier before using this report.",
+        icao.to_uppercase(), name, lat, lon
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_prefixes_have_no_warning() {
+        assert_eq!(mismatch_warning("KJFK", 40.64, -73.78), None);
+        assert_eq!(mismatch_warning("eddm", 48.3537, 11.7861), None);
+        assert_eq!(mismatch_warning("YSSY", -33.95, 151.18), None);
+        // Regions made of several areas: Hawaii and Alaska either side of the date line
+        assert_eq!(mismatch_warning("PHNL", 21.32, -157.92), None);
+        assert_eq!(mismatch_warning("PASY", 52.71, 174.11), None);
+    }
+
+    #[test]
+    fn prefix_from_another_region_is_flagged() {
+        assert_eq!(
+            mismatch_warning("kjfk", 48.3537, 11.7861).as_deref(),
+            Some("KJFK is an ICAO code for the contiguous United States, but 48.3537, 11.7861 is outside it. Check the identifier before using this report.")
+        );
+        assert!(mismatch_warning("EGLL", 40.64, -73.78).is_some());
+    }
+
+    #[test]
+    fn identifiers_that_cannot_be_checked() {
+        // Unassigned letters, FAA-style identifiers and the placeholder for unnamed points
+        assert_eq!(mismatch_warning("XABC", 48.35, 11.79), None);
+        assert_eq!(mismatch_warning("1N7", 48.35, 11.79), None);
+        assert_eq!(mismatch_warning("K1N7X", 48.35, 11.79), None);
+        assert_eq!(mismatch_warning("K-N7", 48.35, 11.79), None);
+        assert_eq!(mismatch_warning(UNNAMED_IDENTIFIER, 48.35, 11.79), None);
+    }
+}
@@ -14,43 +14,238 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use reqwest::{blocking::Client, StatusCode};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::StatusCode;
 use serde_json::Value;
-use std::fs;
-use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::airport_db;
+use crate::disk_cache;
 
 const NOAA_METAR_URL: &str = "https://aviationweather.gov/api/data/metar";
 const NOAA_AIRPORT_URL: &str = "https://aviationweather.gov/api/data/airport";
+const NOAA_SIGMET_URL: &str = "https://aviationweather.gov/api/data/isigmet";
 const GEOCODING_URL: &str = "http://api.openweathermap.org/geo/1.0/direct";
 const ONE_CALL_URL: &str = "https://api.openweathermap.org/data/3.0/onecall";
+const SWPC_SCALES_URL: &str = "https://services.swpc.noaa.gov/products/noaa-scales.json";
+const CHECKWX_URL: &str = "https://api.checkwx.com";
+const AVWX_URL: &str = "https://avwx.rest/api";
+const MARINE_URL: &str = "https://marine-api.open-meteo.com/v1/marine";
+const OPEN_METEO_URL: &str = "https://api.open-meteo.com/v1/forecast";
+// Current values requested from the Open-Meteo forecast API
+const OPEN_METEO_VARIABLES: &str = "temperature_2m,dew_point_2m,relative_humidity_2m,weather_code,cloud_cover,\
+    pressure_msl,wind_speed_10m,wind_direction_10m,wind_gusts_10m,visibility";
+// Current values requested from the marine API
+const MARINE_VARIABLES: &str = "wave_height,swell_wave_height,swell_wave_direction,sea_surface_temperature";
 
-// Bundle the airports.csv file into the binary
-const BUNDLED_AIRPORTS_CSV: &str = include_str!("../airports.csv");
+// Provider names used for the health indicators
+pub const NOAA: &str = "NOAA";
+pub const OWM_GEOCODING: &str = "OpenWeather Geocoding";
+pub const OWM_STANDARD: &str = "OpenWeather Standard";
+pub const OWM_ONE_CALL: &str = "OpenWeather One Call";
+pub const OPEN_METEO: &str = "Open-Meteo";
+pub const OPEN_METEO_MARINE: &str = "Open-Meteo Marine";
+pub const NOAA_SWPC: &str = "NOAA SWPC";
+pub const CHECKWX: &str = "CheckWX";
+pub const AVWX: &str = "AVWX";
 
-fn get_airports_data() -> Result<String, String> {
-    // Try to read from the external file first
-    let airports_csv_path = get_resource_path("airports.csv");
-    match fs::read_to_string(&airports_csv_path) {
-        Ok(data) => Ok(data),
-        Err(_) => Ok(BUNDLED_AIRPORTS_CSV.to_string()) // Fall back to bundled data
+/// How the last request to a provider went.
+#[derive(Clone)]
+pub struct ProviderHealth {
+    pub provider: &'static str,
+    pub checked: Instant,
+    pub latency: Duration,
+    /// Transport error or non-success status of the last call
+    pub error: Option<String>,
+    /// Calls left, when the provider reports it in X-RateLimit-Remaining
+    pub quota_remaining: Option<u64>,
+}
+
+static PROVIDER_HEALTH: Mutex<Vec<ProviderHealth>> = Mutex::new(Vec::new());
+
+/// Latest health of every provider called so far this session.
+pub fn provider_health() -> Vec<ProviderHealth> {
+    PROVIDER_HEALTH.lock().map(|health| health.clone()).unwrap_or_default()
+}
+
+// One HTTP client shared by all requests, so connections are reused
+fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(Client::new)
+}
+
+// Sends a request, recording the latency and outcome for the provider
+fn send(provider: &'static str, request: RequestBuilder) -> reqwest::Result<Response> {
+    let started = Instant::now();
+    let result = request.send();
+    let health = ProviderHealth {
+        provider,
+        checked: Instant::now(),
+        latency: started.elapsed(),
+        error: match &result {
+            Ok(response) if !response.status().is_success() => Some(response.status().to_string()),
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        },
+        quota_remaining: result.as_ref().ok().and_then(|response| {
+            response.headers().get("x-ratelimit-remaining")?.to_str().ok()?.parse().ok()
+        }),
+    };
+    if let Ok(mut providers) = PROVIDER_HEALTH.lock() {
+        providers.retain(|entry| entry.provider != provider);
+        providers.push(health);
     }
+    result
 }
 
-fn get_resource_path(filename: &str) -> PathBuf {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(filename);
-    path
+// OpenWeather language codes that differ from the ISO 639-1 code, as
+// (ISO code, OpenWeather code). Chinese and Portuguese are handled separately.
+const OWM_LANGUAGE_ALIASES: [(&str, &str); 5] = [("cs", "cz"), ("ko", "kr"), ("sq", "al"), ("lv", "la"), ("uk", "ua")];
+
+// Languages OpenWeather returns descriptions in, by OpenWeather code
+const OWM_LANGUAGES: [&str; 46] = [
+    "af", "al", "ar", "az", "bg", "ca", "cz", "da", "de", "el", "en", "es", "eu", "fa", "fi", "fr", "gl", "he",
+    "hi", "hr", "hu", "id", "it", "ja", "kr", "la", "lt", "mk", "nl", "no", "pl", "pt", "pt_br", "ro", "ru",
+    "sk", "sl", "sr", "sv", "th", "tr", "ua", "vi", "zh_cn", "zh_tw", "zu",
+];
+
+/// OpenWeather `lang` code for alert and weather descriptions. A configured
+/// code is used as is; otherwise it follows the OS locale, falling back to
+/// English. Weather condition ids are the same in every language.
+pub fn owm_language(configured: Option<&str>) -> String {
+    if let Some(code) = configured.map(str::trim).filter(|code| !code.is_empty()) {
+        return code.to_lowercase();
+    }
+
+    let locale = sys_locale::get_locale().unwrap_or_default().to_lowercase().replace('-', "_");
+    let language = locale.split(['_', '.']).next().unwrap_or_default();
+    let code = match language {
+        "zh" if locale.contains("tw") || locale.contains("hk") || locale.contains("hant") => "zh_tw".to_string(),
+        "zh" => "zh_cn".to_string(),
+        "pt" if locale.contains("br") => "pt_br".to_string(),
+        // Norwegian Bokmål and Nynorsk
+        "nb" | "nn" => "no".to_string(),
+        _ => OWM_LANGUAGE_ALIASES
+            .iter()
+            .find(|(iso, _)| *iso == language)
+            .map_or(language.to_string(), |(_, owm)| owm.to_string()),
+    };
+
+    if OWM_LANGUAGES.contains(&code.as_str()) {
+        code
+    } else {
+        "en".to_string()
+    }
+}
+
+// Airport and place coordinates rarely change, so lookups are cached for a month
+const COORDINATE_CACHE_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+fn cached_coordinates(namespace: &str, key: &str) -> Option<(f64, f64)> {
+    let coordinates = serde_json::from_str(&disk_cache::get(namespace, key, COORDINATE_CACHE_AGE)?).ok()?;
+    disk_cache::record_hit();
+    Some(coordinates)
+}
+
+fn cache_coordinates(namespace: &str, key: &str, coordinates: (f64, f64)) {
+    if let Ok(contents) = serde_json::to_string(&coordinates) {
+        if let Err(e) = disk_cache::put(namespace, key, &contents) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+/// Where a real METAR came from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MetarSource {
+    Noaa,
+    CheckWx,
+    Avwx,
 }
 
-pub fn poll_noaa_metar(icao: &str) -> Option<String> {
+impl MetarSource {
+    pub const ALL: [MetarSource; 3] = [MetarSource::Noaa, MetarSource::CheckWx, MetarSource::Avwx];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MetarSource::Noaa => NOAA,
+            MetarSource::CheckWx => CHECKWX,
+            MetarSource::Avwx => AVWX,
+        }
+    }
+
+    /// Identifier used in pipe mode output.
+    pub fn key(self) -> &'static str {
+        match self {
+            MetarSource::Noaa => "noaa",
+            MetarSource::CheckWx => "checkwx",
+            MetarSource::Avwx => "avwx",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<MetarSource> {
+        Self::ALL.into_iter().find(|source| source.key() == key)
+    }
+}
+
+// A published METAR along with the reporting station's position and the
+// source it came from
+#[derive(Clone)]
+pub struct RealMetar {
+    pub icao: String,
+    pub raw: String,
+    pub coordinates: Option<(f64, f64)>,
+    pub source: MetarSource,
+}
+
+/// Keys for CheckWX and AVWX, the real-METAR sources asked when NOAA has no
+/// report for a station or can't be reached. A source without a key is skipped.
+#[derive(Clone, Default)]
+pub struct AlternateSources {
+    pub checkwx_key: String,
+    pub avwx_key: String,
+}
+
+impl AlternateSources {
+    pub fn from_config(config: &Value) -> AlternateSources {
+        let key = |name: &str| config[name].as_str().unwrap_or("").trim().to_string();
+        AlternateSources {
+            checkwx_key: key("decrypted_checkwx_api_key"),
+            avwx_key: key("decrypted_avwx_api_key"),
+        }
+    }
+
+    // Current METAR from the first alternate source that has one
+    fn metar(&self, icao: &str) -> Option<RealMetar> {
+        let checkwx = || poll_checkwx_metar(icao, &self.checkwx_key);
+        let avwx = || poll_avwx_metar(icao, &self.avwx_key);
+        (!self.checkwx_key.is_empty()).then(checkwx).flatten()
+            .or_else(|| (!self.avwx_key.is_empty()).then(avwx).flatten())
+    }
+
+    // Station position from the first alternate source that knows it
+    fn station(&self, icao: &str) -> Option<(f64, f64)> {
+        let checkwx = || checkwx_station(icao, &self.checkwx_key);
+        let avwx = || avwx_station(icao, &self.avwx_key);
+        (!self.checkwx_key.is_empty()).then(checkwx).flatten()
+            .or_else(|| (!self.avwx_key.is_empty()).then(avwx).flatten())
+    }
+}
+
+/// Current METAR for a station from NOAA, or from the alternate sources when
+/// NOAA has none or is down.
+pub fn poll_metar(icao: &str, sources: &AlternateSources) -> Option<RealMetar> {
+    poll_noaa_metar(icao).or_else(|| sources.metar(icao))
+}
+
+fn poll_noaa_metar(icao: &str) -> Option<RealMetar> {
     let params = [
         ("ids", icao),
         ("format", "json"),
         ("taf", "false"),
     ];
 
-    let client = Client::new();
-    match client.get(NOAA_METAR_URL).query(&params).send() {
+    match send(NOAA, client().get(NOAA_METAR_URL).query(&params)) {
         Ok(response) if response.status() == StatusCode::OK => {
             match response.json::<Value>() {
                 Ok(metar_data) => {
@@ -58,7 +253,17 @@ pub fn poll_noaa_metar(icao: &str) -> Option<String> {
                     if let Some(array) = metar_data.as_array() {
                         if let Some(first_record) = array.first() {
                             if let Some(raw_metar) = first_record["rawOb"].as_str() {
-                                return Some(raw_metar.to_string());
+                                // Station coordinates are used as a fallback if the airport lookup fails
+                                let coordinates = match (first_record["lat"].as_f64(), first_record["lon"].as_f64()) {
+                                    (Some(lat), Some(lon)) => validate_lat_lon(lat, lon),
+                                    _ => None,
+                                };
+                                return Some(RealMetar {
+                                    icao: icao.to_uppercase(),
+                                    raw: raw_metar.to_string(),
+                                    coordinates,
+                                    source: MetarSource::Noaa,
+                                });
                             }
                         }
                     }
@@ -72,11 +277,146 @@ pub fn poll_noaa_metar(icao: &str) -> Option<String> {
     None
 }
 
-pub fn resolve_icao_to_lat_lon(icao: &str) -> Option<(f64, f64)> {
+// Body of a successful response from CheckWX or AVWX. No content means the
+// source has no report for the station.
+fn alternate_json(provider: &'static str, request: RequestBuilder) -> Option<Value> {
+    match send(provider, request) {
+        Ok(response) if response.status() == StatusCode::OK => match response.json::<Value>() {
+            Ok(body) => return Some(body),
+            Err(e) => eprintln!("Failed to parse {} response: {}", provider, e),
+        },
+        Ok(response) if response.status() == StatusCode::NO_CONTENT => {}
+        Ok(response) => eprintln!("{} returned {}", provider, response.status()),
+        Err(e) => eprintln!("Error querying {}: {}", provider, e),
+    }
+    None
+}
+
+// CheckWX gives positions as GeoJSON, longitude first
+fn checkwx_coordinates(station: &Value) -> Option<(f64, f64)> {
+    let coordinates = station["geometry"]["coordinates"].as_array()?;
+    validate_lat_lon(coordinates.get(1)?.as_f64()?, coordinates.first()?.as_f64()?)
+}
+
+// The identifier uppercased, if it is safe to put in a URL path: three or
+// four letters and digits, as in ^[A-Z0-9]{3,4}$
+fn station_id(icao: &str) -> Option<String> {
+    let icao = icao.trim().to_uppercase();
+    let valid = (3..=4).contains(&icao.len()) && icao.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    valid.then_some(icao)
+}
+
+fn poll_checkwx_metar(icao: &str, key: &str) -> Option<RealMetar> {
+    let icao = station_id(icao)?;
+    let url = format!("{}/metar/{}/decoded", CHECKWX_URL, icao);
+    let body = alternate_json(CHECKWX, client().get(url).header("X-API-Key", key))?;
+    let record = body["data"].as_array()?.first()?;
+    Some(RealMetar {
+        icao,
+        raw: record["raw_text"].as_str()?.to_string(),
+        coordinates: checkwx_coordinates(&record["station"]),
+        source: MetarSource::CheckWx,
+    })
+}
+
+fn checkwx_station(icao: &str, key: &str) -> Option<(f64, f64)> {
+    let icao = station_id(icao)?;
+    let url = format!("{}/station/{}", CHECKWX_URL, icao);
+    let body = alternate_json(CHECKWX, client().get(url).header("X-API-Key", key))?;
+    checkwx_coordinates(body["data"].as_array()?.first()?)
+}
+
+fn avwx_coordinates(station: &Value) -> Option<(f64, f64)> {
+    validate_lat_lon(station["latitude"].as_f64()?, station["longitude"].as_f64()?)
+}
+
+fn poll_avwx_metar(icao: &str, key: &str) -> Option<RealMetar> {
+    let icao = station_id(icao)?;
+    let url = format!("{}/metar/{}", AVWX_URL, icao);
+    let request = client().get(url).query(&[("options", "info")]).bearer_auth(key);
+    let body = alternate_json(AVWX, request)?;
+    Some(RealMetar {
+        icao,
+        raw: body["raw"].as_str()?.to_string(),
+        coordinates: avwx_coordinates(&body["info"]),
+        source: MetarSource::Avwx,
+    })
+}
+
+fn avwx_station(icao: &str, key: &str) -> Option<(f64, f64)> {
+    let icao = station_id(icao)?;
+    let url = format!("{}/station/{}", AVWX_URL, icao);
+    avwx_coordinates(&alternate_json(AVWX, client().get(url).bearer_auth(key))?)
+}
+
+/// Current international SIGMETs for volcanic ash, issued from the VAAC advisories.
+/// Each has the FIR name, the validity and the affected area as "coords".
+pub fn fetch_volcanic_ash_sigmets() -> Option<Vec<Value>> {
+    let params = [("format", "json"), ("hazard", "va")];
+
+    match send(NOAA, client().get(NOAA_SIGMET_URL).query(&params)) {
+        Ok(response) if response.status() == StatusCode::OK => match response.json::<Value>() {
+            Ok(sigmets) => return Some(sigmets.as_array().cloned().unwrap_or_default()),
+            Err(e) => eprintln!("Failed to parse SIGMET data: {}", e),
+        },
+        // No content means there are no SIGMETs in force
+        Ok(response) if response.status() == StatusCode::NO_CONTENT => return Some(Vec::new()),
+        Err(e) => eprintln!("Error querying NOAA SIGMET API: {}", e),
+        _ => eprintln!("Unexpected response when querying NOAA SIGMET API."),
+    }
+    None
+}
+
+/// Downloads all current METARs inside a bounding box, given as
+/// [south, west, north, east], in a single request.
+pub fn fetch_noaa_metars_in_area(bounds: [f64; 4]) -> Option<Vec<RealMetar>> {
+    let bbox = bounds.map(|value| value.to_string()).join(",");
+    let params = [
+        ("bbox", bbox.as_str()),
+        ("format", "json"),
+        ("taf", "false"),
+    ];
+
+    match send(NOAA, client().get(NOAA_METAR_URL).query(&params)) {
+        Ok(response) if response.status() == StatusCode::OK => {
+            match response.json::<Value>() {
+                Ok(metar_data) => {
+                    let records = metar_data.as_array()?;
+                    return Some(records
+                        .iter()
+                        .filter_map(|record| {
+                            Some(RealMetar {
+                                icao: record["icaoId"].as_str()?.to_uppercase(),
+                                raw: record["rawOb"].as_str()?.to_string(),
+                                coordinates: match (record["lat"].as_f64(), record["lon"].as_f64()) {
+                                    (Some(lat), Some(lon)) => validate_lat_lon(lat, lon),
+                                    _ => None,
+                                },
+                                source: MetarSource::Noaa,
+                            })
+                        })
+                        .collect());
+                }
+                Err(e) => eprintln!("Failed to parse METAR data for area {}: {}", bbox, e),
+            }
+        }
+        // No content means there are no reporting stations in the area
+        Ok(response) if response.status() == StatusCode::NO_CONTENT => return Some(Vec::new()),
+        Err(e) => eprintln!("Error querying NOAA METAR API for area {}: {}", bbox, e),
+        _ => eprintln!("Unexpected response when querying NOAA METAR API."),
+    }
+    None
+}
+
+pub fn resolve_icao_to_lat_lon(icao: &str, sources: &AlternateSources) -> Option<(f64, f64)> {
+    let cache_key = icao.to_uppercase();
+    if let Some(coordinates) = cached_coordinates("airport", &cache_key) {
+        return Some(coordinates);
+    }
+
     let params = [("ids", icao), ("format", "json")];
 
-    let client = Client::new();
-    match client.get(NOAA_AIRPORT_URL).query(&params).send() {
+    match send(NOAA, client().get(NOAA_AIRPORT_URL).query(&params)) {
         Ok(response) => {
             match response.status() {
                 StatusCode::NOT_FOUND => {
@@ -99,6 +439,7 @@ pub fn resolve_icao_to_lat_lon(icao: &str) -> Option<(f64, f64)> {
                                     first_record["lat"].as_f64(),
                                     first_record["lon"].as_f64(),
                                 ) {
+                                    cache_coordinates("airport", &cache_key, (lat, lon));
                                     return Some((lat, lon));
                                 }
                             }
@@ -114,47 +455,29 @@ pub fn resolve_icao_to_lat_lon(icao: &str) -> Option<(f64, f64)> {
         }
     }
 
-    // Fallback to local database
-    let csv_data = match get_airports_data() {
-        Ok(data) => data,
-        Err(e) => {
-            eprintln!("Error reading airports database: {}", e);
-            return None;
-        }
-    };
-
-    // Skip header row and license text, parse subsequent rows
-    let mut found_header = false;
-    for line in csv_data.lines() {
-        if line.starts_with("//") || line.trim().is_empty() {
-            continue;  // Skip license text and empty lines
-        }
-        if !found_header {
-            found_header = true;
-            continue;  // Skip the header row (ICAO,Latitude,Longitude)
-        }
-
-        let fields: Vec<&str> = line.split(',').collect();
-        // Ensure we have enough columns and the ICAO matches
-        if fields.len() >= 3 && fields[0].eq_ignore_ascii_case(icao) {
-            // fields[1] = latitude, fields[2] = longitude
-            if let (Ok(lat), Ok(lon)) = (fields[1].parse::<f64>(), fields[2].parse::<f64>()) {
-                return Some((lat, lon));
-            }
-        }
+    // Then the alternate sources
+    if let Some(coordinates) = sources.station(icao) {
+        cache_coordinates("airport", &cache_key, coordinates);
+        return Some(coordinates);
     }
-    None
+
+    // Fallback to local database
+    airport_db::get().airport(icao).map(|airport| (airport.latitude, airport.longitude))
 }
 
 pub fn resolve_freeform_input(location: &str, api_key: &str) -> Option<(f64, f64)> {
+    let cache_key = location.trim().to_lowercase();
+    if let Some(coordinates) = cached_coordinates("geocode", &cache_key) {
+        return Some(coordinates);
+    }
+
     let params = [
         ("q", location.to_string()),
         ("appid", api_key.to_string()),
         ("limit", "1".to_string()),
     ];
 
-    let client = Client::new();
-    match client.get(GEOCODING_URL).query(&params).send() {
+    match send(OWM_GEOCODING, client().get(GEOCODING_URL).query(&params)) {
         Ok(response) => {
             match response.status() {
                 StatusCode::UNAUTHORIZED => {
@@ -186,6 +509,7 @@ pub fn resolve_freeform_input(location: &str, api_key: &str) -> Option<(f64, f64
                     let lon = geocode_data[0]["lon"].as_f64();
 
                     if let (Some(lat), Some(lon)) = (lat, lon) {
+                        cache_coordinates("geocode", &cache_key, (lat, lon));
                         return Some((lat, lon));
                     }
                 }
@@ -199,10 +523,9 @@ pub fn resolve_freeform_input(location: &str, api_key: &str) -> Option<(f64, f64
     }
 }
 
-pub fn fetch_weather_data(lat: f64, lon: f64, api_key: &str) -> Option<Value> {
+pub fn fetch_weather_data(lat: f64, lon: f64, api_key: &str, language: &str) -> Result<Value, OwmError> {
     if api_key.is_empty() {
-        eprintln!("API key is missing or invalid.");
-        return None;
+        return Err(OwmError::MissingKey(OWM_STANDARD));
     }
 
     let params = [
@@ -210,32 +533,13 @@ pub fn fetch_weather_data(lat: f64, lon: f64, api_key: &str) -> Option<Value> {
         ("lon", lon.to_string()),
         ("appid", api_key.to_string()),
         ("units", "metric".to_string()),
+        ("lang", language.to_string()),
     ];
 
-    let client = Client::new();
-    match client.get("https://api.openweathermap.org/data/2.5/weather").query(&params).send() {
+    match send(OWM_STANDARD, client().get("https://api.openweathermap.org/data/2.5/weather").query(&params)) {
         Ok(response) => {
-            match response.status() {
-                StatusCode::UNAUTHORIZED => {
-                    return None;
-                }
-                StatusCode::NOT_FOUND => {
-                    eprintln!("Location not found or invalid coordinates.");
-                    return None;
-                }
-                StatusCode::TOO_MANY_REQUESTS => {
-                    eprintln!("API rate limit exceeded. Please try again later.");
-                    return None;
-                }
-                StatusCode::BAD_REQUEST => {
-                    eprintln!("Invalid request parameters. Please check your input.");
-                    return None;
-                }
-                _ if !response.status().is_success() => {
-                    eprintln!("Unexpected API error. Please try again later.");
-                    return None;
-                }
-                _ => {}
+            if !response.status().is_success() {
+                return Err(owm_failure(OWM_STANDARD, response));
             }
             
             match response.json::<Value>() {
@@ -245,18 +549,125 @@ pub fn fetch_weather_data(lat: f64, lon: f64, api_key: &str) -> Option<Value> {
                     // if let Ok(json_string) = serde_json::to_string_pretty(&data) {
                     //     let _ = fs::write("weather.json", json_string);
                     // }
-                    Some(data)
-                }
-                Err(e) => {
-                    eprintln!("Error parsing weather data: {}", e);
-                    None
+                    Ok(data)
                 }
+                Err(e) => Err(OwmError::Other(format!("Error parsing weather data: {}", e))),
             }
         }
-        Err(e) => {
-            eprintln!("Error fetching weather data: {}", e);
-            None
+        Err(e) => Err(OwmError::Other(format!("Error fetching weather data: {}", e))),
+    }
+}
+
+/// Why an OpenWeather weather request failed, by what the user can do about it.
+pub enum OwmError {
+    MissingKey(&'static str),
+    /// 401: the key is wrong, or too new to be active yet
+    BadKey(&'static str, String),
+    /// One Call 3.0 refused a key without the One Call subscription
+    NoSubscription(String),
+    /// 429, or OpenWeather says a call limit was reached
+    QuotaExceeded(&'static str, String),
+    Other(String),
+}
+
+impl OwmError {
+    /// What went wrong, with what to do about it.
+    pub fn guidance(&self) -> String {
+        match self {
+            OwmError::MissingKey(provider) => format!(
+                "No {} API key is set. Enter one in the Configuration tab, or use Open-Meteo, which needs no key",
+                key_name(provider)
+            ),
+            OwmError::BadKey(provider, message) => format!(
+                "OpenWeather rejected the {} API key ({}). Check it in the Configuration tab. New keys can take a couple of hours to activate",
+                key_name(provider), message.trim_end_matches('.')
+            ),
+            OwmError::NoSubscription(message) => format!(
+                "The One Call API key has no One Call 3.0 subscription ({}). Subscribe to \"One Call by Call\" on openweathermap.org, \
+                 or switch to the Standard API in the Configuration tab",
+                message.trim_end_matches('.')
+            ),
+            OwmError::QuotaExceeded(provider, message) if *provider == OWM_ONE_CALL => format!(
+                "The One Call daily call limit was reached ({}). It resets at midnight UTC. Raise the limit in your OpenWeather \
+                 account or switch to the Standard API or Open-Meteo until then",
+                message.trim_end_matches('.')
+            ),
+            OwmError::QuotaExceeded(_, message) => format!(
+                "OpenWeather call limit reached ({}). Wait a minute before generating again, or lengthen the auto-refresh interval",
+                message.trim_end_matches('.')
+            ),
+            OwmError::Other(message) => message.clone(),
+        }
+    }
+}
+
+// "Standard" or "One Call", for the key the provider is called with
+fn key_name(provider: &str) -> &'static str {
+    if provider == OWM_ONE_CALL { "One Call" } else { "Standard" }
+}
+
+// Sorts a failed OpenWeather response by its status and the explanation in its
+// body. One Call answers keys without the subscription with 401 as well, so the
+// message tells them apart from bad keys.
+fn owm_failure(provider: &'static str, response: Response) -> OwmError {
+    let status = response.status();
+    let message = response
+        .json::<Value>()
+        .ok()
+        .and_then(|body| body["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| status.to_string());
+    let lowercase = message.to_lowercase();
+
+    if provider == OWM_ONE_CALL && (status == StatusCode::FORBIDDEN || lowercase.contains("subscription")) {
+        OwmError::NoSubscription(message)
+    } else if status == StatusCode::TOO_MANY_REQUESTS || lowercase.contains("limit") || lowercase.contains("quota") {
+        OwmError::QuotaExceeded(provider, message)
+    } else if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        OwmError::BadKey(provider, message)
+    } else if status == StatusCode::NOT_FOUND {
+        OwmError::Other("Location not found or invalid coordinates".to_string())
+    } else if status == StatusCode::BAD_REQUEST {
+        OwmError::Other(format!("Invalid request parameters ({})", message))
+    } else {
+        OwmError::Other(format!("{} returned {}", provider, message))
+    }
+}
+
+/// Result of checking an OpenWeather API key.
+pub enum KeyCheck {
+    Active,
+    /// Rejected with 401, with OpenWeather's explanation. New keys are rejected
+    /// until they activate, and One Call 3.0 also needs a subscription.
+    Rejected(String),
+    Failed(String),
+}
+
+/// Checks an OpenWeather key with a single request for 0°N 0°E, against the One
+/// Call 3.0 endpoint or the standard current weather endpoint.
+pub fn check_owm_key(api_key: &str, one_call: bool) -> KeyCheck {
+    let (url, exclude) = if one_call {
+        (ONE_CALL_URL, "minutely,hourly,daily,alerts")
+    } else {
+        ("https://api.openweathermap.org/data/2.5/weather", "")
+    };
+    let mut params = vec![("lat", "0"), ("lon", "0"), ("appid", api_key)];
+    if !exclude.is_empty() {
+        params.push(("exclude", exclude));
+    }
+
+    let provider = if one_call { OWM_ONE_CALL } else { OWM_STANDARD };
+    match send(provider, client().get(url).query(&params)) {
+        Ok(response) if response.status().is_success() => KeyCheck::Active,
+        Ok(response) if response.status() == StatusCode::UNAUTHORIZED => {
+            let message = response
+                .json::<Value>()
+                .ok()
+                .and_then(|body| body["message"].as_str().map(str::to_string))
+                .unwrap_or_else(|| "Invalid API key".to_string());
+            KeyCheck::Rejected(message)
         }
+        Ok(response) => KeyCheck::Failed(format!("OpenWeather returned {}", response.status())),
+        Err(e) => KeyCheck::Failed(format!("Error contacting OpenWeather: {}", e)),
     }
 }
 
@@ -269,33 +680,93 @@ pub fn validate_lat_lon(lat: f64, lon: f64) -> Option<(f64, f64)> {
     }
 }
 
-pub fn fetch_one_call_weather_data(lat: f64, lon: f64, api_key: &str) -> Option<Value> {
+pub fn fetch_one_call_weather_data(lat: f64, lon: f64, api_key: &str, exclude: &str, language: &str) -> Result<Value, OwmError> {
     if api_key.is_empty() {
-        eprintln!("One Call API key is missing or invalid.");
-        return None;
+        return Err(OwmError::MissingKey(OWM_ONE_CALL));
     }
 
-    let params = [
+    let mut params = vec![
         ("lat", lat.to_string()),
         ("lon", lon.to_string()),
         ("appid", api_key.to_string()),
-        ("exclude", "minutely".to_string()),
         ("units", "metric".to_string()),
+        ("lang", language.to_string()),
     ];
+    if !exclude.is_empty() {
+        params.push(("exclude", exclude.to_string()));
+    }
 
-    let client = Client::new();
-    match client.get(ONE_CALL_URL).query(&params).send() {
+    match send(OWM_ONE_CALL, client().get(ONE_CALL_URL).query(&params)) {
         Ok(response) => {
-            match response.status() {
-                StatusCode::UNAUTHORIZED => {
-                    return None;
+            if !response.status().is_success() {
+                return Err(owm_failure(OWM_ONE_CALL, response));
+            }
+            
+            match response.json::<Value>() {
+                Ok(data) => {
+                    // Commented out: Optional feature to save weather data for testing/verification
+                    // Allows comparing the synthesized METAR against raw weather data
+                    // if let Ok(json_string) = serde_json::to_string_pretty(&data) {
+                    //     let _ = fs::write("weather.json", json_string);
+                    // }
+                    Ok(data)
                 }
-                StatusCode::NOT_FOUND => {
-                    eprintln!("Location not found or invalid coordinates.");
-                    return None;
+                Err(e) => Err(OwmError::Other(format!("Error parsing weather data: {}", e))),
+            }
+        }
+        Err(e) => Err(OwmError::Other(format!("Error fetching weather data: {}", e))),
+    }
+}
+
+/// Current sea conditions from the Open-Meteo marine API. No key is needed.
+/// Inland coordinates come back without values, or with a 400 status.
+pub fn fetch_marine_data(lat: f64, lon: f64) -> Option<Value> {
+    let params = [
+        ("latitude", lat.to_string()),
+        ("longitude", lon.to_string()),
+        ("current", MARINE_VARIABLES.to_string()),
+    ];
+
+    match send(OPEN_METEO_MARINE, client().get(MARINE_URL).query(&params)) {
+        Ok(response) => {
+            if response.status() == StatusCode::BAD_REQUEST {
+                return None;
+            }
+            if !response.status().is_success() {
+                eprintln!("Unexpected marine API error. Please try again later.");
+                return None;
+            }
+            match response.json::<Value>() {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    eprintln!("Error parsing marine data: {}", e);
+                    None
                 }
+            }
+        }
+        Err(e) => {
+            eprintln!("Error fetching marine data: {}", e);
+            None
+        }
+    }
+}
+
+/// Current conditions from the Open-Meteo forecast API, with wind in m/s. No
+/// key is needed.
+pub fn fetch_open_meteo_weather(lat: f64, lon: f64) -> Option<Value> {
+    let params = [
+        ("latitude", lat.to_string()),
+        ("longitude", lon.to_string()),
+        ("current", OPEN_METEO_VARIABLES.to_string()),
+        ("wind_speed_unit", "ms".to_string()),
+        ("timeformat", "unixtime".to_string()),
+    ];
+
+    match send(OPEN_METEO, client().get(OPEN_METEO_URL).query(&params)) {
+        Ok(response) => {
+            match response.status() {
                 StatusCode::TOO_MANY_REQUESTS => {
-                    eprintln!("API rate limit exceeded. Please try again later.");
+                    eprintln!("Open-Meteo rate limit exceeded. Please try again later.");
                     return None;
                 }
                 StatusCode::BAD_REQUEST => {
@@ -303,30 +774,72 @@ pub fn fetch_one_call_weather_data(lat: f64, lon: f64, api_key: &str) -> Option<
                     return None;
                 }
                 _ if !response.status().is_success() => {
-                    eprintln!("Unexpected API error. Please try again later.");
+                    eprintln!("Unexpected Open-Meteo error. Please try again later.");
                     return None;
                 }
                 _ => {}
             }
-            
             match response.json::<Value>() {
-                Ok(data) => {
-                    // Commented out: Optional feature to save weather data for testing/verification
-                    // Allows comparing the synthesized METAR against raw weather data
-                    // if let Ok(json_string) = serde_json::to_string_pretty(&data) {
-                    //     let _ = fs::write("weather.json", json_string);
-                    // }
-                    Some(data)
-                }
+                Ok(data) => Some(data),
                 Err(e) => {
-                    eprintln!("Error parsing weather data: {}", e);
+                    eprintln!("Error parsing Open-Meteo data: {}", e);
                     None
                 }
             }
         }
         Err(e) => {
-            eprintln!("Error fetching weather data: {}", e);
+            eprintln!("Error fetching Open-Meteo data: {}", e);
+            None
+        }
+    }
+}
+
+/// Current and forecast NOAA space weather scale levels (R, S and G), keyed
+/// "0" for today's observed levels.
+pub fn fetch_space_weather_scales() -> Option<Value> {
+    match send(NOAA_SWPC, client().get(SWPC_SCALES_URL)) {
+        Ok(response) if response.status() == StatusCode::OK => match response.json::<Value>() {
+            Ok(scales) => Some(scales),
+            Err(e) => {
+                eprintln!("Failed to parse space weather data: {}", e);
+                None
+            }
+        },
+        Ok(response) => {
+            eprintln!("Unexpected response when querying NOAA SWPC: {}", response.status());
+            None
+        }
+        Err(e) => {
+            eprintln!("Error querying NOAA SWPC: {}", e);
             None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn station_ids_are_uppercased() {
+        assert_eq!(station_id("kjfk").as_deref(), Some("KJFK"));
+        assert_eq!(station_id(" EGLL ").as_deref(), Some("EGLL"));
+        assert_eq!(station_id("K2S3").as_deref(), Some("K2S3"));
+        assert_eq!(station_id("SEA").as_deref(), Some("SEA"));
+    }
+
+    #[test]
+    fn station_ids_that_would_change_the_url_are_rejected() {
+        for icao in ["", "KS", "KJFKX", "KJ/K", "../x", "KJ K", "K?a=", "KJ%2F", "ÄBCD"] {
+            assert_eq!(station_id(icao), None, "{}", icao);
+        }
+    }
+
+    #[test]
+    fn metar_sources_round_trip_through_their_keys() {
+        for source in MetarSource::ALL {
+            assert_eq!(MetarSource::from_key(source.key()), Some(source));
+        }
+        assert_eq!(MetarSource::from_key("openmeteo"), None);
+    }
+}
@@ -19,32 +19,95 @@
 use std::process;
 use eframe::egui::ViewportBuilder;
 
+mod airport_db;
+mod bookmarks;
+mod briefing;
+mod cli;
 mod config;
+mod coordinates;
+mod crash_report;
+mod disk_cache;
+mod disk_writer;
+mod flight_category;
+mod gamepad;
+mod generation;
+mod history;
+mod hotkey;
+mod icao_region;
 mod input_handler;
+mod marine;
+mod metar_format;
+mod metar_decoder;
 mod metar_generator;
+mod meteo_math;
+mod msfs;
+mod noaa_cache;
+mod notifications;
 mod one_call_metar;
+mod open_meteo;
+mod ops_profile;
+mod owm_response;
+mod paths;
+mod pipe;
+mod save_worker;
+mod scheduler;
+mod severe_weather;
+mod space_weather;
+mod speech;
+mod station_type;
+mod storage;
+mod units;
+mod volcanic_ash;
+mod waypoints;
+mod xplane;
 mod gui;
 
-use config::{load_config, ensure_config_exists};
+use config::{is_valid_profile_name, load_env_file, load_runtime_config, set_active_profile};
 use gui::{MetGenApp};
 
 fn main() -> eframe::Result<()> {
-    // Create default config if it doesn't exist
-    let is_first_run = ensure_config_exists().unwrap_or(false);
+    // Write a crash report on panic; release builds have no console on Windows
+    crash_report::install_panic_hook();
 
-    // Load config, including decrypted keys
-    let (config_json, decrypted_api_key, decrypted_one_call_api_key) = load_config();
+    // Select the profile given with --profile <name>, if any
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--profile") {
+        // "--profile --locked" is a missing name, not a profile called "--locked"
+        let Some(name) = args.get(index + 1).filter(|name| !name.starts_with("--")) else {
+            eprintln!("--profile needs a profile name");
+            process::exit(1);
+        };
+        if !is_valid_profile_name(name) {
+            eprintln!("Invalid profile name: {}", name);
+            process::exit(1);
+        }
+        set_active_profile(name);
+    }
+
+    // Move files older versions kept in the working directory to the platform directories
+    paths::migrate_working_directory();
+
+    // Index the airport database in the background so the first lookup doesn't wait for it
+    std::thread::spawn(airport_db::get);
 
-    if config_json.is_null() {
+    // Pick up API key overrides from an optional .env file
+    load_env_file();
+
+    let mut config = load_runtime_config();
+    if config.is_null() {
         eprintln!("Failed to load configuration.");
         process::exit(1);
     }
 
-    // Insert decrypted keys back into the config Value
-    let mut config = config_json;
-    config["decrypted_api_key"] = serde_json::Value::String(decrypted_api_key);
-    config["decrypted_one_call_api_key"] = serde_json::Value::String(decrypted_one_call_api_key);
-    config["is_first_run"] = serde_json::Value::Bool(is_first_run);
+    // Locked (kiosk) mode can be enabled from config.json or with --locked
+    let locked = args.iter().any(|arg| arg == "--locked")
+        || config["locked"].as_bool().unwrap_or(false);
+    config["locked"] = serde_json::Value::Bool(locked);
+
+    // Subcommands such as `metgen generate` run without opening a window
+    if let Some(code) = cli::run(&args, &config) {
+        process::exit(code);
+    }
 
     let options = eframe::NativeOptions {
         viewport: ViewportBuilder::default()
@@ -0,0 +1,87 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Sea state for water landings. For coastal and water coordinates the Open-Meteo
+// marine API gives the wave and swell height and the water temperature, which
+// are added to the remarks of a synthesized METAR:
+//
+//   W18/H12      water temperature 18 °C, significant wave height 12 dm (Annex 3 style)
+//   SWELL08/270  swell 0.8 m from 270°
+
+use serde_json::Value;
+use crate::input_handler;
+use crate::units::{Celsius, Meters};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MarineConditions {
+    pub wave_height: Option<Meters>,
+    pub swell_height: Option<Meters>,
+    pub swell_direction: Option<f64>,
+    pub water_temperature: Option<Celsius>,
+}
+
+/// Current sea conditions at the position, or None inland or when the marine
+/// API is unavailable.
+pub fn fetch(lat: f64, lon: f64) -> Option<MarineConditions> {
+    let data = input_handler::fetch_marine_data(lat, lon)?;
+    let conditions = parse(&data);
+    (conditions != MarineConditions::default()).then_some(conditions)
+}
+
+fn parse(data: &Value) -> MarineConditions {
+    let current = &data["current"];
+    MarineConditions {
+        wave_height: current["wave_height"].as_f64().map(Meters),
+        swell_height: current["swell_wave_height"].as_f64().map(Meters),
+        swell_direction: current["swell_wave_direction"].as_f64(),
+        water_temperature: current["sea_surface_temperature"].as_f64().map(Celsius),
+    }
+}
+
+/// Remark groups for the conditions, e.g. ["W18/H12", "SWELL08/270"].
+pub fn remarks(conditions: &MarineConditions) -> Vec<String> {
+    let mut remarks = Vec::new();
+    let decimeters = |Meters(meters): Meters| (meters * 10.0).round().clamp(0.0, 999.0) as u32;
+
+    let temperature = conditions.water_temperature.map(|Celsius(celsius)| {
+        let rounded = celsius.round() as i32;
+        format!("{}{:02}", if rounded < 0 { "M" } else { "" }, rounded.abs())
+    });
+    match (temperature, conditions.wave_height) {
+        (Some(temperature), Some(height)) => remarks.push(format!("W{}/H{:02}", temperature, decimeters(height))),
+        (Some(temperature), None) => remarks.push(format!("W{}/H//", temperature)),
+        (None, Some(height)) => remarks.push(format!("W///H{:02}", decimeters(height))),
+        (None, None) => {}
+    }
+
+    if let Some(height) = conditions.swell_height {
+        let direction = conditions
+            .swell_direction
+            .map_or("///".to_string(), |direction| format!("{:03}", direction.round() as u32 % 360));
+        remarks.push(format!("SWELL{:02}/{}", decimeters(height), direction));
+    }
+    remarks
+}
+
+/// Appends the marine remarks to a METAR, opening a remarks section if it has none.
+pub fn add_remarks(metar: &str, conditions: &MarineConditions) -> String {
+    let remarks = remarks(conditions);
+    if remarks.is_empty() {
+        return metar.to_string();
+    }
+    let separator = if metar.split_whitespace().any(|group| group == "RMK") { " " } else { " RMK " };
+    format!("{}{}{}", metar, separator, remarks.join(" "))
+}
@@ -0,0 +1,711 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Decodes METAR and TAF text, generated or pasted, back into structured fields
+// and a plain-English breakdown. For a TAF only the base forecast is decoded;
+// change groups are kept as text.
+
+use chrono::{DateTime, Datelike, Months, TimeZone, Utc};
+use regex::Regex;
+use std::sync::OnceLock;
+use crate::units::{self, TemperatureUnit};
+
+pub struct Wind {
+    // None for variable (VRB) or unknown (///) direction
+    pub direction: Option<u32>,
+    pub variable: bool,
+    pub speed_kt: f64,
+    pub gust_kt: Option<f64>,
+    // Extremes of a varying direction, e.g. 280V350
+    pub variable_between: Option<(u32, u32)>,
+}
+
+pub struct CloudLayer {
+    pub cover: String,
+    pub height_ft: Option<u32>,
+    // CB or TCU
+    pub cloud_type: Option<String>,
+}
+
+#[derive(Default)]
+pub struct DecodedMetar {
+    // METAR, SPECI or TAF
+    pub report_type: String,
+    pub station: Option<String>,
+    // Day of month, hour and minute (UTC)
+    pub time: Option<(u32, u32, u32)>,
+    // TAF validity, e.g. "1212/1318"
+    pub validity: Option<String>,
+    pub auto: bool,
+    pub corrected: bool,
+    pub wind: Option<Wind>,
+    pub visibility_m: Option<f64>,
+    // Set when the visibility was given in statute miles
+    pub visibility_statute: bool,
+    pub cavok: bool,
+    pub weather: Vec<String>,
+    pub clouds: Vec<CloudLayer>,
+    // No significant / no detected / clear sky groups
+    pub sky_clear: Option<String>,
+    pub vertical_visibility_ft: Option<u32>,
+    pub temperature: Option<i32>,
+    pub dew_point: Option<i32>,
+    pub pressure_hpa: Option<f64>,
+    // Set when the pressure was given as an altimeter setting
+    pub altimeter_inhg: Option<f64>,
+    // Sea group (W18/H12 or W18/S3) and marine swell remark (SWELL08/270)
+    pub water_temperature: Option<i32>,
+    pub wave_height_m: Option<f64>,
+    // State of the sea, WMO code 0 (glassy) to 9 (phenomenal)
+    pub sea_state: Option<u32>,
+    // Height in meters and the direction it comes from
+    pub swell: Option<(f64, Option<u32>)>,
+    pub trend: Option<String>,
+    pub remarks: Option<String>,
+    pub unknown: Vec<String>,
+}
+
+impl DecodedMetar {
+    /// Height of the lowest broken or overcast layer, or the vertical visibility, in feet.
+    pub fn ceiling_ft(&self) -> Option<u32> {
+        self.clouds
+            .iter()
+            .filter(|layer| layer.cover == "BKN" || layer.cover == "OVC")
+            .filter_map(|layer| layer.height_ft)
+            .chain(self.vertical_visibility_ft)
+            .min()
+    }
+
+    /// The report's time group as a date: the latest matching time no later than
+    /// `now`, in this month or the one before.
+    pub fn observed_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let (day, hour, minute) = self.time?;
+        [now, now.checked_sub_months(Months::new(1))?]
+            .into_iter()
+            .filter_map(|month| Utc.with_ymd_and_hms(month.year(), month.month(), day, hour, minute, 0).single())
+            .find(|time| *time <= now + chrono::Duration::minutes(5))
+    }
+}
+
+struct Patterns {
+    wind: Regex,
+    variable_wind: Regex,
+    weather: Regex,
+    cloud: Regex,
+    temperature: Regex,
+    sea: Regex,
+    swell: Regex,
+}
+
+static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+
+fn patterns() -> &'static Patterns {
+    PATTERNS.get_or_init(|| Patterns {
+        wind: Regex::new(r"^(\d{3}|VRB|///)(\d{2,3}|//)(?:G(\d{2,3}))?(KT|MPS|KMH)$").unwrap(),
+        variable_wind: Regex::new(r"^(\d{3})V(\d{3})$").unwrap(),
+        weather: Regex::new(
+            r"^(-|\+|VC)?(MI|BC|PR|DR|BL|SH|TS|FZ)?((?:DZ|RA|SN|SG|IC|PL|GR|GS|UP|BR|FG|FU|VA|DU|SA|HZ|PY|PO|SQ|FC|SS|DS)*)$",
+        )
+        .unwrap(),
+        cloud: Regex::new(r"^(FEW|SCT|BKN|OVC)(\d{3}|///)?(CB|TCU|///)?$").unwrap(),
+        temperature: Regex::new(r"^(M?\d{2})/(M?\d{2})?$").unwrap(),
+        sea: Regex::new(r"^W(M?\d{2}|//)/([SH])(\d{1,3}|/+)$").unwrap(),
+        swell: Regex::new(r"^SWELL(\d{2,3})/(\d{3}|///)$").unwrap(),
+    })
+}
+
+fn is_trend(token: &str) -> bool {
+    matches!(token, "BECMG" | "TEMPO" | "NOSIG" | "INTER" | "FCST")
+        || token.starts_with("PROB")
+        || token.len() == 8 && token.starts_with("FM") && token[2..].chars().all(|c| c.is_ascii_digit())
+}
+
+fn all_digits(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Decodes a METAR, SPECI or TAF. Groups that are not recognised are collected
+/// in `unknown` rather than failing the whole report.
+pub fn decode_metar(text: &str) -> DecodedMetar {
+    let patterns = patterns();
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let mut decoded = DecodedMetar {
+        report_type: "METAR".to_string(),
+        ..Default::default()
+    };
+    let mut whole_miles: Option<f64> = None;
+
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = tokens[index];
+        let rest = || tokens[index..].join(" ");
+
+        if token == "RMK" {
+            decoded.remarks = Some(tokens[index + 1..].join(" "));
+            for &remark in &tokens[index + 1..] {
+                decode_sea_group(remark, &mut decoded);
+            }
+            break;
+        }
+        if is_trend(token) {
+            decoded.trend = Some(rest());
+            break;
+        }
+
+        let mut whole = None;
+        match token {
+            "METAR" | "SPECI" | "TAF" => decoded.report_type = token.to_string(),
+            "AMD" => {}
+            "AUTO" => decoded.auto = true,
+            "COR" | "CC" => decoded.corrected = true,
+            "CAVOK" => {
+                decoded.cavok = true;
+                decoded.visibility_m = Some(10_000.0);
+            }
+            "SKC" | "CLR" | "NSC" | "NCD" => decoded.sky_clear = Some(token.to_string()),
+            _ if decoded.station.is_none()
+                && token.len() == 4
+                && token.starts_with(|c: char| c.is_ascii_uppercase())
+                && token.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) =>
+            {
+                decoded.station = Some(token.to_string());
+            }
+            _ if token.len() == 7 && token.ends_with('Z') && all_digits(&token[..6]) => {
+                let number = |range: std::ops::Range<usize>| token[range].parse().unwrap_or(0);
+                decoded.time = Some((number(0..2), number(2..4), number(4..6)));
+            }
+            _ if token.len() == 9 && token.as_bytes()[4] == b'/' && all_digits(&token[..4]) && all_digits(&token[5..]) => {
+                decoded.validity = Some(token.to_string());
+            }
+            _ if patterns.wind.is_match(token) => {
+                let captures = patterns.wind.captures(token).unwrap();
+                let factor = match &captures[4] {
+                    "MPS" => units::KNOTS_PER_MPS,
+                    "KMH" => units::KNOTS_PER_KMH,
+                    _ => 1.0,
+                };
+                decoded.wind = Some(Wind {
+                    direction: captures[1].parse().ok(),
+                    variable: &captures[1] == "VRB",
+                    speed_kt: captures[2].parse::<f64>().unwrap_or(0.0) * factor,
+                    gust_kt: captures.get(3).and_then(|gust| gust.as_str().parse::<f64>().ok()).map(|gust| gust * factor),
+                    variable_between: None,
+                });
+            }
+            _ if patterns.variable_wind.is_match(token) => {
+                let captures = patterns.variable_wind.captures(token).unwrap();
+                if let Some(wind) = decoded.wind.as_mut() {
+                    wind.variable_between = Some((captures[1].parse().unwrap_or(0), captures[2].parse().unwrap_or(0)));
+                }
+            }
+            _ if token.get(..4).is_some_and(all_digits) && (token.len() == 4 || token.ends_with("NDV")) => {
+                decoded.visibility_m = token[..4].parse().ok();
+            }
+            _ if token.ends_with("SM") => {
+                decoded.visibility_statute = true;
+                decoded.visibility_m = parse_statute_miles(&token[..token.len() - 2])
+                    .map(|miles| units::statute_miles_to_meters(miles + whole_miles.unwrap_or(0.0)));
+            }
+            // A whole number of miles is written apart from its fraction, as in "1 1/2SM"
+            _ if token.len() == 1 && all_digits(token) => whole = token.parse().ok(),
+            _ if token.starts_with('R') && token.contains('/') => {
+                // Runway visual range is not decoded
+            }
+            _ if token.starts_with("VV") => {
+                decoded.vertical_visibility_ft = token[2..].parse::<u32>().ok().map(|height| height * 100);
+            }
+            _ if patterns.cloud.is_match(token) => {
+                let captures = patterns.cloud.captures(token).unwrap();
+                decoded.clouds.push(CloudLayer {
+                    cover: captures[1].to_string(),
+                    height_ft: captures.get(2).and_then(|height| height.as_str().parse::<u32>().ok()).map(|height| height * 100),
+                    cloud_type: captures.get(3).map(|kind| kind.as_str().to_string()).filter(|kind| kind != "///"),
+                });
+            }
+            _ if patterns.temperature.is_match(token) => {
+                let captures = patterns.temperature.captures(token).unwrap();
+                decoded.temperature = parse_temperature(&captures[1]);
+                decoded.dew_point = captures.get(2).and_then(|dew| parse_temperature(dew.as_str()));
+            }
+            _ if token.len() == 5 && token.starts_with('Q') && all_digits(&token[1..]) => {
+                decoded.pressure_hpa = token[1..].parse().ok();
+            }
+            _ if token.len() == 5 && token.starts_with('A') && all_digits(&token[1..]) => {
+                let inhg = token[1..].parse::<f64>().unwrap_or(0.0) / 100.0;
+                decoded.altimeter_inhg = Some(inhg);
+                decoded.pressure_hpa = Some(units::inhg_to_hpa(inhg));
+            }
+            _ if decode_sea_group(token, &mut decoded) => {}
+            _ if token != "//" && patterns.weather.captures(token).is_some_and(|c| !c[3].is_empty() || c.get(2).is_some()) => {
+                decoded.weather.push(token.to_string());
+            }
+            _ => decoded.unknown.push(token.to_string()),
+        }
+        whole_miles = whole;
+        index += 1;
+    }
+
+    decoded
+}
+
+// Sea temperature and state or wave height, or the swell; false for any other group
+fn decode_sea_group(token: &str, decoded: &mut DecodedMetar) -> bool {
+    let patterns = patterns();
+    if let Some(captures) = patterns.sea.captures(token) {
+        decoded.water_temperature = parse_temperature(&captures[1]);
+        let value = captures[3].parse::<u32>().ok();
+        match &captures[2] {
+            "S" => decoded.sea_state = value,
+            _ => decoded.wave_height_m = value.map(|decimeters| f64::from(decimeters) / 10.0),
+        }
+        true
+    } else if let Some(captures) = patterns.swell.captures(token) {
+        let height = captures[1].parse::<u32>().unwrap_or(0);
+        decoded.swell = Some((f64::from(height) / 10.0, captures[2].parse().ok()));
+        true
+    } else {
+        false
+    }
+}
+
+/// Statute miles from "10", "1/2", "M1/4" or "P6"; less than and more than are taken at the limit.
+pub fn parse_statute_miles(value: &str) -> Option<f64> {
+    let value = value.trim_start_matches(['M', 'P']);
+    match value.split_once('/') {
+        Some((numerator, denominator)) => {
+            let denominator = denominator.parse::<f64>().ok()?;
+            (denominator > 0.0).then_some(numerator.parse::<f64>().ok()? / denominator)
+        }
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_temperature(value: &str) -> Option<i32> {
+    match value.strip_prefix('M') {
+        Some(value) => value.parse::<i32>().ok().map(|t| -t),
+        None => value.parse().ok(),
+    }
+}
+
+/// Headwind and crosswind components in knots for a runway heading in degrees.
+/// A negative headwind is a tailwind; a positive crosswind comes from the right.
+pub fn wind_components(direction: u32, speed_kt: f64, runway_heading: u32) -> (f64, f64) {
+    let angle = (direction as f64 - runway_heading as f64).to_radians();
+    (speed_kt * angle.cos(), speed_kt * angle.sin())
+}
+
+const WEATHER_DESCRIPTORS: [(&str, &str); 8] = [
+    ("MI", "shallow"), ("BC", "patches of"), ("PR", "partial"), ("DR", "low drifting"),
+    ("BL", "blowing"), ("SH", "showers of"), ("TS", "thunderstorm with"), ("FZ", "freezing"),
+];
+
+// Condition icons, most significant first
+const THUNDERSTORM_ICON: &str = "⛈";
+const ICON_PRECEDENCE: [&str; 8] = [THUNDERSTORM_ICON, "🌪", "❄", "🌧", "🌫", "☁", "⛅", "☀"];
+
+// Code, name and condition icon of each present weather phenomenon
+const WEATHER_PHENOMENA: [(&str, &str, &str); 22] = [
+    ("DZ", "drizzle", "🌧"), ("RA", "rain", "🌧"), ("SN", "snow", "❄"), ("SG", "snow grains", "❄"),
+    ("IC", "ice crystals", "❄"), ("PL", "ice pellets", "❄"), ("GR", "hail", "🌧"), ("GS", "small hail", "🌧"),
+    ("UP", "unknown precipitation", "🌧"), ("BR", "mist", "🌫"), ("FG", "fog", "🌫"), ("FU", "smoke", "🌫"),
+    ("VA", "volcanic ash", "🌫"), ("DU", "dust", "🌫"), ("SA", "sand", "🌫"), ("HZ", "haze", "🌫"),
+    ("PY", "spray", "🌫"), ("PO", "dust whirls", "🌪"), ("SQ", "squalls", "🌪"), ("FC", "funnel cloud", "🌪"),
+    ("SS", "sandstorm", "🌪"), ("DS", "duststorm", "🌪"),
+];
+
+/// Icon for the most significant condition in a report: thunderstorm, squalls,
+/// snow, rain, fog, cloud or clear. None when the report has neither weather nor sky.
+pub fn condition_icon(decoded: &DecodedMetar) -> Option<&'static str> {
+    let patterns = patterns();
+    let mut icons: Vec<&str> = Vec::new();
+    for group in &decoded.weather {
+        let Some(captures) = patterns.weather.captures(group) else { continue };
+        // Weather in the vicinity isn't at the station
+        if captures.get(1).is_some_and(|m| m.as_str() == "VC") {
+            continue;
+        }
+        if captures.get(2).is_some_and(|m| m.as_str() == "TS") {
+            icons.push(THUNDERSTORM_ICON);
+        }
+        let phenomena = &captures[3];
+        icons.extend(
+            (0..phenomena.len() / 2)
+                .filter_map(|i| WEATHER_PHENOMENA.iter().find(|(code, _, _)| *code == &phenomena[i * 2..i * 2 + 2]))
+                .map(|(_, _, icon)| *icon),
+        );
+    }
+
+    if decoded.ceiling_ft().is_some() {
+        icons.push("☁");
+    } else if !decoded.clouds.is_empty() {
+        icons.push("⛅");
+    } else if decoded.cavok || decoded.sky_clear.is_some() {
+        icons.push("☀");
+    }
+    ICON_PRECEDENCE.into_iter().find(|icon| icons.contains(icon))
+}
+
+/// Plain-English reading of a present weather group, e.g. "-SHRA" is "light showers of rain".
+pub fn describe_weather(group: &str) -> String {
+    let Some(captures) = patterns().weather.captures(group) else {
+        return group.to_string();
+    };
+    let mut words = Vec::new();
+    match captures.get(1).map(|m| m.as_str()) {
+        Some("-") => words.push("light"),
+        Some("+") => words.push("heavy"),
+        _ => {}
+    }
+    let phenomena = &captures[3];
+    match captures.get(2).map(|m| m.as_str()) {
+        Some("TS") if phenomena.is_empty() => words.push("thunderstorm"),
+        Some("SH") if phenomena.is_empty() => words.push("showers"),
+        Some(descriptor) => words.extend(WEATHER_DESCRIPTORS.iter().filter(|(code, _)| *code == descriptor).map(|(_, word)| *word)),
+        None => {}
+    }
+    let names: Vec<&str> = (0..phenomena.len() / 2)
+        .filter_map(|i| WEATHER_PHENOMENA.iter().find(|(code, _, _)| *code == &phenomena[i * 2..i * 2 + 2]))
+        .map(|(_, name, _)| *name)
+        .collect();
+    let names = names.join(" and ");
+    if !names.is_empty() {
+        words.push(&names);
+    }
+    let mut text = words.join(" ");
+    if captures.get(1).is_some_and(|m| m.as_str() == "VC") {
+        text.push_str(" in the vicinity");
+    }
+    text
+}
+
+fn cover_name(cover: &str) -> &str {
+    match cover {
+        "FEW" => "few",
+        "SCT" => "scattered",
+        "BKN" => "broken",
+        "OVC" => "overcast",
+        _ => cover,
+    }
+}
+
+// WMO code table 3700
+fn sea_state_name(code: u32) -> &'static str {
+    match code {
+        0 => "calm (glassy)",
+        1 => "calm (rippled)",
+        2 => "smooth",
+        3 => "slight",
+        4 => "moderate",
+        5 => "rough",
+        6 => "very rough",
+        7 => "high",
+        8 => "very high",
+        _ => "phenomenal",
+    }
+}
+
+// Quarter-mile precision, e.g. 1.5 becomes "1 1/2"
+fn format_miles(miles: f64) -> String {
+    let quarters = (miles * 4.0).round() as u32;
+    let fraction = match quarters % 4 {
+        1 => "1/4",
+        2 => "1/2",
+        3 => "3/4",
+        _ => "",
+    };
+    match (quarters / 4, fraction) {
+        (whole, "") => whole.to_string(),
+        (0, fraction) => fraction.to_string(),
+        (whole, fraction) => format!("{} {}", whole, fraction),
+    }
+}
+
+fn format_feet(feet: u32) -> String {
+    if feet >= 1000 {
+        format!("{},{:03} ft", feet / 1000, feet % 1000)
+    } else {
+        format!("{} ft", feet)
+    }
+}
+
+/// Plain-English breakdown of a decoded report as (element, description) rows,
+/// with temperatures in the given unit.
+pub fn describe(decoded: &DecodedMetar, temperature_unit: TemperatureUnit) -> Vec<(&'static str, String)> {
+    let mut rows = Vec::new();
+
+    if let Some(station) = &decoded.station {
+        rows.push(("Station", format!("{} ({})", station, decoded.report_type)));
+    }
+    if let Some((day, hour, minute)) = decoded.time {
+        let mut text = format!("Day {} at {:02}:{:02} UTC", day, hour, minute);
+        if decoded.auto {
+            text.push_str(", automated");
+        }
+        if decoded.corrected {
+            text.push_str(", corrected");
+        }
+        rows.push(("Issued", text));
+    }
+    if let Some(validity) = &decoded.validity {
+        let (from, to) = validity.split_once('/').unwrap_or((validity, ""));
+        rows.push(("Valid", format!(
+            "Day {} {}:00 to day {} {}:00 UTC",
+            &from[..2], &from[2..], to.get(..2).unwrap_or(""), to.get(2..).unwrap_or("")
+        )));
+    }
+
+    if let Some(wind) = &decoded.wind {
+        let mut text = if wind.speed_kt < 0.5 {
+            "Calm".to_string()
+        } else {
+            match wind.direction {
+                Some(direction) => format!("{:03}° at {:.0} kt", direction, wind.speed_kt),
+                None if wind.variable => format!("Variable at {:.0} kt", wind.speed_kt),
+                None => format!("Direction unknown at {:.0} kt", wind.speed_kt),
+            }
+        };
+        if let Some(gust) = wind.gust_kt {
+            text.push_str(&format!(" gusting {:.0} kt", gust));
+        }
+        if let Some((from, to)) = wind.variable_between {
+            text.push_str(&format!(", varying between {:03}° and {:03}°", from, to));
+        }
+        rows.push(("Wind", text));
+    }
+
+    if decoded.cavok {
+        rows.push(("Visibility", "CAVOK: 10 km or more, no cloud below 5,000 ft, no significant weather".to_string()));
+    } else if let Some(meters) = decoded.visibility_m {
+        let miles = units::meters_to_statute_miles(meters);
+        let text = if decoded.visibility_statute {
+            format!("{} SM ({:.1} km)", format_miles(miles), meters / 1000.0)
+        } else if meters >= 9999.0 {
+            "10 km or more".to_string()
+        } else if meters >= 5000.0 {
+            format!("{:.0} km ({:.1} SM)", meters / 1000.0, miles)
+        } else {
+            format!("{:.0} m ({:.2} SM)", meters, miles)
+        };
+        rows.push(("Visibility", text));
+    }
+
+    if !decoded.weather.is_empty() {
+        let weather: Vec<String> = decoded.weather.iter().map(|group| describe_weather(group)).collect();
+        rows.push(("Weather", weather.join(", ")));
+    }
+
+    let mut clouds: Vec<String> = decoded
+        .clouds
+        .iter()
+        .map(|layer| {
+            let mut text = cover_name(&layer.cover).to_string();
+            if let Some(height) = layer.height_ft {
+                text.push_str(&format!(" at {}", format_feet(height)));
+            }
+            match layer.cloud_type.as_deref() {
+                Some("CB") => text.push_str(" (cumulonimbus)"),
+                Some("TCU") => text.push_str(" (towering cumulus)"),
+                _ => {}
+            }
+            text
+        })
+        .collect();
+    if let Some(height) = decoded.vertical_visibility_ft {
+        clouds.push(format!("sky obscured, vertical visibility {}", format_feet(height)));
+    }
+    if let Some(clear) = &decoded.sky_clear {
+        clouds.push(match clear.as_str() {
+            "NSC" => "no significant cloud".to_string(),
+            "NCD" => "no cloud detected".to_string(),
+            _ => "clear".to_string(),
+        });
+    }
+    if !clouds.is_empty() {
+        let mut text = clouds.join(", ");
+        if let Some(ceiling) = decoded.ceiling_ft() {
+            text.push_str(&format!("; ceiling {}", format_feet(ceiling)));
+        }
+        rows.push(("Clouds", text));
+    }
+
+    if let Some(temperature) = decoded.temperature {
+        let mut text = temperature_unit.format(f64::from(temperature));
+        if let Some(dew_point) = decoded.dew_point {
+            text.push_str(&format!(", dew point {}", temperature_unit.format(f64::from(dew_point))));
+        }
+        rows.push(("Temperature", text));
+    }
+
+    match (decoded.altimeter_inhg, decoded.pressure_hpa) {
+        (Some(inhg), Some(hpa)) => rows.push(("Pressure", format!("{:.2} inHg ({:.0} hPa)", inhg, hpa))),
+        (None, Some(hpa)) => rows.push(("Pressure", format!("{:.0} hPa ({:.2} inHg)", hpa, units::hpa_to_inhg(hpa)))),
+        _ => {}
+    }
+
+    let mut sea = Vec::new();
+    if let Some(temperature) = decoded.water_temperature {
+        sea.push(format!("water {}", temperature_unit.format(f64::from(temperature))));
+    }
+    if let Some(height) = decoded.wave_height_m {
+        sea.push(format!("waves {:.1} m", height));
+    }
+    if let Some(state) = decoded.sea_state {
+        sea.push(format!("sea {}", sea_state_name(state)));
+    }
+    if !sea.is_empty() {
+        rows.push(("Sea", sea.join(", ")));
+    }
+    if let Some((height, direction)) = decoded.swell {
+        rows.push(("Swell", match direction {
+            Some(direction) => format!("{:.1} m from {:03}°", height, direction),
+            None => format!("{:.1} m", height),
+        }));
+    }
+
+    if let Some(trend) = &decoded.trend {
+        rows.push((if decoded.report_type == "TAF" { "Changes" } else { "Trend" }, trend.clone()));
+    }
+    if let Some(remarks) = &decoded.remarks {
+        rows.push(("Remarks", remarks.clone()));
+    }
+    if !decoded.unknown.is_empty() {
+        rows.push(("Not decoded", decoded.unknown.join(" ")));
+    }
+
+    rows
+}
+
+/// One plain-English sentence with the main conditions, e.g. "Wind 240° at 12 kt
+/// gusting 20 kt, visibility 10 km or more, few at 3,000 ft, temperature 12 °C,
+/// dew point 8 °C, pressure 1013 hPa (29.91 inHg)."
+pub fn summary(decoded: &DecodedMetar, temperature_unit: TemperatureUnit) -> String {
+    let parts: Vec<String> = describe(decoded, temperature_unit)
+        .into_iter()
+        .filter_map(|(element, text)| match element {
+            "Wind" => Some(format!("wind {}", lowercase_first(&text))),
+            "Visibility" if decoded.cavok => Some("CAVOK".to_string()),
+            "Visibility" => Some(format!("visibility {}", text)),
+            "Weather" | "Clouds" => Some(lowercase_first(&text)),
+            "Temperature" => Some(format!("temperature {}", text)),
+            "Pressure" => Some(format!("pressure {}", text)),
+            _ => None,
+        })
+        .collect();
+    if parts.is_empty() {
+        return String::new();
+    }
+    let sentence = parts.join(", ");
+    let mut chars = sentence.chars();
+    let first = chars.next().map(|c| c.to_uppercase().to_string()).unwrap_or_default();
+    format!("{}{}.", first, chars.as_str())
+}
+
+fn lowercase_first(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(actual: Option<f64>, expected: f64) -> bool {
+        actual.is_some_and(|actual| (actual - expected).abs() < 0.5)
+    }
+
+    #[test]
+    fn icao_report_groups() {
+        let decoded = decode_metar("METAR EDDM 141150Z AUTO 27015G25KT 240V300 9999 -RA FEW030 BKN045CB 15/M02 Q1013 NOSIG");
+        assert_eq!(decoded.station.as_deref(), Some("EDDM"));
+        assert_eq!(decoded.time, Some((14, 11, 50)));
+        assert!(decoded.auto);
+        let wind = decoded.wind.as_ref().unwrap();
+        assert_eq!((wind.direction, wind.speed_kt, wind.gust_kt), (Some(270), 15.0, Some(25.0)));
+        assert_eq!(wind.variable_between, Some((240, 300)));
+        assert_eq!(decoded.visibility_m, Some(9999.0));
+        assert_eq!(decoded.weather, ["-RA"]);
+        let clouds: Vec<(&str, Option<u32>, Option<&str>)> = decoded
+            .clouds
+            .iter()
+            .map(|layer| (layer.cover.as_str(), layer.height_ft, layer.cloud_type.as_deref()))
+            .collect();
+        assert_eq!(clouds, [("FEW", Some(3000), None), ("BKN", Some(4500), Some("CB"))]);
+        assert_eq!(decoded.ceiling_ft(), Some(4500));
+        assert_eq!((decoded.temperature, decoded.dew_point), (Some(15), Some(-2)));
+        assert_eq!(decoded.pressure_hpa, Some(1013.0));
+        assert_eq!(decoded.trend.as_deref(), Some("NOSIG"));
+        assert!(decoded.unknown.is_empty(), "{:?}", decoded.unknown);
+    }
+
+    #[test]
+    fn us_report_groups() {
+        let decoded = decode_metar("KJFK 141151Z VRB03KT 1 1/2SM BR VV008 M01/ A2992 RMK AO2 SLP132");
+        let wind = decoded.wind.as_ref().unwrap();
+        assert_eq!((wind.direction, wind.variable, wind.speed_kt), (None, true, 3.0));
+        assert!(decoded.visibility_statute);
+        assert!(near(decoded.visibility_m, 2414.0));
+        assert_eq!(decoded.weather, ["BR"]);
+        assert_eq!(decoded.vertical_visibility_ft, Some(800));
+        assert_eq!(decoded.ceiling_ft(), Some(800));
+        assert_eq!((decoded.temperature, decoded.dew_point), (Some(-1), None));
+        assert_eq!(decoded.altimeter_inhg, Some(29.92));
+        assert!(near(decoded.pressure_hpa, 1013.0));
+        assert_eq!(decoded.remarks.as_deref(), Some("AO2 SLP132"));
+    }
+
+    #[test]
+    fn wind_in_other_units_is_converted_to_knots() {
+        let decoded = decode_metar("UUEE 141200Z 18005MPS CAVOK 20/10 Q1015");
+        assert!((decoded.wind.unwrap().speed_kt - 5.0 * units::KNOTS_PER_MPS).abs() < 1e-9);
+        assert!(decoded.cavok);
+        assert_eq!(decoded.visibility_m, Some(10_000.0));
+    }
+
+    #[test]
+    fn taf_base_forecast_and_change_groups() {
+        let decoded = decode_metar("TAF EGLL 141100Z 1412/1518 24010KT 9999 SCT035 TEMPO 1415/1419 4000 SHRA");
+        assert_eq!(decoded.report_type, "TAF");
+        assert_eq!(decoded.validity.as_deref(), Some("1412/1518"));
+        assert_eq!(decoded.visibility_m, Some(9999.0));
+        assert_eq!(decoded.trend.as_deref(), Some("TEMPO 1415/1419 4000 SHRA"));
+    }
+
+    #[test]
+    fn sea_groups_and_unknown_groups() {
+        let decoded = decode_metar("LGAV 141150Z 36012KT 9999 NSC 28/18 Q1012 W24/S3 XYZZY RMK SWELL08/270");
+        assert_eq!(decoded.sky_clear.as_deref(), Some("NSC"));
+        assert_eq!((decoded.water_temperature, decoded.sea_state), (Some(24), Some(3)));
+        assert_eq!(decoded.swell, Some((0.8, Some(270))));
+        assert_eq!(decoded.unknown, ["XYZZY"]);
+
+        let decoded = decode_metar("KXYZ 141150Z 00000KT 10SM CLR 20/10 A3000 WM02/H15");
+        assert_eq!(decoded.water_temperature, Some(-2));
+        assert_eq!(decoded.wave_height_m, Some(1.5));
+    }
+
+    #[test]
+    fn statute_mile_values() {
+        assert_eq!(parse_statute_miles("10"), Some(10.0));
+        assert_eq!(parse_statute_miles("3/4"), Some(0.75));
+        assert_eq!(parse_statute_miles("M1/4"), Some(0.25));
+        assert_eq!(parse_statute_miles("P6"), Some(6.0));
+        assert_eq!(parse_statute_miles("1/0"), None);
+        assert_eq!(parse_statute_miles("//"), None);
+    }
+}
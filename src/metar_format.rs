@@ -0,0 +1,1060 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Formatting shared by both METAR generators. Weather values arrive as an
+// Observation filled in by each provider's parse_weather_data, typed in the
+// provider's units: speeds in m/s, visibility in meters, pressure in hPa and
+// temperatures in degrees Celsius.
+
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde_json::Value;
+use crate::metar_decoder;
+use crate::meteo_math;
+use crate::units::{
+    self, Celsius, Hpa, Knots, MetarUnits, Meters, MetersPerSecond, PressureUnit, StatuteMiles,
+    VisibilityUnit, WindUnit,
+};
+
+/// Weather at one point in time, as reported by a provider. Missing values are None.
+#[derive(Clone, Default, Debug)]
+pub struct Observation {
+    pub wind_direction: Option<f64>,
+    pub wind_speed: Option<MetersPerSecond>,
+    pub wind_gust: Option<MetersPerSecond>,
+    pub visibility: Option<Meters>,
+    // Cloud cover in percent
+    pub cloud_coverage: Option<f64>,
+    pub temperature: Option<Celsius>,
+    pub dew_point: Option<Celsius>,
+    // Relative humidity in percent, used when the provider has no dew point
+    pub humidity: Option<f64>,
+    pub pressure: Option<Hpa>,
+    // OpenWeather condition codes
+    pub weather_codes: Vec<i32>,
+    // When the provider observed the conditions, from its `dt` field
+    pub observed_at: Option<DateTime<Utc>>,
+}
+
+impl Observation {
+    /// Temperature and dew point. Without a reported dew point it is worked out
+    /// from the relative humidity.
+    pub fn temperature_and_dew_point(&self) -> Option<(Celsius, Celsius)> {
+        let temperature = self.temperature?;
+        let dew_point = self.dew_point.or_else(|| Some(meteo_math::dew_point(temperature, self.humidity?)))?;
+        Some((temperature, dew_point))
+    }
+
+    /// Reported visibility, or with `estimate` an estimate from the weather when
+    /// the provider left it out.
+    pub fn visibility_or_estimate(&self, estimate: bool) -> Option<Meters> {
+        self.visibility.or_else(|| estimate.then(|| estimate_visibility(&self.weather_codes, self.humidity)).flatten())
+    }
+}
+
+// Typical visibility in meters for OpenWeather condition codes that reduce it,
+// used when the provider leaves visibility out. Rough values in the middle of
+// the range each METAR group is reported at: fog below 1000 m, mist 1000-5000 m,
+// heavy rain around 4000 m, heavy snow well below that.
+const VISIBILITY_ESTIMATES: [(i32, f64); 42] = [
+    (200, 8000.0), (201, 6000.0), (202, 3000.0), (230, 8000.0), (231, 6000.0), (232, 3000.0),
+    (300, 8000.0), (301, 5000.0), (302, 3000.0), (310, 7000.0), (311, 5000.0), (312, 3000.0),
+    (313, 6000.0), (314, 4000.0), (321, 6000.0),
+    (500, 9000.0), (501, 7000.0), (502, 4000.0), (503, 3000.0), (504, 2000.0), (511, 5000.0),
+    (520, 8000.0), (521, 6000.0), (522, 4000.0), (531, 6000.0),
+    (600, 4000.0), (601, 2000.0), (602, 800.0),  (611, 4000.0), (612, 5000.0), (613, 4000.0),
+    (615, 5000.0), (616, 3000.0), (620, 4000.0), (621, 2000.0), (622, 800.0),
+    (701, 3000.0), (711, 4000.0), (721, 5000.0), (731, 3000.0), (741, 500.0), (751, 3000.0),
+];
+
+// Relative humidity (percent) above which moisture alone is taken to reduce
+// visibility, with the estimate used: near saturation gives mist
+const HUMIDITY_VISIBILITY: [(f64, f64); 2] = [(97.0, 4000.0), (90.0, 8000.0)];
+
+/// Visibility estimated from the condition codes and relative humidity, the lowest
+/// of the estimates that apply: each reducing condition's typical visibility, mist
+/// for a near-saturated atmosphere, and 10 km otherwise. None without any weather
+/// to go on. Dust, sand, ash and squalls (761, 762, 771) aren't reliable enough to
+/// estimate from and count as unrestricted; a tornado (781) gives 1000 m.
+pub fn estimate_visibility(weather_codes: &[i32], humidity: Option<f64>) -> Option<Meters> {
+    if weather_codes.is_empty() && humidity.is_none() {
+        return None;
+    }
+    let from_weather = weather_codes.iter().filter_map(|&id| match id {
+        781 => Some(1000.0),
+        _ => VISIBILITY_ESTIMATES.iter().find(|&&(code, _)| code == id).map(|&(_, meters)| meters),
+    });
+    let from_humidity = humidity.and_then(|humidity| {
+        HUMIDITY_VISIBILITY.iter().find(|&&(threshold, _)| humidity >= threshold).map(|&(_, meters)| meters)
+    });
+    let estimate = from_weather.chain(from_humidity).fold(10000.0, f64::min);
+    Some(Meters(estimate))
+}
+
+/// Formatting choices beyond the units, captured with the generation settings.
+#[derive(Clone, Default, Debug)]
+pub struct FormatOptions {
+    // Minutes past the hour reports are stamped with; empty for the exact time
+    pub observation_minutes: Vec<u32>,
+    // Estimate visibility from the weather when the provider leaves it out
+    pub estimate_visibility: bool,
+    // Give cloud layers a base estimated from the temperature/dew point spread
+    pub estimate_cloud_base: bool,
+    // Add the RMK section: sea level pressure, T-group and peak wind
+    pub include_remarks: bool,
+    // Stamp reports with the provider's observation time instead of the current time
+    pub use_observation_time: bool,
+}
+
+impl FormatOptions {
+    /// Options from "observation_minutes", "estimate_visibility",
+    /// "estimate_cloud_base" and "include_remarks" (all on by default) and
+    /// "use_observation_time" (off by default) in the config.
+    pub fn from_config(config: &Value) -> FormatOptions {
+        FormatOptions {
+            observation_minutes: observation_minutes_from_config(config),
+            estimate_visibility: config["estimate_visibility"].as_bool().unwrap_or(true),
+            estimate_cloud_base: config["estimate_cloud_base"].as_bool().unwrap_or(true),
+            include_remarks: config["include_remarks"].as_bool().unwrap_or(true),
+            use_observation_time: config["use_observation_time"].as_bool().unwrap_or(false),
+        }
+    }
+
+    /// Time a report of this observation is stamped with: the provider's
+    /// observation time when enabled and known, otherwise `now`, moved back to the
+    /// standard observation minutes.
+    pub fn report_time(&self, observation: &Observation, now: DateTime<Utc>) -> DateTime<Utc> {
+        let time = observation.observed_at.filter(|_| self.use_observation_time).unwrap_or(now);
+        observation_time(time, &self.observation_minutes)
+    }
+
+    /// The estimated cloud base for these temperatures, if cloud bases are estimated.
+    pub fn cloud_base(&self, temp_dew: Option<(Celsius, Celsius)>) -> Option<u32> {
+        temp_dew.filter(|_| self.estimate_cloud_base).map(estimate_cloud_base)
+    }
+}
+
+/// Controls which optional groups end up in a synthesized METAR. Some older
+/// simulators misparse fully standards-compliant reports.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum OutputPolicy {
+    /// Annex 3 groups only: no remarks, NCD instead of CLR, cloud layers always
+    /// carry a height (or ///), and only BECMG/TEMPO/NOSIG trends
+    StrictIcao,
+    /// US practice, as generated: CLR, remarks and the forecast section
+    #[default]
+    FaaStyle,
+    /// The main body only, without AUTO, remarks, trends or missing-data groups
+    FsxSafe,
+}
+
+impl OutputPolicy {
+    pub const ALL: [OutputPolicy; 3] = [OutputPolicy::StrictIcao, OutputPolicy::FaaStyle, OutputPolicy::FsxSafe];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OutputPolicy::StrictIcao => "Strict ICAO",
+            OutputPolicy::FaaStyle => "FAA-style",
+            OutputPolicy::FsxSafe => "FSX-safe (simplified)",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            OutputPolicy::StrictIcao => "strict",
+            OutputPolicy::FaaStyle => "faa",
+            OutputPolicy::FsxSafe => "fsx",
+        }
+    }
+
+    pub fn from_config(config: &Value) -> OutputPolicy {
+        let key = config.get("output_policy").and_then(|p| p.as_str()).unwrap_or_default();
+        OutputPolicy::ALL
+            .into_iter()
+            .find(|policy| policy.key() == key)
+            .unwrap_or_default()
+    }
+}
+
+const CLOUD_COVERS: [&str; 4] = ["FEW", "SCT", "BKN", "OVC"];
+
+/// Rewrites a generated METAR to follow the output policy.
+pub fn apply_output_policy(metar: &str, policy: OutputPolicy) -> String {
+    if policy == OutputPolicy::FaaStyle {
+        return metar.to_string();
+    }
+
+    let automatic = metar.split_whitespace().any(|group| group == "AUTO");
+    let mut groups = Vec::new();
+    let mut skipping_trend = false;
+
+    for group in metar.split_whitespace() {
+        // Remarks are national practice and always come last
+        if group == "RMK" {
+            break;
+        }
+
+        // A trend section runs until the next trend keyword
+        if matches!(group, "FCST" | "BECMG" | "TEMPO") {
+            skipping_trend = match policy {
+                OutputPolicy::StrictIcao => group == "FCST",
+                _ => true,
+            };
+        }
+        if skipping_trend || (group == "NOSIG" && policy == OutputPolicy::FsxSafe) {
+            continue;
+        }
+
+        match policy {
+            OutputPolicy::StrictIcao => groups.push(match group {
+                "CLR" | "SKC" if automatic => "NCD".to_string(),
+                "CLR" | "SKC" => "NSC".to_string(),
+                _ if CLOUD_COVERS.contains(&group) => format!("{}///", group),
+                _ => group.to_string(),
+            }),
+            _ => {
+                let missing = group.trim_start_matches(['Q', 'A']).chars().all(|c| c == '/');
+                if matches!(group, "AUTO" | "COR") || missing {
+                    continue;
+                }
+                // Older sims only understand VRB for an unknown wind direction
+                match group.strip_prefix("///") {
+                    Some(wind) if wind.ends_with("KT") || wind.ends_with("MPS") => groups.push(format!("VRB{}", wind)),
+                    _ => groups.push(group.to_string()),
+                }
+            }
+        }
+    }
+
+    groups.join(" ")
+}
+
+/// Rewrites the wind, visibility and pressure groups of a report in other units,
+/// e.g. Q1013 as A2991 or 9999 as 10SM, for tools that expect a different
+/// convention. Everything else, remarks included, is left as it is.
+pub fn convert_units(metar: &str, units: &MetarUnits) -> String {
+    let tokens: Vec<&str> = metar.split_whitespace().collect();
+    let mut groups = Vec::new();
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = tokens[index];
+        if token == "RMK" {
+            groups.extend(tokens[index..].iter().map(|group| group.to_string()));
+            break;
+        }
+
+        // A whole number of miles is written apart from its fraction, as in "1 1/2SM"
+        let fraction = tokens.get(index + 1).and_then(|next| next.strip_suffix("SM"));
+        if let (Ok(whole), Some(fraction)) = (token.parse::<u32>(), fraction) {
+            if token.len() == 1 {
+                let miles = metar_decoder::parse_statute_miles(fraction).map(|miles| miles + f64::from(whole));
+                groups.push(convert_statute_miles(miles, units).unwrap_or_else(|| format!("{} {}SM", token, fraction)));
+                index += 2;
+                continue;
+            }
+        }
+
+        let converted = if let Some(miles) = token.strip_suffix("SM") {
+            convert_statute_miles(metar_decoder::parse_statute_miles(miles), units)
+        } else {
+            convert_wind(token, units).or_else(|| convert_meters(token, units)).or_else(|| convert_pressure(token, units))
+        };
+        groups.push(converted.unwrap_or_else(|| token.to_string()));
+        index += 1;
+    }
+    groups.join(" ")
+}
+
+// "27015G25KT" as "27008G13MPS" and the other way round
+fn convert_wind(token: &str, units: &MetarUnits) -> Option<String> {
+    let (speeds, to_mps): (&str, fn(f64) -> f64) = match (token.strip_suffix("KT"), token.strip_suffix("MPS")) {
+        (Some(speeds), _) if units.wind == WindUnit::MetersPerSecond => (speeds, units::knots_to_mps),
+        (_, Some(speeds)) if units.wind == WindUnit::Knots => (speeds, |mps| mps),
+        _ => return None,
+    };
+    let direction = speeds.get(..3).filter(|direction| {
+        *direction == "VRB" || *direction == "///" || direction.chars().all(|c| c.is_ascii_digit())
+    })?;
+    let (speed, gust) = match speeds[3..].split_once('G') {
+        Some((speed, gust)) => (speed, Some(gust)),
+        None => (&speeds[3..], None),
+    };
+    let in_unit = |value: &str| -> Option<String> {
+        let mps = to_mps(value.parse::<f64>().ok()?);
+        let converted = match units.wind {
+            WindUnit::Knots => Knots::from(MetersPerSecond(mps)).0,
+            WindUnit::MetersPerSecond => mps,
+        };
+        Some(format!("{:02}", converted.round() as i32))
+    };
+    let speed = if speed == "//" { speed.to_string() } else { in_unit(speed)? };
+    let gust = match gust {
+        Some(gust) => format!("G{}", in_unit(gust)?),
+        None => String::new(),
+    };
+    let unit = if units.wind == WindUnit::Knots { "KT" } else { "MPS" };
+    Some(format!("{}{}{}{}", direction, speed, gust, unit))
+}
+
+// Visibility in meters ("9999", "0800NDV") as statute miles
+fn convert_meters(token: &str, units: &MetarUnits) -> Option<String> {
+    if units.visibility != VisibilityUnit::StatuteMiles {
+        return None;
+    }
+    let meters = token.strip_suffix("NDV").unwrap_or(token);
+    if meters.len() != 4 || !meters.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // 9999 is 10 km or more
+    let meters = match meters.parse::<f64>().ok()? {
+        meters if meters >= 9999.0 => 10000.0,
+        meters => meters,
+    };
+    Some(format_visibility(Some(Meters(meters)), units, &[]))
+}
+
+fn convert_statute_miles(miles: Option<f64>, units: &MetarUnits) -> Option<String> {
+    if units.visibility != VisibilityUnit::Meters {
+        return None;
+    }
+    Some(format_visibility(Some(Meters(units::statute_miles_to_meters(miles?))), units, &[]))
+}
+
+// "Q1013" as "A2991" and the other way round
+fn convert_pressure(token: &str, units: &MetarUnits) -> Option<String> {
+    let value = token.get(1..).filter(|value| value.len() == 4 && value.chars().all(|c| c.is_ascii_digit()))?;
+    let value = value.parse::<f64>().ok()?;
+    match (token.as_bytes()[0], units.pressure) {
+        (b'Q', PressureUnit::InchesOfMercury) => Some(format_pressure(Some(Hpa(value)), units)),
+        (b'A', PressureUnit::Hectopascals) => Some(format_pressure(Some(Hpa(units::inhg_to_hpa(value / 100.0))), units)),
+        _ => None,
+    }
+}
+
+/// Trend period groups (FMddhhmm TLddhhmm) for a forecast period. The day is
+/// always written, so periods crossing midnight UTC read unambiguously, and a
+/// period ending at midnight ends at 2400 of its own day rather than 0000 of
+/// the next.
+pub fn trend_period(start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    format!("FM{} TL{}", start.format("%d%H%M"), period_end(end))
+}
+
+fn period_end(end: DateTime<Utc>) -> String {
+    if end.hour() == 0 && end.minute() == 0 {
+        format!("{}2400", (end - Duration::days(1)).format("%d"))
+    } else {
+        end.format("%d%H%M").to_string()
+    }
+}
+
+/// Time a report is stamped with. With standard observation minutes, e.g.
+/// [20, 50], the time is moved back to the latest of them, the way routine
+/// reports are stamped. An empty list keeps the exact time.
+pub fn observation_time(now: DateTime<Utc>, standard_minutes: &[u32]) -> DateTime<Utc> {
+    standard_minutes
+        .iter()
+        .filter_map(|&minute| {
+            let candidate = now.with_minute(minute)?.with_second(0)?.with_nanosecond(0)?;
+            Some(if candidate > now { candidate - Duration::hours(1) } else { candidate })
+        })
+        .max()
+        .unwrap_or(now)
+}
+
+/// Observation time group, e.g. "151753Z".
+pub fn format_observation_time(time: DateTime<Utc>) -> String {
+    time.format("%d%H%MZ").to_string()
+}
+
+/// How station identifiers shorter than four characters are made valid for
+/// the METAR header. Simulators reject reports whose identifier isn't four
+/// letters or digits.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum IdentifierPadding {
+    /// Refuse to generate; the identifier must already be four characters
+    #[default]
+    Reject,
+    /// US convention for three-character FAA identifiers: "1N7" becomes "K1N7"
+    PrefixK,
+    /// Fill up with X: "BEA" becomes "BEAX"
+    TrailingX,
+}
+
+impl IdentifierPadding {
+    pub const ALL: [IdentifierPadding; 3] =
+        [IdentifierPadding::Reject, IdentifierPadding::PrefixK, IdentifierPadding::TrailingX];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            IdentifierPadding::Reject => "Require 4 characters",
+            IdentifierPadding::PrefixK => "Prefix K (US)",
+            IdentifierPadding::TrailingX => "Pad with X",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            IdentifierPadding::Reject => "reject",
+            IdentifierPadding::PrefixK => "prefix_k",
+            IdentifierPadding::TrailingX => "trailing_x",
+        }
+    }
+
+    pub fn from_config(config: &Value) -> IdentifierPadding {
+        let key = config.get("identifier_padding").and_then(|p| p.as_str()).unwrap_or_default();
+        IdentifierPadding::ALL
+            .into_iter()
+            .find(|padding| padding.key() == key)
+            .unwrap_or_default()
+    }
+}
+
+/// The station identifier for the METAR header: whitespace removed, uppercased,
+/// letters and digits only, and exactly four characters (padded if allowed).
+pub fn station_identifier(icao: &str, padding: IdentifierPadding) -> Result<String, String> {
+    let identifier: String = icao.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+    if identifier.is_empty() {
+        return Err("A station identifier is required".to_string());
+    }
+    if !identifier.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("Station identifier {} may only contain letters and digits", identifier));
+    }
+
+    match (identifier.len(), padding) {
+        (4, _) => Ok(identifier),
+        (len, _) if len > 4 => Err(format!("Station identifier {} is longer than 4 characters", identifier)),
+        (3, IdentifierPadding::PrefixK) => Ok(format!("K{}", identifier)),
+        (_, IdentifierPadding::TrailingX) => Ok(format!("{:X<4}", identifier)),
+        _ => Err(format!(
+            "Station identifier {} must be 4 characters (or choose a padding scheme in the Configuration tab)",
+            identifier
+        )),
+    }
+}
+
+/// Standard observation minutes from the config, sorted; empty for the exact time.
+pub fn observation_minutes_from_config(config: &Value) -> Vec<u32> {
+    let mut minutes: Vec<u32> = config
+        .get("observation_minutes")
+        .and_then(|minutes| minutes.as_array())
+        .map(|minutes| {
+            minutes
+                .iter()
+                .filter_map(|minute| minute.as_u64())
+                .filter(|&minute| minute < 60)
+                .map(|minute| minute as u32)
+                .collect()
+        })
+        .unwrap_or_default();
+    minutes.sort_unstable();
+    minutes.dedup();
+    minutes
+}
+
+// Winds up to this speed with no steady direction are reported as VRB
+pub const LIGHT_VARIABLE_WIND_KT: f64 = 6.0;
+
+pub fn format_wind(
+    direction: Option<f64>,
+    speed: Option<MetersPerSecond>,
+    gust: Option<MetersPerSecond>,
+    units: &MetarUnits,
+) -> String {
+    let spd = speed.unwrap_or(MetersPerSecond(0.0));
+    let gst = gust.unwrap_or(MetersPerSecond(0.0));
+
+    let (in_unit, unit): (fn(MetersPerSecond) -> f64, &str) = match units.wind {
+        WindUnit::Knots => (|speed| Knots::from(speed).0, "KT"),
+        WindUnit::MetersPerSecond => (|speed| speed.0, "MPS"),
+    };
+    let speed = in_unit(spd).round() as i32;
+    let gust = in_unit(gst).round() as i32;
+
+    // Calm is reported without direction or gust
+    if speed == 0 {
+        return format!("00000{}", unit);
+    }
+
+    // Unknown direction is VRB for light winds only, otherwise reported as missing
+    let direction = if let Some(dir) = direction.filter(|dir| *dir >= 0.0) {
+        format!("{:03}", dir as i32)
+    } else if Knots::from(spd).0 <= LIGHT_VARIABLE_WIND_KT {
+        "VRB".to_string()
+    } else {
+        "///".to_string()
+    };
+
+    if gust > speed {
+        format!("{}{:02}G{:02}{}", direction, speed, gust, unit)
+    } else {
+        format!("{}{:02}{}", direction, speed, unit)
+    }
+}
+
+pub fn format_visibility(visibility: Option<Meters>, units: &MetarUnits, weather_codes: &[i32]) -> String {
+    let Some(Meters(vis)) = visibility else {
+        return "////".to_string();
+    };
+
+    if units.visibility == VisibilityUnit::Meters {
+        let rounded_vis = ((vis / 100.0).round() * 100.0) as i32;
+        return if rounded_vis >= 10000 {
+            "9999".to_string()
+        } else {
+            format!("{:04}", rounded_vis)
+        };
+    }
+
+    // The providers cap visibility at 10 km, which only means 10SM or more when
+    // nothing is reducing it
+    let reducing_conditions = weather_codes.iter().any(|id| (200..800).contains(id));
+    if (vis - 10000.0).abs() < f64::EPSILON && !reducing_conditions {
+        return "10SM".to_string();
+    }
+
+    // Statute miles to the nearest quarter
+    let StatuteMiles(visibility_sm) = Meters(vis).into();
+    let whole = visibility_sm.floor() as i32;
+    let quarters = ((visibility_sm - whole as f64) * 4.0).round() as i32;
+    let (whole, quarters) = if quarters == 4 { (whole + 1, 0) } else { (whole, quarters) };
+    let divisor = gcd(quarters, 4);
+
+    match (whole, quarters) {
+        (whole, 0) => format!("{}SM", whole),
+        (0, quarters) => format!("{}/{}SM", quarters / divisor, 4 / divisor),
+        (whole, quarters) => format!("{} {}/{}SM", whole, quarters / divisor, 4 / divisor),
+    }
+}
+
+pub fn format_pressure(pressure: Option<Hpa>, units: &MetarUnits) -> String {
+    match (pressure, units.pressure) {
+        (Some(p), PressureUnit::InchesOfMercury) => format!("A{:04}", units::altimeter_hundredths(p)),
+        (Some(p), PressureUnit::Hectopascals) => format!("Q{:04}", p.0.round() as i32),
+        (None, PressureUnit::InchesOfMercury) => "A////".to_string(),
+        (None, PressureUnit::Hectopascals) => "Q////".to_string(),
+    }
+}
+
+// OpenWeather condition codes and their METAR present weather groups. Codes 8xx
+// describe cloud cover, which is reported by format_cloud_coverage instead.
+const WEATHER_MAP: [(i32, &str); 50] = [
+    (200, "TSRA"), (201, "TSRA"), (202, "+TSRA"),
+    (210, "TS"),   (211, "TS"),   (212, "+TS"),
+    (221, "TS"),   (230, "TSRA"), (231, "TSRA"), (232, "+TSRA"),
+    (300, "-DZ"),  (301, "DZ"),   (302, "+DZ"),  (310, "-DZRA"),
+    (311, "DZRA"), (312, "+DZRA"),(313, "SHRA"), (314, "+SHRA"),
+    (321, "SHRA"), (500, "-RA"),  (501, "RA"),   (502, "+RA"),
+    (503, "+RA"),  (504, "+RA"),  (511, "FZRA"), (520, "-SHRA"),
+    (521, "SHRA"), (522, "+SHRA"),(531, "SHRA"), (600, "-SN"),
+    (601, "SN"),   (602, "+SN"),  (611, "SLT"),  (612, "-SHSL"),
+    (613, "SHSL"), (615, "-RASN"),(616, "RASN"), (620, "-SHSN"),
+    (621, "SHSN"), (622, "+SHSN"),(701, "BR"),   (711, "FU"),
+    (721, "HZ"),   (731, "DU"),   (741, "FG"),   (751, "SA"),
+    (761, "DU"),   (762, "VA"),   (771, "SQ"),   (781, "+FC"),
+];
+
+/// Present weather groups for a list of OpenWeather condition codes.
+pub fn format_weather_conditions(weather_codes: &[i32]) -> String {
+    weather_codes
+        .iter()
+        .copied()
+        .filter(|&id| id < 800)
+        .filter_map(|id| WEATHER_MAP.iter().find(|&&(code, _)| code == id))
+        .map(|&(_, abbreviation)| abbreviation)
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Cloud group for the cover in percent, with the cloud base when one is given,
+/// e.g. "BKN025". Most simulators reject layers without a height.
+pub fn format_cloud_coverage(cloud_coverage: Option<f64>, base_ft: Option<u32>) -> String {
+    let cover = match cloud_coverage.map(|c| c.round() as i32) {
+        Some(0) => "CLR",
+        Some(c) if c <= 25 => "FEW",
+        Some(c) if c <= 50 => "SCT",
+        Some(c) if c <= 87 => "BKN",
+        Some(c) if c <= 100 => "OVC",
+        _ => "CLR",
+    };
+    match base_ft {
+        Some(height) if cover != "CLR" => format!("{}{:03}", cover, height / 100),
+        _ => cover.to_string(),
+    }
+}
+
+// Cloud at or above this height is of no operational significance for CAVOK and NSC
+const SIGNIFICANT_CLOUD_FT: u32 = 5000;
+// OpenWeather condition code for fog
+const FOG: i32 = 741;
+// Below this visibility, an overcast sky in fog is reported as obscured
+const OBSCURED_SKY_VISIBILITY_M: f64 = 1000.0;
+
+/// Visibility and cloud groups, e.g. "9999 SCT030". Metric reports follow ICAO
+/// practice: 10 km or more with no significant weather and no cloud below
+/// 5,000 ft is CAVOK, and a sky without cloud below 5,000 ft is NSC instead of the
+/// US-style CLR. A layer of unknown height or unknown cover is never taken as
+/// insignificant. An overcast sky in fog below 1,000 m is obscured and reported
+/// as vertical visibility, e.g. "0200 VV001".
+pub fn format_visibility_and_clouds(
+    visibility: Option<Meters>,
+    cloud_coverage: Option<f64>,
+    cloud_base_ft: Option<u32>,
+    weather_codes: &[i32],
+    units: &MetarUnits,
+) -> String {
+    let visibility_group = format_visibility(visibility, units, weather_codes);
+    let clouds = format_cloud_coverage(cloud_coverage, cloud_base_ft);
+    let obscured = weather_codes.contains(&FOG)
+        && clouds.starts_with("OVC")
+        && visibility.is_some_and(|Meters(vis)| vis < OBSCURED_SKY_VISIBILITY_M);
+    if obscured {
+        let height = cloud_base_ft.map_or("///".to_string(), |base| format!("{:03}", base / 100));
+        return format!("{} VV{}", visibility_group, height);
+    }
+    if units.visibility != VisibilityUnit::Meters || cloud_coverage.is_none() {
+        return format!("{} {}", visibility_group, clouds);
+    }
+
+    let no_significant_cloud = clouds == "CLR" || cloud_base_ft.is_some_and(|base| base >= SIGNIFICANT_CLOUD_FT);
+    if !no_significant_cloud {
+        return format!("{} {}", visibility_group, clouds);
+    }
+    if visibility_group == "9999" && format_weather_conditions(weather_codes).is_empty() {
+        "CAVOK".to_string()
+    } else {
+        format!("{} NSC", visibility_group)
+    }
+}
+
+// Limits of an estimated cloud base in feet: the lowest reportable layer, and
+// a cap above which a spread says more about dry air than about cloud
+const MIN_CLOUD_BASE_FT: f64 = 100.0;
+const MAX_CLOUD_BASE_FT: f64 = 12000.0;
+
+/// Cloud base estimated from the temperature/dew point spread: about 400 ft per
+/// degree Celsius, the rate at which a rising parcel closes the spread. A dew point
+/// above the temperature counts as saturated. Clamped to 100-12,000 ft and rounded
+/// to the reporting increments: 100 ft up to 5,000 ft, 500 ft up to 10,000 ft and
+/// 1,000 ft above.
+pub fn estimate_cloud_base((Celsius(temperature), Celsius(dew_point)): (Celsius, Celsius)) -> u32 {
+    let height = ((temperature - dew_point).max(0.0) * 400.0).clamp(MIN_CLOUD_BASE_FT, MAX_CLOUD_BASE_FT);
+    let increment = if height <= 5000.0 {
+        100.0
+    } else if height <= 10000.0 {
+        500.0
+    } else {
+        1000.0
+    };
+    ((height / increment).round() * increment) as u32
+}
+
+/// Formats a temperature in whole degrees for the body of the report. Halves round
+/// away from zero, and anything below zero keeps its sign: -0.4 is "M00", -0.5 and
+/// -0.6 are "M01".
+pub fn format_temperature(Celsius(celsius): Celsius) -> String {
+    let rounded = celsius.round();
+    if celsius < 0.0 {
+        format!("M{:02}", rounded.abs() as i32)
+    } else {
+        format!("{:02}", rounded as i32)
+    }
+}
+
+pub fn format_temp_dew(temp_dew: Option<(Celsius, Celsius)>) -> String {
+    match temp_dew {
+        Some((temp, dew_point)) => format!("{}/{}", format_temperature(temp), format_temperature(dew_point)),
+        None => "/// ///".to_string(),
+    }
+}
+
+/// The remarks T-group with temperature and dew point in tenths, e.g. 17.2/10.6
+/// becomes "T01720106" and -0.4/-2.1 becomes "T10041021".
+pub fn temperature_remark(temp: Celsius, dew_point: Celsius) -> String {
+    let tenths = |Celsius(celsius): Celsius| {
+        let value = (celsius * 10.0).round() as i32;
+        format!("{}{:03}", if value < 0 { 1 } else { 0 }, value.abs())
+    };
+    format!("T{}{}", tenths(temp), tenths(dew_point))
+}
+
+// Gusts above this are reported as a peak wind remark, as at US stations
+const PEAK_WIND_KT: f64 = 25.0;
+
+/// The peak wind remark for gusts above 25 kt, e.g. "PK WND 28032/53" for a gust
+/// of 32 kt from 280° at 53 minutes past the hour. The gust is taken as the
+/// peak at the observation time, in the units of the wind group. Without a
+/// direction there is no remark.
+pub fn peak_wind_remark(
+    direction: Option<f64>,
+    gust: Option<MetersPerSecond>,
+    report_time: DateTime<Utc>,
+    units: &MetarUnits,
+) -> Option<String> {
+    let direction = direction.filter(|dir| *dir >= 0.0)?;
+    let gust = gust.filter(|gust| Knots::from(*gust).0 > PEAK_WIND_KT)?;
+    let speed = match units.wind {
+        WindUnit::Knots => Knots::from(gust).0,
+        WindUnit::MetersPerSecond => gust.0,
+    };
+    Some(format!("PK WND {:03}{:02}/{:02}", direction as i32, speed.round() as i32, report_time.minute()))
+}
+
+/// The remarks section, including the leading " RMK", or an empty string if
+/// there is nothing to add or remarks are turned off.
+pub fn format_remarks(
+    observation: &Observation,
+    report_time: DateTime<Utc>,
+    units: &MetarUnits,
+    options: &FormatOptions,
+) -> String {
+    if !options.include_remarks {
+        return String::new();
+    }
+
+    let mut remarks = Vec::new();
+    if let Some(peak_wind) = peak_wind_remark(observation.wind_direction, observation.wind_gust, report_time, units) {
+        remarks.push(peak_wind);
+    }
+    if units.sea_level_pressure_remark {
+        if let Some(pressure) = observation.pressure {
+            remarks.push(units::sea_level_pressure_remark(pressure));
+        }
+    }
+    if let Some((temp, dew_point)) = observation.temperature_and_dew_point() {
+        remarks.push(temperature_remark(temp, dew_point));
+    }
+
+    if remarks.is_empty() {
+        String::new()
+    } else {
+        format!(" RMK {}", remarks.join(" "))
+    }
+}
+
+pub fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn temperatures_round_away_from_zero_and_keep_their_sign() {
+        assert_eq!(format_temperature(Celsius(-0.4)), "M00");
+        assert_eq!(format_temperature(Celsius(-0.5)), "M01");
+        assert_eq!(format_temperature(Celsius(-0.6)), "M01");
+        assert_eq!(format_temperature(Celsius(-0.0)), "00");
+        assert_eq!(format_temperature(Celsius(0.4)), "00");
+        assert_eq!(format_temperature(Celsius(0.5)), "01");
+        assert_eq!(format_temperature(Celsius(-10.5)), "M11");
+        assert_eq!(format_temperature(Celsius(23.49)), "23");
+    }
+
+    #[test]
+    fn temperature_groups_pair_temperature_and_dew_point() {
+        assert_eq!(format_temp_dew(Some((Celsius(0.4), Celsius(-0.4)))), "00/M00");
+        assert_eq!(format_temp_dew(Some((Celsius(12.0), Celsius(-0.6)))), "12/M01");
+        assert_eq!(format_temp_dew(None), "/// ///");
+    }
+
+    #[test]
+    fn temperature_remarks_are_in_signed_tenths() {
+        assert_eq!(temperature_remark(Celsius(-0.4), Celsius(-0.4)), "T10041004");
+        assert_eq!(temperature_remark(Celsius(0.4), Celsius(0.4)), "T00040004");
+        assert_eq!(temperature_remark(Celsius(-0.5), Celsius(-0.6)), "T10051006");
+        assert_eq!(temperature_remark(Celsius(17.2), Celsius(10.6)), "T01720106");
+        assert_eq!(temperature_remark(Celsius(-0.04), Celsius(0.0)), "T00000000");
+    }
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, month, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn trend_period_starting_at_2330() {
+        assert_eq!(trend_period(utc(6, 14, 23, 30), utc(6, 14, 23, 59)), "FM142330 TL142359");
+    }
+
+    #[test]
+    fn trend_period_crossing_midnight_rolls_the_day_over() {
+        assert_eq!(trend_period(utc(6, 14, 23, 30), utc(6, 15, 1, 30)), "FM142330 TL150130");
+    }
+
+    #[test]
+    fn trend_period_crossing_a_month_end() {
+        assert_eq!(trend_period(utc(1, 31, 23, 30), utc(2, 1, 1, 30)), "FM312330 TL010130");
+        assert_eq!(trend_period(utc(2, 28, 22, 0), utc(3, 1, 0, 0)), "FM282200 TL282400");
+    }
+
+    #[test]
+    fn trend_period_ending_at_midnight_ends_at_2400() {
+        assert_eq!(trend_period(utc(6, 14, 22, 0), utc(6, 15, 0, 0)), "FM142200 TL142400");
+        assert_eq!(trend_period(utc(6, 14, 23, 0), utc(6, 15, 0, 30)), "FM142300 TL150030");
+        assert_eq!(trend_period(utc(6, 15, 0, 0), utc(6, 15, 2, 0)), "FM150000 TL150200");
+    }
+
+    fn knots() -> MetarUnits {
+        MetarUnits::default()
+    }
+
+    fn statute_miles() -> MetarUnits {
+        MetarUnits { visibility: VisibilityUnit::StatuteMiles, pressure: PressureUnit::InchesOfMercury, ..MetarUnits::default() }
+    }
+
+    fn mps(knots: f64) -> Option<MetersPerSecond> {
+        Some(MetersPerSecond(knots / units::KNOTS_PER_MPS))
+    }
+
+    #[test]
+    fn calm_wind_has_no_direction_or_gust() {
+        assert_eq!(format_wind(Some(270.0), Some(MetersPerSecond(0.2)), mps(10.0), &knots()), "00000KT");
+        assert_eq!(format_wind(None, None, None, &knots()), "00000KT");
+    }
+
+    #[test]
+    fn light_wind_without_direction_is_variable() {
+        assert_eq!(format_wind(None, mps(4.0), None, &knots()), "VRB04KT");
+        assert_eq!(format_wind(None, mps(6.0), None, &knots()), "VRB06KT");
+        assert_eq!(format_wind(None, mps(12.0), None, &knots()), "///12KT");
+    }
+
+    #[test]
+    fn gusts_are_reported_above_the_mean_speed() {
+        assert_eq!(format_wind(Some(270.0), mps(19.0), mps(29.0), &knots()), "27019G29KT");
+        assert_eq!(format_wind(Some(90.0), mps(8.0), mps(8.0), &knots()), "09008KT");
+        assert_eq!(format_wind(Some(5.0), mps(8.0), mps(3.0), &knots()), "00508KT");
+    }
+
+    #[test]
+    fn speeds_of_100_or_more_have_three_digits() {
+        assert_eq!(format_wind(Some(270.0), mps(117.0), None, &knots()), "270117KT");
+        assert_eq!(format_wind(Some(270.0), mps(95.0), mps(136.0), &knots()), "27095G136KT");
+    }
+
+    #[test]
+    fn wind_in_meters_per_second() {
+        let units = MetarUnits { wind: WindUnit::MetersPerSecond, ..MetarUnits::default() };
+        assert_eq!(format_wind(Some(270.0), Some(MetersPerSecond(10.0)), Some(MetersPerSecond(15.0)), &units), "27010G15MPS");
+    }
+
+    #[test]
+    fn metric_visibility() {
+        assert_eq!(format_visibility(Some(Meters(10000.0)), &knots(), &[]), "9999");
+        assert_eq!(format_visibility(Some(Meters(9960.0)), &knots(), &[]), "9999");
+        assert_eq!(format_visibility(Some(Meters(4449.0)), &knots(), &[]), "4400");
+        assert_eq!(format_visibility(Some(Meters(200.0)), &knots(), &[]), "0200");
+        assert_eq!(format_visibility(None, &knots(), &[]), "////");
+    }
+
+    #[test]
+    fn statute_mile_visibility_in_quarters() {
+        let mile = units::METERS_PER_STATUTE_MILE;
+        assert_eq!(format_visibility(Some(Meters(10000.0)), &statute_miles(), &[]), "10SM");
+        assert_eq!(format_visibility(Some(Meters(10000.0)), &statute_miles(), &[500]), "6 1/4SM");
+        assert_eq!(format_visibility(Some(Meters(mile)), &statute_miles(), &[]), "1SM");
+        assert_eq!(format_visibility(Some(Meters(mile * 0.25)), &statute_miles(), &[]), "1/4SM");
+        assert_eq!(format_visibility(Some(Meters(mile * 0.5)), &statute_miles(), &[]), "1/2SM");
+        assert_eq!(format_visibility(Some(Meters(mile * 0.75)), &statute_miles(), &[]), "3/4SM");
+        assert_eq!(format_visibility(Some(Meters(mile * 1.5)), &statute_miles(), &[]), "1 1/2SM");
+        assert_eq!(format_visibility(Some(Meters(mile * 2.9)), &statute_miles(), &[]), "3SM");
+    }
+
+    #[test]
+    fn cavok_and_no_significant_cloud() {
+        let units = knots();
+        assert_eq!(format_visibility_and_clouds(Some(Meters(10000.0)), Some(0.0), None, &[], &units), "CAVOK");
+        assert_eq!(format_visibility_and_clouds(Some(Meters(10000.0)), Some(40.0), Some(6000), &[], &units), "CAVOK");
+        assert_eq!(format_visibility_and_clouds(Some(Meters(5000.0)), Some(0.0), None, &[], &units), "5000 NSC");
+        assert_eq!(format_visibility_and_clouds(Some(Meters(10000.0)), Some(0.0), None, &[500], &units), "9999 NSC");
+        assert_eq!(format_visibility_and_clouds(Some(Meters(10000.0)), Some(40.0), Some(3000), &[], &units), "9999 SCT030");
+        assert_eq!(format_visibility_and_clouds(Some(Meters(10000.0)), None, None, &[], &units), "9999 CLR");
+        assert_eq!(format_visibility_and_clouds(Some(Meters(10000.0)), Some(0.0), None, &[], &statute_miles()), "10SM CLR");
+    }
+
+    #[test]
+    fn pressure_groups() {
+        assert_eq!(format_pressure(Some(Hpa(1013.25)), &knots()), "Q1013");
+        assert_eq!(format_pressure(Some(Hpa(1013.5)), &knots()), "Q1014");
+        assert_eq!(format_pressure(Some(Hpa(987.0)), &knots()), "Q0987");
+        assert_eq!(format_pressure(None, &knots()), "Q////");
+        assert_eq!(format_pressure(Some(Hpa(1013.25)), &statute_miles()), "A2992");
+        assert_eq!(format_pressure(None, &statute_miles()), "A////");
+    }
+
+    #[test]
+    fn cloud_cover_boundaries() {
+        let cover = |percent: f64| format_cloud_coverage(Some(percent), None);
+        assert_eq!(cover(0.0), "CLR");
+        assert_eq!(cover(1.0), "FEW");
+        assert_eq!(cover(25.0), "FEW");
+        assert_eq!(cover(26.0), "SCT");
+        assert_eq!(cover(50.0), "SCT");
+        assert_eq!(cover(51.0), "BKN");
+        assert_eq!(cover(87.0), "BKN");
+        assert_eq!(cover(88.0), "OVC");
+        assert_eq!(cover(100.0), "OVC");
+        assert_eq!(format_cloud_coverage(None, Some(2500)), "CLR");
+        assert_eq!(format_cloud_coverage(Some(45.0), Some(2500)), "SCT025");
+        assert_eq!(format_cloud_coverage(Some(0.0), Some(2500)), "CLR");
+    }
+
+    #[test]
+    fn sky_obscured_by_fog_is_vertical_visibility() {
+        let units = knots();
+        assert_eq!(format_visibility_and_clouds(Some(Meters(200.0)), Some(100.0), Some(100), &[741], &units), "0200 VV001");
+        assert_eq!(format_visibility_and_clouds(Some(Meters(200.0)), Some(100.0), None, &[741], &units), "0200 VV///");
+        assert_eq!(format_visibility_and_clouds(Some(Meters(200.0)), Some(40.0), Some(100), &[741], &units), "0200 SCT001");
+        assert_eq!(format_visibility_and_clouds(Some(Meters(1500.0)), Some(100.0), Some(300), &[741], &units), "1500 OVC003");
+    }
+
+    #[test]
+    fn converts_wind_between_knots_and_meters_per_second() {
+        let mps = MetarUnits { wind: WindUnit::MetersPerSecond, ..MetarUnits::default() };
+        assert_eq!(convert_units("27015G25KT", &mps), "27008G13MPS");
+        assert_eq!(convert_units("VRB03KT", &mps), "VRB02MPS");
+        assert_eq!(convert_units("27008G13MPS", &knots()), "27016G25KT");
+        assert_eq!(convert_units("///05MPS", &knots()), "///10KT");
+        assert_eq!(convert_units("27015KT", &knots()), "27015KT");
+    }
+
+    #[test]
+    fn converts_visibility_between_meters_and_statute_miles() {
+        assert_eq!(convert_units("9999", &statute_miles()), "10SM");
+        assert_eq!(convert_units("2400", &statute_miles()), "1 1/2SM");
+        assert_eq!(convert_units("0800", &statute_miles()), "1/2SM");
+        assert_eq!(convert_units("10SM", &knots()), "9999");
+        assert_eq!(convert_units("1 1/2SM", &knots()), "2400");
+        assert_eq!(convert_units("3/4SM", &knots()), "1200");
+        assert_eq!(convert_units("M1/4SM", &knots()), "0400");
+    }
+
+    #[test]
+    fn converts_altimeter_between_hectopascals_and_inches() {
+        assert_eq!(convert_units("Q1013", &statute_miles()), "A2991");
+        assert_eq!(convert_units("A2992", &knots()), "Q1013");
+        assert_eq!(convert_units("A3015", &statute_miles()), "A3015");
+    }
+
+    #[test]
+    fn trend_and_remark_groups_are_left_intact() {
+        assert_eq!(
+            convert_units("EGLL 141150Z 27015KT 9999 FEW030 15/08 Q1013 BECMG FM141300 TL141500 4000 RA", &statute_miles()),
+            "EGLL 141150Z 27015KT 10SM FEW030 15/08 A2991 BECMG FM141300 TL141500 2 1/2SM RA"
+        );
+        assert_eq!(
+            convert_units("KJFK 141151Z 27015KT 3SM BR A2992 TEMPO FM1300 TL1500 1SM RMK AO2 SLP132", &knots()),
+            "KJFK 141151Z 27015KT 4800 BR Q1013 TEMPO FM1300 TL1500 1600 RMK AO2 SLP132"
+        );
+    }
+
+    const CAVOK_REPORT: &str = "EGLL 141150Z AUTO 27015KT CAVOK 15/08 Q1013 NOSIG RMK AO2";
+    const NSC_REPORT: &str = "EDDF 141150Z ///05KT 9999 NSC 15/08 Q//// FCST FM141300 TL141500 SCT030 RMK SLP132";
+    const CLEAR_REPORT: &str = "KJFK 141151Z COR 27015KT 10SM CLR BKN 15/08 A2992 TEMPO FM141300 TL141500 3SM BR RMK AO2";
+
+    #[test]
+    fn strict_icao_drops_remarks_and_forecasts_but_keeps_trends() {
+        let strict = |metar| apply_output_policy(metar, OutputPolicy::StrictIcao);
+        assert_eq!(strict(CAVOK_REPORT), "EGLL 141150Z AUTO 27015KT CAVOK 15/08 Q1013 NOSIG");
+        assert_eq!(strict(NSC_REPORT), "EDDF 141150Z ///05KT 9999 NSC 15/08 Q////");
+        assert_eq!(
+            strict(CLEAR_REPORT),
+            "KJFK 141151Z COR 27015KT 10SM NSC BKN/// 15/08 A2992 TEMPO FM141300 TL141500 3SM BR"
+        );
+        assert_eq!(strict("EGLL 141150Z AUTO 27015KT 9999 CLR 15/08 Q1013"), "EGLL 141150Z AUTO 27015KT 9999 NCD 15/08 Q1013");
+    }
+
+    #[test]
+    fn faa_style_leaves_the_report_as_generated() {
+        for metar in [CAVOK_REPORT, NSC_REPORT, CLEAR_REPORT] {
+            assert_eq!(apply_output_policy(metar, OutputPolicy::FaaStyle), metar);
+        }
+    }
+
+    #[test]
+    fn fsx_safe_keeps_only_the_main_body() {
+        let fsx = |metar| apply_output_policy(metar, OutputPolicy::FsxSafe);
+        assert_eq!(fsx(CAVOK_REPORT), "EGLL 141150Z 27015KT CAVOK 15/08 Q1013");
+        assert_eq!(fsx(NSC_REPORT), "EDDF 141150Z VRB05KT 9999 NSC 15/08");
+        assert_eq!(fsx(CLEAR_REPORT), "KJFK 141151Z 27015KT 10SM CLR BKN 15/08 A2992");
+    }
+
+    #[test]
+    fn four_character_identifiers_pass_with_any_padding() {
+        for padding in IdentifierPadding::ALL {
+            assert_eq!(station_identifier(" egll ", padding), Ok("EGLL".to_string()));
+            assert_eq!(station_identifier("K1N7", padding), Ok("K1N7".to_string()));
+        }
+    }
+
+    #[test]
+    fn short_identifiers_are_padded_by_the_chosen_scheme() {
+        assert!(station_identifier("1N7", IdentifierPadding::Reject).is_err());
+        assert_eq!(station_identifier("1n7", IdentifierPadding::PrefixK), Ok("K1N7".to_string()));
+        assert_eq!(station_identifier("BEA", IdentifierPadding::TrailingX), Ok("BEAX".to_string()));
+        assert!(station_identifier("XY", IdentifierPadding::PrefixK).is_err());
+        assert_eq!(station_identifier("XY", IdentifierPadding::TrailingX), Ok("XYXX".to_string()));
+    }
+
+    #[test]
+    fn long_empty_or_punctuated_identifiers_are_rejected() {
+        for padding in IdentifierPadding::ALL {
+            assert!(station_identifier("EGLLX", padding).is_err());
+            assert!(station_identifier("  ", padding).is_err());
+            assert!(station_identifier("EG-L", padding).is_err());
+        }
+    }
+
+    #[test]
+    fn visibility_estimates_from_weather_and_humidity() {
+        let cases: [(&[i32], Option<f64>, Option<f64>); 10] = [
+            (&[], None, None),
+            (&[], Some(50.0), Some(10000.0)),
+            (&[], Some(89.9), Some(10000.0)),
+            (&[], Some(90.0), Some(8000.0)),
+            (&[], Some(97.0), Some(4000.0)),
+            (&[800], None, Some(10000.0)),
+            (&[741], None, Some(500.0)),
+            (&[781], None, Some(1000.0)),
+            (&[761, 771], None, Some(10000.0)),
+            (&[500, 602], Some(98.0), Some(800.0)),
+        ];
+        for (codes, humidity, expected) in cases {
+            assert_eq!(estimate_visibility(codes, humidity).map(|Meters(meters)| meters), expected, "{:?} {:?}", codes, humidity);
+        }
+    }
+
+    #[test]
+    fn cloud_base_from_the_temperature_dew_point_spread() {
+        let cases = [
+            (10.0, 10.0, 100),
+            (10.0, 12.0, 100),
+            (10.0, 7.5, 1000),
+            (20.0, 15.0, 2000),
+            (20.0, 7.4, 5000),
+            (25.0, 10.0, 6000),
+            (30.0, 3.0, 11000),
+            (35.0, -5.0, 12000),
+        ];
+        for (temperature, dew_point, expected) in cases {
+            assert_eq!(estimate_cloud_base((Celsius(temperature), Celsius(dew_point))), expected, "{}/{}", temperature, dew_point);
+        }
+    }
+}
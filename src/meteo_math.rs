@@ -0,0 +1,78 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+
+// Atmospheric calculations shared by the providers and formatters.
+
+use crate::units::Celsius;
+
+// Magnus coefficients over water (Sonntag 1990), accurate to about 0.1 °C from
+// -45 °C to 60 °C. Relative humidity is reported against water even below
+// freezing, so they apply to sub-zero temperatures as well.
+const MAGNUS_A: f64 = 17.62;
+const MAGNUS_B: f64 = 243.12;
+
+// Humidity is clamped to this floor so a reported 0% doesn't give minus infinity
+const MIN_HUMIDITY: f64 = 1.0;
+
+/// Dew point for a temperature and relative humidity in percent, from the
+/// Magnus formula.
+pub fn dew_point(Celsius(temperature): Celsius, humidity: f64) -> Celsius {
+    let humidity = humidity.clamp(MIN_HUMIDITY, 100.0);
+    let gamma = (humidity / 100.0).ln() + MAGNUS_A * temperature / (MAGNUS_B + temperature);
+    Celsius(MAGNUS_B * gamma / (MAGNUS_A - gamma))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(Celsius(actual): Celsius, expected: f64) {
+        assert!((actual - expected).abs() < 0.1, "{} is not within 0.1 of {}", actual, expected);
+    }
+
+    #[test]
+    fn dew_points_match_reference_values() {
+        assert_near(dew_point(Celsius(20.0), 50.0), 9.3);
+        assert_near(dew_point(Celsius(30.0), 10.0), -5.0);
+        assert_near(dew_point(Celsius(25.0), 80.0), 21.3);
+    }
+
+    #[test]
+    fn sub_zero_dew_points() {
+        assert_near(dew_point(Celsius(-10.0), 80.0), -12.8);
+        assert_near(dew_point(Celsius(-20.0), 50.0), -27.8);
+    }
+
+    #[test]
+    fn very_dry_air() {
+        assert_near(dew_point(Celsius(20.0), 1.0), -38.0);
+    }
+
+    #[test]
+    fn saturated_air_has_the_temperature_as_dew_point() {
+        assert_near(dew_point(Celsius(15.0), 100.0), 15.0);
+    }
+
+    #[test]
+    fn out_of_range_humidity_is_clamped() {
+        let dry = dew_point(Celsius(20.0), 0.0);
+        assert!(dry.0.is_finite());
+        assert_near(dry, dew_point(Celsius(20.0), 1.0).0);
+        assert_near(dew_point(Celsius(20.0), -5.0), dry.0);
+        assert_near(dew_point(Celsius(20.0), 120.0), 20.0);
+    }
+}
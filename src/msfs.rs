@@ -0,0 +1,161 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Export of a report as a Microsoft Flight Simulator custom weather preset
+// (.WPR), the AceXML file MSFS lists under custom weather. The preset is built
+// from the decoded report, so it works for real NOAA METARs as well as
+// synthesized ones. MSFS has no visibility setting; reduced visibility is
+// carried over as aerosol density (haze), and precipitation as a rate.
+
+use crate::metar_decoder::DecodedMetar;
+
+const FEET_TO_METERS: f64 = 0.3048;
+const STANDARD_PRESSURE_PA: f64 = 101325.0;
+const STANDARD_TEMPERATURE_K: f64 = 288.15;
+const ZERO_CELSIUS_K: f64 = 273.15;
+// Thickness given to a layer in meters, since METARs only report the base
+const LAYER_THICKNESS_M: f64 = 600.0;
+const CONVECTIVE_THICKNESS_M: f64 = 3000.0;
+
+// Layer density (0 to 1) for each cloud cover
+fn cover_density(cover: &str) -> f64 {
+    match cover {
+        "FEW" => 0.2,
+        "SCT" => 0.4,
+        "BKN" => 0.75,
+        _ => 1.0,
+    }
+}
+
+// Precipitation rate in mm/h for the heaviest present weather group, e.g. -RA 1,
+// RA 4, +RA 10. Drizzle starts from 0.5 instead of 4.
+fn precipitation_rate(weather: &[String]) -> f64 {
+    weather
+        .iter()
+        .filter_map(|group| {
+            let base = if group.contains("DZ") {
+                0.5
+            } else if ["RA", "SN", "PL", "GR", "GS", "SG"].iter().any(|code| group.contains(code)) {
+                4.0
+            } else {
+                return None;
+            };
+            Some(match group.chars().next() {
+                Some('-') => base / 4.0,
+                Some('+') => base * 2.5,
+                _ => base,
+            })
+        })
+        .fold(0.0, f64::max)
+}
+
+// Thunderstorm intensity (0 to 1): +TS 1, TS 0.6, -TS or a reported CB 0.3
+fn thunderstorm_intensity(decoded: &DecodedMetar) -> f64 {
+    let thunderstorm = decoded.weather.iter().filter(|group| group.contains("TS") && !group.starts_with("VC"));
+    let intensity = thunderstorm
+        .map(|group| match group.chars().next() {
+            Some('+') => 1.0,
+            Some('-') => 0.3,
+            _ => 0.6,
+        })
+        .fold(0.0, f64::max);
+    let convective = decoded.clouds.iter().any(|layer| layer.cloud_type.as_deref() == Some("CB"));
+    if convective { intensity.max(0.3) } else { intensity }
+}
+
+// Haze for the reported visibility: none from 10 km, rising to 20 below 500 m
+fn aerosol_density(decoded: &DecodedMetar) -> f64 {
+    match decoded.visibility_m {
+        Some(visibility) if !decoded.cavok && visibility < 10000.0 => (10000.0 / visibility.max(1.0)).min(20.0),
+        _ => 0.0,
+    }
+}
+
+fn value(name: &str, value: f64, unit: Option<&str>) -> String {
+    match unit {
+        Some(unit) => format!("<{} Value=\"{:.6}\" Unit=\"{}\"></{}>", name, value, unit, name),
+        None => format!("<{} Value=\"{:.6}\"></{}>", name, value, name),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The report as a .WPR preset named `name`. Missing pressure and temperature
+/// fall back to the standard atmosphere; cloud bases are above ground level.
+pub fn format_wpr(name: &str, decoded: &DecodedMetar) -> String {
+    let pressure_pa = decoded.pressure_hpa.map_or(STANDARD_PRESSURE_PA, |hpa| hpa * 100.0);
+    let temperature_k = decoded
+        .temperature
+        .map_or(STANDARD_TEMPERATURE_K, |celsius| celsius as f64 + ZERO_CELSIUS_K);
+
+    let mut lines = vec![
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>".to_string(),
+        "<SimBase.Document Type=\"WeatherPlanData\" version=\"1,3\">".to_string(),
+        "    <Descr>AceXML Document</Descr>".to_string(),
+        "    <WeatherPreset.Preset>".to_string(),
+        format!("        <Name>{}</Name>", escape(name)),
+        "        <IsAltitudeAMGL>True</IsAltitudeAMGL>".to_string(),
+        format!("        {}", value("MSLPressure", pressure_pa, Some("pa"))),
+        format!("        {}", value("MSLTemperature", temperature_k, Some("k"))),
+        format!("        {}", value("AerosolDensity", aerosol_density(decoded), None)),
+        format!("        {}", value("Precipitations", precipitation_rate(&decoded.weather), Some("mm/h"))),
+        format!("        {}", value("SnowCover", 0.0, Some("cm"))),
+        format!("        {}", value("ThunderstormIntensity", thunderstorm_intensity(decoded), None)),
+    ];
+
+    // Cloud layers with a known base, and an indefinite ceiling as a low overcast
+    let layers = decoded
+        .clouds
+        .iter()
+        .filter_map(|layer| {
+            let thickness = if layer.cloud_type.is_some() { CONVECTIVE_THICKNESS_M } else { LAYER_THICKNESS_M };
+            Some((layer.height_ft?, thickness, cover_density(&layer.cover)))
+        })
+        .chain(decoded.vertical_visibility_ft.map(|height| (height, LAYER_THICKNESS_M, 1.0)));
+    for (base_ft, thickness, density) in layers {
+        let base = base_ft as f64 * FEET_TO_METERS;
+        lines.push("        <CloudLayer>".to_string());
+        lines.push(format!("            {}", value("CloudLayerAltitudeBot", base, Some("m"))));
+        lines.push(format!("            {}", value("CloudLayerAltitudeTop", base + thickness, Some("m"))));
+        lines.push(format!("            {}", value("CloudLayerDensity", density, Some("(0 - 1)"))));
+        lines.push(format!("            {}", value("CloudLayerScattering", 0.5, Some("(0 - 1)"))));
+        lines.push("        </CloudLayer>".to_string());
+    }
+
+    // Surface wind; a variable direction is left at north
+    if let Some(wind) = &decoded.wind {
+        let direction = wind.direction.unwrap_or(0) as f64;
+        lines.push("        <WindLayer>".to_string());
+        lines.push(format!("            {}", value("WindLayerAltitude", 0.0, Some("m"))));
+        lines.push(format!("            {}", value("WindLayerAngle", direction, Some("degrees"))));
+        lines.push(format!("            {}", value("WindLayerSpeed", wind.speed_kt, Some("knts"))));
+        if let Some(gust) = wind.gust_kt.filter(|gust| *gust > wind.speed_kt) {
+            lines.push("            <GustWave>".to_string());
+            lines.push(format!("                {}", value("GustWaveInterval", 8.0, Some("s"))));
+            lines.push(format!("                {}", value("GustWaveDuration", 3.0, Some("s"))));
+            lines.push(format!("                {}", value("GustWaveSpeed", gust - wind.speed_kt, Some("knts"))));
+            lines.push(format!("                {}", value("GustAngle", direction, Some("degrees"))));
+            lines.push("            </GustWave>".to_string());
+        }
+        lines.push("        </WindLayer>".to_string());
+    }
+
+    lines.push("    </WeatherPreset.Preset>".to_string());
+    lines.push("</SimBase.Document>".to_string());
+    lines.join("\n") + "\n"
+}
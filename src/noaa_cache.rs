@@ -0,0 +1,111 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Cache of NOAA METARs prefetched for a whole region, so station lookups during
+// a session can be answered without a request per airport
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::disk_cache;
+use crate::input_handler::{MetarSource, RealMetar};
+
+// Entry in the disk cache holding every prefetched report
+const CACHE_NAMESPACE: &str = "noaa";
+const CACHE_KEY: &str = "metars";
+// Routine METARs are issued hourly, so older entries are likely superseded
+const MAX_AGE_MINUTES: i64 = 75;
+
+/// Prefetch regions offered in the GUI as (name, [south, west, north, east]).
+pub const REGIONS: [(&str, [f64; 4]); 8] = [
+    ("Continental US", [24.0, -125.0, 50.0, -66.0]),
+    ("Alaska", [51.0, -170.0, 72.0, -129.0]),
+    ("Canada", [41.0, -141.0, 84.0, -52.0]),
+    ("Caribbean", [10.0, -90.0, 27.0, -59.0]),
+    ("Europe", [35.0, -11.0, 72.0, 40.0]),
+    ("United Kingdom & Ireland", [49.5, -11.0, 61.0, 2.0]),
+    ("Australia", [-44.0, 112.0, -10.0, 154.0]),
+    ("New Zealand", [-48.0, 165.0, -34.0, 179.0]),
+];
+
+#[derive(Serialize, Deserialize)]
+struct CachedMetar {
+    icao: String,
+    raw: String,
+    coordinates: Option<(f64, f64)>,
+    fetched: DateTime<Utc>,
+}
+
+fn load_cache() -> Vec<CachedMetar> {
+    // Individual reports expire on their own, the entry as a whole when all have
+    disk_cache::get(CACHE_NAMESPACE, CACHE_KEY, std::time::Duration::from_secs(MAX_AGE_MINUTES as u64 * 60))
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Adds freshly downloaded METARs to the cache, replacing older reports for the
+/// same stations and dropping expired ones. Returns the number of cached stations.
+pub fn store(metars: &[RealMetar]) -> Result<usize, String> {
+    let now = Utc::now();
+    let mut cache: Vec<CachedMetar> = load_cache()
+        .into_iter()
+        .filter(|entry| is_fresh(entry, now) && !metars.iter().any(|metar| metar.icao == entry.icao))
+        .collect();
+    cache.extend(metars.iter().map(|metar| CachedMetar {
+        icao: metar.icao.clone(),
+        raw: metar.raw.clone(),
+        coordinates: metar.coordinates,
+        fetched: now,
+    }));
+
+    let contents = serde_json::to_string(&cache).map_err(|e| e.to_string())?;
+    disk_cache::put(CACHE_NAMESPACE, CACHE_KEY, &contents)?;
+    Ok(cache.len())
+}
+
+/// The cached METAR for a station, if it was prefetched recently enough.
+pub fn cached_metar(icao: &str) -> Option<RealMetar> {
+    let now = Utc::now();
+    let entry = load_cache()
+        .into_iter()
+        .find(|entry| entry.icao.eq_ignore_ascii_case(icao) && is_fresh(entry, now))?;
+    disk_cache::record_hit();
+    Some(RealMetar {
+        icao: entry.icao,
+        raw: entry.raw,
+        coordinates: entry.coordinates,
+        source: MetarSource::Noaa,
+    })
+}
+
+fn is_fresh(entry: &CachedMetar, now: DateTime<Utc>) -> bool {
+    now - entry.fetched < Duration::minutes(MAX_AGE_MINUTES)
+}
+
+/// Parses a bounding box written as "south,west,north,east".
+pub fn parse_bounding_box(input: &str) -> Option<[f64; 4]> {
+    let values: Vec<f64> = input
+        .split(',')
+        .map(|value| value.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let [south, west, north, east] = values.try_into().ok()?;
+    let valid = (-90.0..=90.0).contains(&south)
+        && (-90.0..=90.0).contains(&north)
+        && (-180.0..=180.0).contains(&west)
+        && (-180.0..=180.0).contains(&east)
+        && south < north;
+    valid.then_some([south, west, north, east])
+}
@@ -0,0 +1,167 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Messages shown to the user. The latest error and success message are shown
+// as banners below the output panel; the notification center keeps the last
+// MAX_NOTIFICATIONS of them with their times, so a message replaced by the next
+// one can still be read from the header.
+//
+// Errors from background refreshes are queued separately. During a provider
+// outage every auto-refresh tick fails the same way, so identical errors for a
+// station are coalesced into one entry with a count and the time of the next
+// retry instead of a new banner each time.
+
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Messages kept in the notification center
+const MAX_NOTIFICATIONS: usize = 50;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Success,
+    Error,
+}
+
+pub struct Notification {
+    pub time: DateTime<Local>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct NotificationCenter {
+    // Newest first
+    history: VecDeque<Notification>,
+    unread: usize,
+    pub error_banner: Option<String>,
+    pub success_banner: Option<String>,
+}
+
+impl NotificationCenter {
+    /// Shows an error banner and adds it to the history.
+    pub fn error(&mut self, message: String) {
+        self.record(Severity::Error, message.clone());
+        self.error_banner = Some(message);
+    }
+
+    /// Shows a success banner and adds it to the history.
+    pub fn success(&mut self, message: String) {
+        self.record(Severity::Success, message.clone());
+        self.success_banner = Some(message);
+    }
+
+    /// Adds a message to the history without a banner.
+    pub fn record(&mut self, severity: Severity, message: String) {
+        self.history.push_front(Notification { time: Local::now(), severity, message });
+        self.history.truncate(MAX_NOTIFICATIONS);
+        self.unread = (self.unread + 1).min(MAX_NOTIFICATIONS);
+    }
+
+    /// Hides both banners. The history keeps the messages.
+    pub fn clear_banners(&mut self) {
+        self.error_banner = None;
+        self.success_banner = None;
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = &Notification> {
+        self.history.iter()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.unread = 0;
+    }
+
+    /// Messages added since the history was last opened.
+    pub fn unread(&self) -> usize {
+        self.unread
+    }
+
+    pub fn mark_read(&mut self) {
+        self.unread = 0;
+    }
+}
+
+pub struct RepeatedError {
+    // Station the error belongs to
+    pub source: String,
+    pub message: String,
+    pub count: u32,
+    // When the failing request will next be tried, if it will be
+    pub retry_at: Option<Instant>,
+}
+
+impl RepeatedError {
+    /// The banner text, e.g. "KSEA: Failed to generate METAR (3 times, retrying in 4:32)".
+    pub fn text(&self) -> String {
+        let mut details = Vec::new();
+        if self.count > 1 {
+            details.push(format!("{} times", self.count));
+        }
+        if let Some(retry_at) = self.retry_at {
+            let remaining = retry_at.saturating_duration_since(Instant::now()).as_secs();
+            details.push(if remaining == 0 {
+                "retrying now".to_string()
+            } else {
+                format!("retrying in {}:{:02}", remaining / 60, remaining % 60)
+            });
+        }
+        if details.is_empty() {
+            format!("{}: {}", self.source, self.message)
+        } else {
+            format!("{}: {} ({})", self.source, self.message, details.join(", "))
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ErrorQueue {
+    errors: Vec<RepeatedError>,
+}
+
+impl ErrorQueue {
+    /// Adds an error for a station, or counts it against the same error already queued.
+    /// A different error replaces the station's previous one. Returns false for a repeat.
+    pub fn push(&mut self, source: &str, message: String, retry_in: Option<Duration>) -> bool {
+        let retry_at = retry_in.map(|delay| Instant::now() + delay);
+        match self.errors.iter_mut().find(|error| error.source == source) {
+            Some(error) if error.message == message => {
+                error.count += 1;
+                error.retry_at = retry_at;
+                return false;
+            }
+            Some(error) => *error = RepeatedError { source: source.to_string(), message, count: 1, retry_at },
+            None => self.errors.push(RepeatedError { source: source.to_string(), message, count: 1, retry_at }),
+        }
+        true
+    }
+
+    /// Drops the station's error, once it succeeds or is closed.
+    pub fn resolve(&mut self, source: &str) {
+        self.errors.retain(|error| error.source != source);
+    }
+
+    pub fn errors(&self) -> &[RepeatedError] {
+        &self.errors
+    }
+
+    /// Whether a retry countdown is showing and needs redrawing.
+    pub fn counting_down(&self) -> bool {
+        self.errors.iter().any(|error| error.retry_at.is_some())
+    }
+}
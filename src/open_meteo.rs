@@ -0,0 +1,80 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Synthesis from the Open-Meteo forecast API, for users without an OpenWeather
+// key. Open-Meteo reports WMO weather codes, which are translated to the
+// OpenWeather condition codes the rest of the formatting works with.
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use crate::input_handler;
+use crate::metar_format::{FormatOptions, Observation};
+use crate::metar_generator;
+use crate::units::{Celsius, Hpa, MetarUnits, Meters, MetersPerSecond};
+
+// Open-Meteo reports visibility well beyond 10 km. The OpenWeather providers cap it
+// there, and format_visibility treats exactly 10 km as unrestricted.
+const MAX_VISIBILITY_M: f64 = 10000.0;
+
+// WMO weather codes and the closest OpenWeather condition codes. Groups without
+// an equivalent in the phenomena table fall back to the nearest one: freezing
+// drizzle to freezing rain, snow grains to light snow, and thunderstorms with
+// hail to thunderstorms with rain.
+const WMO_CODES: [(i64, i32); 28] = [
+    (0, 800),  (1, 801),  (2, 802),  (3, 804),
+    (45, 741), (48, 741),
+    (51, 300), (53, 301), (55, 302), (56, 511), (57, 511),
+    (61, 500), (63, 501), (65, 502), (66, 511), (67, 511),
+    (71, 600), (73, 601), (75, 602), (77, 600),
+    (80, 520), (81, 521), (82, 522),
+    (85, 620), (86, 622),
+    (95, 201), (96, 201), (99, 202),
+];
+
+/// Synthesizes a METAR from Open-Meteo's current conditions. Returns it with the
+/// time of the model step the conditions are for.
+pub fn generate_metar(
+    icao: &str,
+    lat: f64,
+    lon: f64,
+    units: &MetarUnits,
+    options: &FormatOptions,
+) -> Option<(String, Option<DateTime<Utc>>)> {
+    let weather_data = input_handler::fetch_open_meteo_weather(lat, lon)?;
+    let observation = parse_weather_data(&weather_data["current"]);
+    Some((metar_generator::format_metar(icao, &observation, units, options), observation.observed_at))
+}
+
+fn parse_weather_data(current: &Value) -> Observation {
+    Observation {
+        wind_direction: current["wind_direction_10m"].as_f64(),
+        wind_speed: current["wind_speed_10m"].as_f64().map(MetersPerSecond),
+        wind_gust: current["wind_gusts_10m"].as_f64().map(MetersPerSecond),
+        visibility: current["visibility"].as_f64().map(|vis| Meters(vis.min(MAX_VISIBILITY_M))),
+        cloud_coverage: current["cloud_cover"].as_f64(),
+        temperature: current["temperature_2m"].as_f64().map(Celsius),
+        dew_point: current["dew_point_2m"].as_f64().map(Celsius),
+        humidity: current["relative_humidity_2m"].as_f64(),
+        pressure: current["pressure_msl"].as_f64().map(Hpa),
+        weather_codes: current["weather_code"].as_i64().and_then(condition_code).into_iter().collect(),
+        observed_at: current["time"].as_i64().and_then(|time| DateTime::from_timestamp(time, 0)),
+    }
+}
+
+// OpenWeather condition code for a WMO weather code
+fn condition_code(wmo_code: i64) -> Option<i32> {
+    WMO_CODES.iter().find(|&&(wmo, _)| wmo == wmo_code).map(|&(_, code)| code)
+}
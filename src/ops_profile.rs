@@ -0,0 +1,153 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Operation profiles for the decoded report. Rotor and seaplane pilots look at
+// a METAR differently from fixed-wing pilots: helicopters care most about
+// density altitude and gusts, floatplanes about the wind over the water. The
+// profile picks which decoded elements are emphasized and adds advisories.
+
+use serde_json::Value;
+use crate::metar_decoder::DecodedMetar;
+use crate::units;
+
+// Gust spread above which turbulence near the ground is likely
+const GUST_SPREAD_KT: f64 = 10.0;
+// Density altitude above standard at which hover performance is worth checking
+const HIGH_DENSITY_ALTITUDE_FT: f64 = 1000.0;
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum OpsProfile {
+    #[default]
+    FixedWing,
+    Rotor,
+    Seaplane,
+}
+
+impl OpsProfile {
+    pub const ALL: [OpsProfile; 3] = [OpsProfile::FixedWing, OpsProfile::Rotor, OpsProfile::Seaplane];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OpsProfile::FixedWing => "Fixed-wing",
+            OpsProfile::Rotor => "Rotor",
+            OpsProfile::Seaplane => "Seaplane",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            OpsProfile::FixedWing => "fixed_wing",
+            OpsProfile::Rotor => "rotor",
+            OpsProfile::Seaplane => "seaplane",
+        }
+    }
+
+    pub fn from_config(config: &Value) -> OpsProfile {
+        let key = config.get("ops_profile").and_then(|p| p.as_str()).unwrap_or_default();
+        OpsProfile::ALL
+            .into_iter()
+            .find(|profile| profile.key() == key)
+            .unwrap_or_default()
+    }
+
+    /// Whether a row from `metar_decoder::describe` should stand out for this profile.
+    pub fn emphasizes(self, element: &str) -> bool {
+        match self {
+            OpsProfile::FixedWing => false,
+            OpsProfile::Rotor => matches!(element, "Wind" | "Temperature" | "Pressure"),
+            OpsProfile::Seaplane => matches!(element, "Wind" | "Visibility" | "Weather"),
+        }
+    }
+}
+
+/// Extra (element, description) rows for the profile. The field elevation is
+/// only used for the density altitude; without it sea level is assumed.
+pub fn advisories(decoded: &DecodedMetar, profile: OpsProfile, elevation_ft: Option<f64>) -> Vec<(&'static str, String)> {
+    let mut rows = Vec::new();
+    match profile {
+        OpsProfile::FixedWing => {}
+        OpsProfile::Rotor => {
+            if let Some(density_altitude) = density_altitude(decoded, elevation_ft.unwrap_or(0.0)) {
+                let elevation = elevation_ft.unwrap_or(0.0);
+                let mut text = format!("{:.0} ft", density_altitude);
+                if elevation_ft.is_none() {
+                    text.push_str(" at sea level (enter the field elevation)");
+                }
+                if density_altitude - elevation >= HIGH_DENSITY_ALTITUDE_FT {
+                    text.push_str(&format!(
+                        "; {:.0} ft above the field, check hover out of ground effect performance",
+                        density_altitude - elevation
+                    ));
+                }
+                rows.push(("Density altitude", text));
+            }
+            if let Some(text) = gust_advisory(decoded) {
+                rows.push(("Gusts", text));
+            }
+        }
+        OpsProfile::Seaplane => {
+            if let Some(height) = decoded.wave_height_m {
+                rows.push(("Water", wave_advisory(height)));
+            } else if let Some(wind) = &decoded.wind {
+                rows.push(("Water", sea_state(wind.speed_kt).to_string()));
+            }
+            if let Some(text) = gust_advisory(decoded) {
+                rows.push(("Gusts", text));
+            }
+        }
+    }
+    rows
+}
+
+// Density altitude from the reported pressure and temperature, in feet
+fn density_altitude(decoded: &DecodedMetar, elevation_ft: f64) -> Option<f64> {
+    let altimeter = decoded
+        .altimeter_inhg
+        .or_else(|| decoded.pressure_hpa.map(units::hpa_to_inhg))?;
+    let temperature = f64::from(decoded.temperature?);
+    let pressure_altitude = elevation_ft + (29.92 - altimeter) * 1000.0;
+    let isa_temperature = 15.0 - 2.0 * pressure_altitude / 1000.0;
+    Some(pressure_altitude + 118.8 * (temperature - isa_temperature))
+}
+
+fn gust_advisory(decoded: &DecodedMetar) -> Option<String> {
+    let wind = decoded.wind.as_ref()?;
+    let spread = wind.gust_kt? - wind.speed_kt;
+    (spread >= GUST_SPREAD_KT).then(|| format!("Gust spread {:.0} kt, expect turbulence close to the surface", spread))
+}
+
+fn wave_advisory(height_m: f64) -> String {
+    let assessment = match height_m {
+        h if h < 0.1 => "flat water, glassy if the wind is calm",
+        h if h < 0.5 => "comfortable for floatplanes",
+        h if h < 1.0 => "demanding for light floatplanes",
+        _ => "beyond most floatplane limits",
+    };
+    format!("Waves {:.1} m reported, {}", height_m, assessment)
+}
+
+// Likely water surface for a sustained wind. Without a fetch or swell source
+// this is only an estimate from the wind speed.
+fn sea_state(speed_kt: f64) -> &'static str {
+    match speed_kt {
+        s if s < 1.0 => "Glassy water likely: depth perception is poor, use a glassy water landing",
+        s if s < 7.0 => "Ripples to small wavelets (estimated from wind)",
+        s if s < 11.0 => "Smooth, scattered whitecaps (estimated from wind)",
+        s if s < 17.0 => "Slight, frequent whitecaps (estimated from wind)",
+        s if s < 22.0 => "Moderate waves, rough for light floatplanes (estimated from wind)",
+        _ => "Rough water, beyond most floatplane limits (estimated from wind)",
+    }
+}
@@ -0,0 +1,168 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// OpenWeather response bodies. Every field is optional so a partial response
+// still deserializes; fields the METAR needs but didn't get are reported by
+// name instead of silently turning into "////" groups.
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Deserializes a provider response, reporting a body that doesn't match the
+/// expected layout at all (e.g. a field with the wrong type).
+pub fn parse<T: DeserializeOwned>(provider: &str, data: Value) -> Option<T> {
+    match serde_json::from_value(data) {
+        Ok(response) => Some(response),
+        Err(e) => {
+            eprintln!("{} response doesn't match the expected format: {}", provider, e);
+            None
+        }
+    }
+}
+
+/// Reports the fields a METAR is built from that are missing from a response.
+pub fn report_missing(provider: &str, missing: &[&str]) {
+    if !missing.is_empty() {
+        eprintln!(
+            "{} response is missing {}; the groups built from them are reported as missing",
+            provider,
+            missing.join(", ")
+        );
+    }
+}
+
+/// An entry of a "weather" array.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Condition {
+    pub id: Option<i32>,
+}
+
+/// OpenWeather condition codes of a "weather" array.
+pub fn condition_codes(weather: &[Condition]) -> Vec<i32> {
+    weather.iter().filter_map(|condition| condition.id).collect()
+}
+
+/// Current weather from the Standard API (/data/2.5/weather).
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct CurrentWeather {
+    // Observation time, seconds since the epoch
+    pub dt: Option<i64>,
+    pub weather: Vec<Condition>,
+    pub main: MainValues,
+    pub wind: Wind,
+    pub clouds: Clouds,
+    pub visibility: Option<f64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct MainValues {
+    pub temp: Option<f64>,
+    pub pressure: Option<f64>,
+    pub humidity: Option<f64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Wind {
+    pub speed: Option<f64>,
+    pub deg: Option<f64>,
+    pub gust: Option<f64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Clouds {
+    pub all: Option<f64>,
+}
+
+impl CurrentWeather {
+    /// Fields the METAR is built from that the response left out. Gusts are only
+    /// reported when there are any.
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        missing([
+            ("main.temp", self.main.temp.is_none()),
+            ("main.pressure", self.main.pressure.is_none()),
+            ("main.humidity", self.main.humidity.is_none()),
+            ("wind.speed", self.wind.speed.is_none()),
+            ("wind.deg", self.wind.deg.is_none()),
+            ("clouds.all", self.clouds.all.is_none()),
+            ("visibility", self.visibility.is_none()),
+        ])
+    }
+}
+
+/// Response of the One Call API (/data/3.0/onecall).
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct OneCall {
+    pub current: Option<OneCallConditions>,
+    pub hourly: Vec<OneCallConditions>,
+    pub alerts: Vec<Alert>,
+}
+
+/// A "current" or "hourly" entry.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct OneCallConditions {
+    pub dt: Option<i64>,
+    pub temp: Option<f64>,
+    pub dew_point: Option<f64>,
+    pub humidity: Option<f64>,
+    pub pressure: Option<f64>,
+    pub wind_speed: Option<f64>,
+    pub wind_deg: Option<f64>,
+    pub wind_gust: Option<f64>,
+    pub clouds: Option<f64>,
+    pub visibility: Option<f64>,
+    pub weather: Vec<Condition>,
+}
+
+/// A government weather alert.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Alert {
+    pub event: String,
+    pub description: String,
+}
+
+impl OneCall {
+    /// Fields of the current conditions the METAR is built from that the
+    /// response left out.
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        let Some(current) = &self.current else {
+            return vec!["current"];
+        };
+        missing([
+            ("current.temp", current.temp.is_none()),
+            // The dew point can be estimated from the humidity
+            ("current.dew_point", current.dew_point.is_none() && current.humidity.is_none()),
+            ("current.pressure", current.pressure.is_none()),
+            ("current.wind_speed", current.wind_speed.is_none()),
+            ("current.wind_deg", current.wind_deg.is_none()),
+            ("current.clouds", current.clouds.is_none()),
+            ("current.visibility", current.visibility.is_none()),
+        ])
+    }
+}
+
+// Names of the checked fields that are missing
+fn missing<const N: usize>(checks: [(&'static str, bool); N]) -> Vec<&'static str> {
+    checks.into_iter().filter(|&(_, missing)| missing).map(|(field, _)| field).collect()
+}
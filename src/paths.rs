@@ -0,0 +1,146 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Where METGen keeps its files: the platform's config, data and cache
+// directories rather than whatever folder it happens to be started from.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use directories::ProjectDirs;
+
+use crate::airport_db::{AIRPORTS_FILE, BUNDLED_AIRPORTS_CSV};
+
+// Files that older versions kept in the working directory, with the directory
+// they belong in now
+const CONFIG_FILES: &[&str] = &["config.json"];
+const DATA_FILES: &[&str] = &["history.jsonl", "metgen.db", "briefing.json"];
+const PROFILE_FILE_PREFIX: &str = "config-";
+
+struct Dirs {
+    config: PathBuf,
+    data: PathBuf,
+}
+
+static DIRS: OnceLock<Dirs> = OnceLock::new();
+
+// Falls back to the working directory when the platform directories cannot be
+// determined (no home directory) or created. Tests get a temporary directory
+// instead, so they never touch the user's files.
+fn dirs() -> &'static Dirs {
+    DIRS.get_or_init(|| {
+        if cfg!(test) {
+            let dir = std::env::temp_dir().join(format!("metgen-test-{}", std::process::id()));
+            let _ = fs::create_dir_all(&dir);
+            return Dirs { config: dir.clone(), data: dir };
+        }
+        let Some(project) = ProjectDirs::from("com", "FiendishDrWu", "METGen") else {
+            return Dirs { config: PathBuf::from("."), data: PathBuf::from(".") };
+        };
+        let usable = |dir: &Path| {
+            if fs::create_dir_all(dir).is_ok() {
+                dir.to_path_buf()
+            } else {
+                PathBuf::from(".")
+            }
+        };
+        Dirs {
+            config: usable(project.config_dir()),
+            data: usable(project.data_dir()),
+        }
+    })
+}
+
+/// Directory holding config.json and the profile files.
+pub fn config_dir() -> &'static Path {
+    &dirs().config
+}
+
+/// Directory holding the history, database, briefing, caches and crash reports.
+pub fn data_dir() -> &'static Path {
+    &dirs().data
+}
+
+/// Path of a file in the config directory.
+pub fn config_file(name: &str) -> PathBuf {
+    config_dir().join(name)
+}
+
+/// Path of a file in the data directory.
+pub fn data_file(name: &str) -> PathBuf {
+    data_dir().join(name)
+}
+
+/// Moves files left in the working directory by older versions into the
+/// platform directories, so running METGen from another folder no longer starts
+/// with a fresh config. Files already present in the new location are kept.
+pub fn migrate_working_directory() {
+    let Ok(cwd) = std::env::current_dir() else {
+        return;
+    };
+
+    let mut config_files: Vec<String> = CONFIG_FILES.iter().map(|name| name.to_string()).collect();
+    if let Ok(entries) = fs::read_dir(&cwd) {
+        config_files.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| name.starts_with(PROFILE_FILE_PREFIX) && name.ends_with(".json")),
+        );
+    }
+
+    for name in &config_files {
+        migrate_file(&cwd.join(name), &config_file(name));
+    }
+    for name in DATA_FILES {
+        migrate_file(&cwd.join(name), &data_file(name));
+    }
+
+    // The airports.csv in the checkout or distribution folder is the bundled one
+    // the build reads, so it is never moved. Only a user's own edited copy is
+    // copied over as the override.
+    let airports = cwd.join(AIRPORTS_FILE);
+    let edited = fs::read_to_string(&airports)
+        .is_ok_and(|contents| contents.replace("\r\n", "\n") != BUNDLED_AIRPORTS_CSV.replace("\r\n", "\n"));
+    let new = data_file(AIRPORTS_FILE);
+    if edited && !new.exists() && !same_file(&airports, &new) {
+        match fs::copy(&airports, &new) {
+            Ok(_) => eprintln!("Copied {} to {}", airports.display(), new.display()),
+            Err(e) => eprintln!("Failed to copy {} to {}: {}", airports.display(), new.display(), e),
+        }
+    }
+}
+
+fn migrate_file(old: &Path, new: &Path) {
+    if !old.is_file() || new.exists() || same_file(old, new) {
+        return;
+    }
+    // Rename fails across drives, so fall back to copying
+    let moved = fs::rename(old, new).or_else(|_| fs::copy(old, new).and_then(|_| fs::remove_file(old)));
+    match moved {
+        Ok(()) => eprintln!("Moved {} to {}", old.display(), new.display()),
+        Err(e) => eprintln!("Failed to move {} to {}: {}", old.display(), new.display(), e),
+    }
+}
+
+// True when the platform directory is the working directory itself
+fn same_file(old: &Path, new: &Path) -> bool {
+    match (old.parent().and_then(|p| p.canonicalize().ok()), new.parent().and_then(|p| p.canonicalize().ok())) {
+        (Some(old_dir), Some(new_dir)) => old_dir == new_dir,
+        _ => false,
+    }
+}
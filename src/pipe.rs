@@ -0,0 +1,164 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Pipe mode: `metgen pipe` reads stations and coordinates from stdin and writes
+// one report per line to stdout, for use from scripts and other flight tools.
+//
+//   echo "KSEA KBFI 47.1,-122.3" | metgen pipe --format json
+//
+// Each whitespace separated item is an ICAO code or a lat,lon pair, optionally
+// with an identifier for the report (KXYZ=47.1,-122.3; otherwise ZZZZ). ICAO codes
+// get the real NOAA METAR if there is one, like the ICAO lookup in the GUI.
+
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use crate::coordinates::parse_coordinate_pair;
+use crate::generation::{ApiType, GenerationSettings};
+use crate::icao_region;
+use crate::input_handler::{self, validate_lat_lon};
+use crate::noaa_cache;
+
+// ICAO "no location indicator", used for coordinates without an identifier
+pub const UNNAMED_IDENTIFIER: &str = "ZZZZ";
+
+const USAGE: &str = "Usage: metgen [--profile <name>] pipe [--format text|json] [--provider standard|onecall|openmeteo]";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn from_key(key: &str) -> Option<Format> {
+        match key {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+pub struct PipeResult {
+    pub input: String,
+    pub icao: String,
+    // "noaa" for a real METAR, otherwise the provider key
+    pub source: &'static str,
+    pub metar: Result<String, String>,
+}
+
+/// Runs pipe mode with the arguments following "pipe". Returns the process exit
+/// code: 0 if every item produced a report, 1 if any failed, 2 for bad arguments.
+pub fn run(args: &[String], config: &Value) -> i32 {
+    let mut format = Format::Text;
+    let mut api = ApiType::Standard;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next().map(|value| value.as_str())) {
+            ("--format", Some(key)) if Format::from_key(key).is_some() => format = Format::from_key(key).unwrap_or(format),
+            ("--provider", Some(key)) if ApiType::ALL.iter().any(|api| api.key() == key) => {
+                api = ApiType::ALL.into_iter().find(|api| api.key() == key).unwrap_or_default();
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+
+    let settings = GenerationSettings::from_config(config, api);
+    let mut failed = false;
+    let stdout = io::stdout();
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Failed to read input: {}", e);
+                return 1;
+            }
+        };
+        for item in line.split_whitespace() {
+            let result = generate(item, &settings);
+            failed |= result.metar.is_err();
+            let mut out = stdout.lock();
+            // Stop quietly when the reading end of the pipe has gone away
+            if write_result(&mut out, &result, format).and_then(|_| out.flush()).is_err() {
+                return if failed { 1 } else { 0 };
+            }
+        }
+    }
+    if failed { 1 } else { 0 }
+}
+
+fn generate(item: &str, settings: &GenerationSettings) -> PipeResult {
+    let (identifier, location) = match item.split_once('=') {
+        Some((identifier, location)) => (Some(identifier), location),
+        None => (None, item),
+    };
+    let result = |icao: &str, source, metar| PipeResult { input: item.to_string(), icao: icao.to_string(), source, metar };
+
+    if location.contains(',') {
+        let icao = identifier.unwrap_or(UNNAMED_IDENTIFIER).to_uppercase();
+        let Some((lat, lon)) = parse_coordinate_pair(location).and_then(|(lat, lon)| validate_lat_lon(lat, lon)) else {
+            return result(&icao, "", Err("Invalid latitude/longitude values".to_string()));
+        };
+        if let Some(warning) = icao_region::mismatch_warning(&icao, lat, lon) {
+            eprintln!("Warning: {}", warning);
+        }
+        return synthesize(item, &icao, lat, lon, settings);
+    }
+
+    let icao = location.to_uppercase();
+    if let Some(existing) = noaa_cache::cached_metar(&icao)
+        .or_else(|| input_handler::poll_metar(&icao, &settings.alternate_sources))
+    {
+        return result(&icao, existing.source.key(), Ok(existing.raw));
+    }
+    match input_handler::resolve_icao_to_lat_lon(&icao, &settings.alternate_sources) {
+        Some((lat, lon)) => synthesize(item, identifier.map(str::to_uppercase).as_deref().unwrap_or(&icao), lat, lon, settings),
+        None => result(&icao, "", Err(format!("Could not resolve ICAO code: {}", icao))),
+    }
+}
+
+pub fn synthesize(item: &str, icao: &str, lat: f64, lon: f64, settings: &GenerationSettings) -> PipeResult {
+    let (source, metar) = match settings.synthesize(icao, lat, lon) {
+        Ok(synthesized) => (synthesized.api.key(), Ok(synthesized.metar)),
+        Err(e) => ("", Err(e)),
+    };
+    PipeResult { input: item.to_string(), icao: icao.to_string(), source, metar }
+}
+
+// Text output is the bare METAR, with failures reported on stderr. JSON output
+// has one object per item, failures included.
+pub fn write_result(out: &mut impl Write, result: &PipeResult, format: Format) -> io::Result<()> {
+    match (format, &result.metar) {
+        (Format::Text, Ok(metar)) => writeln!(out, "{}", metar),
+        (Format::Text, Err(e)) => {
+            eprintln!("{}: {}", result.input, e);
+            Ok(())
+        }
+        (Format::Json, metar) => {
+            let object = json!({
+                "input": result.input,
+                "icao": result.icao,
+                "source": Some(result.source).filter(|source| !source.is_empty()),
+                "metar": metar.as_ref().ok(),
+                "error": metar.as_ref().err(),
+            });
+            writeln!(out, "{}", object)
+        }
+    }
+}
@@ -0,0 +1,381 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Writes config changes on a background thread, so the GUI never reads or
+// writes config.json from its update loop. Each change is queued as an edit
+// of the config file that was active when it was made. The worker waits a
+// moment for more changes (a slider drag or typing queues many), then applies
+// the whole batch with one read and one write per file. Saved airport and
+// bookmark writes go through the same thread, since the JSON storage keeps
+// them in config.json too, and the worker reads the lists back for the GUI.
+// It also loads the config when the GUI switches profiles and, while idle,
+// watches the file for edits made outside the app, handing the parsed config
+// to the GUI.
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use crate::bookmarks::{self, Bookmark};
+use crate::config::{
+    active_profile, config_path, get_user_airports, list_profiles, load_runtime_config, set_active_profile,
+    update_config_file, UserAirport,
+};
+
+// How long the worker collects changes before writing them
+const BATCH_DELAY: Duration = Duration::from_millis(250);
+// How often the config file is checked for outside edits
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+type Change = Box<dyn FnOnce(&mut Value) + Send>;
+// A write to the saved airports or bookmarks, returning a message for the GUI
+type StorageWrite = Box<dyn FnOnce() -> Result<Option<String>, String> + Send>;
+
+#[derive(Default)]
+struct Queue {
+    changes: Vec<(PathBuf, Change)>,
+    storage_writes: Vec<StorageWrite>,
+    // Set while a batch is being written
+    writing: bool,
+    errors: Vec<String>,
+    notices: Vec<String>,
+    // Read back after storage writes, not yet taken by the GUI
+    saved_places: Option<SavedPlaces>,
+    // Whether outside edits of the config file are looked for
+    watching: bool,
+    // Config reloaded after an outside edit, not yet taken by the GUI
+    reloaded: Option<Value>,
+    // Profile to switch to once the queued changes are written
+    switch_to: Option<String>,
+    switched: Option<ProfileSwitch>,
+}
+
+impl Queue {
+    fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.storage_writes.is_empty()
+    }
+}
+
+/// The active profile's saved airports and bookmarks.
+pub struct SavedPlaces {
+    pub airports: Vec<UserAirport>,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl SavedPlaces {
+    fn load() -> SavedPlaces {
+        SavedPlaces { airports: get_user_airports(), bookmarks: bookmarks::get_bookmarks() }
+    }
+}
+
+/// Outcome of a profile switch made on the worker.
+pub enum ProfileSwitch {
+    Loaded { name: String, config: Value, profiles: Vec<String> },
+    Failed(String),
+}
+
+// Makes `name` the active profile and loads its config, staying on the
+// previous profile if it can't be loaded
+fn load_profile(name: String) -> ProfileSwitch {
+    let previous = active_profile();
+    set_active_profile(&name);
+    let config = load_runtime_config();
+    if config.is_null() {
+        set_active_profile(&previous);
+        return ProfileSwitch::Failed(name);
+    }
+    ProfileSwitch::Loaded { name, config, profiles: list_profiles() }
+}
+
+// Modification time of the active profile's config file as last read or
+// written by the worker, so its own writes aren't taken for outside edits
+#[derive(Default)]
+struct Watched {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl Watched {
+    fn record(&mut self) {
+        self.path = config_path();
+        self.modified = modified_time(&self.path);
+    }
+
+    // The active profile's config, if its file changed since it was recorded.
+    // The caller records the file once the config has been handed over.
+    fn check(&mut self) -> Option<Value> {
+        let path = config_path();
+        if path != self.path {
+            self.record();
+            return None;
+        }
+        let modified = modified_time(&path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+
+        let config = load_runtime_config();
+        if config.is_null() {
+            // Most likely saved halfway through an edit; try again on the next change
+            eprintln!("Ignoring changed config file that could not be parsed.");
+            self.record();
+            return None;
+        }
+        Some(config)
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+struct Worker {
+    queue: Mutex<Queue>,
+    // Signalled when changes are queued and when a batch has been written
+    changed: Condvar,
+}
+
+static WORKER: OnceLock<Worker> = OnceLock::new();
+// Called when there is something for the GUI to pick up
+static WAKE: OnceLock<Box<dyn Fn() + Send + Sync>> = OnceLock::new();
+
+fn wake() {
+    if let Some(wake) = WAKE.get() {
+        wake();
+    }
+}
+
+/// Sets the function called when a write, profile switch or reload has
+/// finished, e.g. to repaint the GUI.
+pub fn set_wake(wake: impl Fn() + Send + Sync + 'static) {
+    let _ = WAKE.set(Box::new(wake));
+}
+
+fn worker() -> &'static Worker {
+    WORKER.get_or_init(|| {
+        thread::spawn(run);
+        Worker { queue: Mutex::new(Queue::default()), changed: Condvar::new() }
+    })
+}
+
+fn run() {
+    let worker = worker();
+    let mut watched = Watched::default();
+    loop {
+        let Ok(queue) = worker.queue.lock() else { return };
+        let idle = |queue: &mut Queue| queue.is_empty() && queue.switch_to.is_none();
+        let mut queue = match worker.changed.wait_timeout_while(queue, CONFIG_CHECK_INTERVAL, idle) {
+            Ok((queue, _)) => queue,
+            Err(_) => return,
+        };
+        // Changes queued before the switch still go to the previous profile
+        if queue.is_empty() {
+            if let Some(name) = queue.switch_to.take() {
+                drop(queue);
+                let switched = load_profile(name);
+                watched.record();
+                let Ok(mut queue) = worker.queue.lock() else { return };
+                queue.reloaded = None;
+                queue.switched = Some(switched);
+                wake();
+                continue;
+            }
+
+            // Queued changes would be reverted by a reload until they are written
+            let watching = queue.watching;
+            drop(queue);
+            if let Some(config) = watching.then(|| watched.check()).flatten() {
+                let Ok(mut queue) = worker.queue.lock() else { return };
+                // A change queued during the reload would be reverted by it, so
+                // the reload is dropped and the file checked again when idle
+                if queue.is_empty() && queue.switch_to.is_none() {
+                    // Recorded after loading, which can migrate and rewrite the file
+                    watched.record();
+                    queue.reloaded = Some(config);
+                    wake();
+                }
+            }
+            continue;
+        }
+        queue.writing = true;
+        drop(queue);
+
+        thread::sleep(BATCH_DELAY);
+        let Ok(mut queue) = worker.queue.lock() else { return };
+        let changes = std::mem::take(&mut queue.changes);
+        let storage_writes = std::mem::take(&mut queue.storage_writes);
+        drop(queue);
+
+        let mut errors = write_batch(changes);
+        let mut notices = Vec::new();
+        let saved_places = (!storage_writes.is_empty()).then(|| {
+            for write in storage_writes {
+                match write() {
+                    Ok(notice) => notices.extend(notice),
+                    Err(e) => errors.push(e),
+                }
+            }
+            SavedPlaces::load()
+        });
+        watched.record();
+
+        let Ok(mut queue) = worker.queue.lock() else { return };
+        queue.writing = false;
+        queue.errors.extend(errors);
+        queue.notices.extend(notices);
+        if saved_places.is_some() {
+            queue.saved_places = saved_places;
+        }
+        worker.changed.notify_all();
+        drop(queue);
+        wake();
+    }
+}
+
+// Applies the changes to each file in the order they were queued
+fn write_batch(changes: Vec<(PathBuf, Change)>) -> Vec<String> {
+    let mut by_file: Vec<(PathBuf, Vec<Change>)> = Vec::new();
+    for (path, change) in changes {
+        match by_file.iter_mut().find(|(file, _)| *file == path) {
+            Some((_, file_changes)) => file_changes.push(change),
+            None => by_file.push((path, vec![change])),
+        }
+    }
+
+    let mut errors = Vec::new();
+    for (path, file_changes) in by_file {
+        let result = update_config_file(&path, |config| {
+            for change in file_changes {
+                change(config);
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("Failed to save {}: {}", path.display(), e);
+            errors.push(format!("Failed to save configuration: {}", e));
+        }
+    }
+    errors
+}
+
+/// Queues an edit of the active profile's config file.
+pub fn queue(change: impl FnOnce(&mut Value) + Send + 'static) {
+    let worker = worker();
+    if let Ok(mut queue) = worker.queue.lock() {
+        queue.changes.push((config_path(), Box::new(change)));
+        // A reload from before the change would revert it in the GUI
+        queue.reloaded = None;
+        worker.changed.notify_all();
+    }
+}
+
+/// Queues a write to the active profile's saved airports or bookmarks. Its
+/// message or error is shown by the GUI, and the lists are read back afterwards
+/// for take_saved_places.
+pub fn queue_storage(write: impl FnOnce() -> Result<Option<String>, String> + Send + 'static) {
+    let worker = worker();
+    if let Ok(mut queue) = worker.queue.lock() {
+        queue.storage_writes.push(Box::new(write));
+        // With the JSON storage the write changes the config file too
+        queue.reloaded = None;
+        worker.changed.notify_all();
+    }
+}
+
+/// Reads the saved airports and bookmarks on the worker, for take_saved_places.
+pub fn load_saved_places() {
+    queue_storage(|| Ok(None));
+}
+
+/// Saved airports and bookmarks read back after the last storage writes.
+pub fn take_saved_places() -> Option<SavedPlaces> {
+    WORKER
+        .get()
+        .and_then(|worker| worker.queue.lock().ok())
+        .and_then(|mut queue| queue.saved_places.take())
+}
+
+/// Queues a single top-level setting.
+pub fn queue_setting(key: &str, value: Value) {
+    let key = key.to_string();
+    queue(move |config| config[key.as_str()] = value);
+}
+
+/// Waits until every queued change has been written, e.g. before exiting.
+pub fn flush() {
+    let Some(worker) = WORKER.get() else { return };
+    let Ok(mut queue) = worker.queue.lock() else { return };
+    while queue.writing || !queue.is_empty() {
+        queue = match worker.changed.wait(queue) {
+            Ok(queue) => queue,
+            Err(_) => return,
+        };
+    }
+}
+
+/// Switches to another profile once the queued changes are written. The
+/// outcome is picked up with take_profile_switch.
+pub fn switch_profile(name: &str) {
+    let worker = worker();
+    if let Ok(mut queue) = worker.queue.lock() {
+        queue.switch_to = Some(name.to_string());
+        worker.changed.notify_all();
+    }
+}
+
+/// The outcome of the last profile switch, once it has finished.
+pub fn take_profile_switch() -> Option<ProfileSwitch> {
+    WORKER
+        .get()
+        .and_then(|worker| worker.queue.lock().ok())
+        .and_then(|mut queue| queue.switched.take())
+}
+
+/// Starts watching the active profile's config file for edits made outside the
+/// app. Reloads are picked up with take_reloaded_config.
+pub fn watch_config() {
+    let worker = worker();
+    if let Ok(mut queue) = worker.queue.lock() {
+        queue.watching = true;
+    }
+}
+
+/// The config reloaded after an outside edit since the last call, if any.
+pub fn take_reloaded_config() -> Option<Value> {
+    WORKER
+        .get()
+        .and_then(|worker| worker.queue.lock().ok())
+        .and_then(|mut queue| queue.reloaded.take())
+}
+
+/// Errors from writes since the last call, for the GUI to show.
+pub fn take_errors() -> Vec<String> {
+    WORKER
+        .get()
+        .and_then(|worker| worker.queue.lock().ok())
+        .map(|mut queue| std::mem::take(&mut queue.errors))
+        .unwrap_or_default()
+}
+
+/// Messages from storage writes that succeeded since the last call.
+pub fn take_notices() -> Vec<String> {
+    WORKER
+        .get()
+        .and_then(|worker| worker.queue.lock().ok())
+        .map(|mut queue| std::mem::take(&mut queue.notices))
+        .unwrap_or_default()
+}
@@ -0,0 +1,251 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Background job scheduler for work that isn't started by a click: auto-refresh,
+// watchlists and batch jobs. A small pool of worker threads takes jobs from a
+// priority queue. A station never has more than one job queued or running,
+// and jobs for the same provider are spaced out to respect its rate limit.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Job priority, most urgent first.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Priority {
+    /// Asked for by the user, e.g. a regenerate hotkey
+    Immediate,
+    /// Periodic refresh of the station on screen
+    Refresh,
+    /// Work that can wait: stations in the background, routes, watchlists
+    Batch,
+}
+
+type Work<T> = Box<dyn FnOnce() -> T + Send>;
+
+struct QueuedJob<T> {
+    station: String,
+    provider: &'static str,
+    priority: Priority,
+    sequence: u64,
+    queued: Instant,
+    work: Work<T>,
+}
+
+struct State<T> {
+    queue: Vec<QueuedJob<T>>,
+    // Stations with a running job, with the time it was queued
+    running: HashMap<String, Instant>,
+    // Running jobs whose result should be dropped
+    cancelled: HashSet<String>,
+    last_start: HashMap<&'static str, Instant>,
+    next_sequence: u64,
+    shutdown: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    changed: Condvar,
+    rate_limits: HashMap<&'static str, Duration>,
+}
+
+pub struct Scheduler<T> {
+    shared: Arc<Shared<T>>,
+    results: mpsc::Receiver<(String, T)>,
+}
+
+impl<T: Send + 'static> Scheduler<T> {
+    /// Starts `workers` threads. `rate_limits` gives the minimum time between
+    /// job starts per provider; `wake` is called whenever a result is ready.
+    pub fn new(
+        workers: usize,
+        rate_limits: &[(&'static str, Duration)],
+        wake: impl Fn() + Send + Sync + 'static,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: Vec::new(),
+                running: HashMap::new(),
+                cancelled: HashSet::new(),
+                last_start: HashMap::new(),
+                next_sequence: 0,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+            rate_limits: rate_limits.iter().copied().collect(),
+        });
+        let (sender, results) = mpsc::channel();
+        let wake = Arc::new(wake);
+        for _ in 0..workers.max(1) {
+            let shared = shared.clone();
+            let sender = sender.clone();
+            let wake = wake.clone();
+            thread::spawn(move || run_worker(&shared, &sender, &*wake));
+        }
+        Self { shared, results }
+    }
+
+    /// Queues a job for a station. Returns false without queueing when the
+    /// station already has a job queued or running.
+    pub fn schedule(
+        &self,
+        station: &str,
+        provider: &'static str,
+        priority: Priority,
+        work: impl FnOnce() -> T + Send + 'static,
+    ) -> bool {
+        let Ok(mut state) = self.shared.state.lock() else { return false };
+        if state.running.contains_key(station) || state.queue.iter().any(|job| job.station == station) {
+            // A more urgent request for a waiting job moves it up the queue
+            if let Some(job) = state.queue.iter_mut().find(|job| job.station == station) {
+                job.priority = job.priority.min(priority);
+            }
+            return false;
+        }
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.queue.push(QueuedJob {
+            station: station.to_string(),
+            provider,
+            priority,
+            sequence,
+            queued: Instant::now(),
+            work: Box::new(work),
+        });
+        self.shared.changed.notify_all();
+        true
+    }
+
+    /// When the station's queued or running job was queued, if it has one.
+    pub fn busy_since(&self, station: &str) -> Option<Instant> {
+        let state = self.shared.state.lock().ok()?;
+        state.running.get(station).copied().or_else(|| {
+            state.queue.iter().find(|job| job.station == station).map(|job| job.queued)
+        })
+    }
+
+    /// Drops the station's queued job, or discards the result of a running one.
+    pub fn cancel(&self, station: &str) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.queue.retain(|job| job.station != station);
+            if state.running.contains_key(station) {
+                state.cancelled.insert(station.to_string());
+            }
+        }
+    }
+
+    /// Results of finished jobs as (station, result).
+    pub fn finished(&self) -> Vec<(String, T)> {
+        self.results.try_iter().collect()
+    }
+}
+
+impl<T> Drop for Scheduler<T> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.shutdown = true;
+            state.queue.clear();
+        }
+        self.shared.changed.notify_all();
+    }
+}
+
+fn run_worker<T>(shared: &Shared<T>, sender: &mpsc::Sender<(String, T)>, wake: &(dyn Fn() + Send + Sync)) {
+    loop {
+        let Some(job) = next_job(shared) else { return };
+        let station = job.station.clone();
+        let result = (job.work)();
+
+        let Ok(mut state) = shared.state.lock() else { return };
+        state.running.remove(&station);
+        let cancelled = state.cancelled.remove(&station);
+        drop(state);
+        shared.changed.notify_all();
+
+        if !cancelled {
+            if sender.send((station, result)).is_err() {
+                return;
+            }
+            wake();
+        }
+    }
+}
+
+// Waits for the most urgent job whose provider may be called again, and marks
+// its station as running. None once the scheduler is dropped.
+fn next_job<T>(shared: &Shared<T>) -> Option<QueuedJob<T>> {
+    let mut state = shared.state.lock().ok()?;
+    loop {
+        if state.shutdown {
+            return None;
+        }
+
+        let now = Instant::now();
+        let ready_at = |job: &QueuedJob<T>| {
+            let interval = shared.rate_limits.get(job.provider).copied().unwrap_or_default();
+            state.last_start.get(job.provider).map_or(now, |started| (*started + interval).max(now))
+        };
+        let ready = state
+            .queue
+            .iter()
+            .enumerate()
+            .filter(|(_, job)| ready_at(job) <= now)
+            .min_by_key(|(_, job)| (job.priority, job.sequence))
+            .map(|(index, _)| index);
+        let next_ready = state.queue.iter().map(ready_at).min();
+
+        if let Some(index) = ready {
+            let job = state.queue.remove(index);
+            state.last_start.insert(job.provider, now);
+            state.running.insert(job.station.clone(), job.queued);
+            return Some(job);
+        }
+
+        state = match next_ready {
+            Some(ready) => shared.changed.wait_timeout(state, ready - now).ok()?.0,
+            None => shared.changed.wait(state).ok()?,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancelling_twice_only_drops_the_running_result() {
+        let scheduler = Scheduler::new(1, &[], || {});
+        let (started, running) = mpsc::channel();
+        let (release, released) = mpsc::channel::<()>();
+        scheduler.schedule("KSEA", "test", Priority::Immediate, move || {
+            started.send(()).unwrap();
+            released.recv().unwrap();
+            1
+        });
+        running.recv().unwrap();
+        scheduler.cancel("KSEA");
+        scheduler.cancel("KSEA");
+        release.send(()).unwrap();
+        while scheduler.busy_since("KSEA").is_some() {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(scheduler.schedule("KSEA", "test", Priority::Immediate, || 2));
+        assert_eq!(scheduler.results.recv_timeout(Duration::from_secs(5)).ok(), Some(("KSEA".to_string(), 2)));
+    }
+}
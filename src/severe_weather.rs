@@ -0,0 +1,149 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Severe weather warnings: thunderstorms, funnel clouds and hurricane-force wind
+// in a report, and One Call alerts for severe storms at its position. They are
+// shown as a banner above the METAR, with an optional chime when a station turns
+// severe, so they are noticed before the flight is loaded into the sim.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use crate::metar_decoder::DecodedMetar;
+
+// Beaufort force 12
+const HURRICANE_FORCE_KT: f64 = 64.0;
+
+// Words in an alert that make it a severe weather alert
+const SEVERE_ALERT_WORDS: [&str; 7] = ["thunderstorm", "tornado", "hurricane", "typhoon", "cyclone", "derecho", "waterspout"];
+
+// Severe alerts found for each position at its last synthesis
+static ALERTS: Mutex<Option<HashMap<String, Vec<String>>>> = Mutex::new(None);
+
+/// What the Configuration tab offers, from "severe_weather_banner" (on by
+/// default) and "severe_weather_chime" (off by default) in the config.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SevereWeatherSettings {
+    pub banner: bool,
+    pub chime: bool,
+}
+
+impl SevereWeatherSettings {
+    pub fn from_config(config: &Value) -> SevereWeatherSettings {
+        SevereWeatherSettings {
+            banner: config["severe_weather_banner"].as_bool().unwrap_or(true),
+            chime: config["severe_weather_chime"].as_bool().unwrap_or(false),
+        }
+    }
+}
+
+/// Keeps the provider's alerts for the position that warn of severe weather,
+/// for `alerts`.
+pub fn check(lat: f64, lon: f64, alerts: &[String]) {
+    let severe: Vec<String> = alerts.iter().filter(|alert| is_severe_alert(alert)).cloned().collect();
+    if let Ok(mut cache) = ALERTS.lock() {
+        cache.get_or_insert_with(HashMap::new).insert(position_key(lat, lon), severe);
+    }
+}
+
+/// Severe weather alerts found when a report for the position was last synthesized.
+pub fn alerts(lat: f64, lon: f64) -> Vec<String> {
+    ALERTS
+        .lock()
+        .ok()
+        .and_then(|cache| cache.as_ref()?.get(&position_key(lat, lon)).cloned())
+        .unwrap_or_default()
+}
+
+/// The severe phenomena in a report, e.g. "Thunderstorm (+TSRA)" or
+/// "Hurricane-force wind, gusts 70 kt".
+pub fn phenomena(decoded: &DecodedMetar) -> Vec<String> {
+    let mut found = Vec::new();
+    for group in &decoded.weather {
+        // Thunderstorms in the vicinity (VCTS) count too; they're what ATC routes around
+        let codes = group.trim_start_matches(['+', '-']).trim_start_matches("VC");
+        let has = |code: &[u8]| codes.as_bytes().chunks(2).any(|chunk| chunk == code);
+        if has(b"FC") {
+            let name = if group.starts_with('+') { "Tornado or waterspout" } else { "Funnel cloud" };
+            found.push(format!("{} ({})", name, group));
+        } else if has(b"TS") {
+            found.push(format!("Thunderstorm ({})", group));
+        }
+    }
+    if let Some(wind) = &decoded.wind {
+        let strongest = wind.gust_kt.unwrap_or(0.0).max(wind.speed_kt);
+        if strongest >= HURRICANE_FORCE_KT {
+            let gusts = if wind.gust_kt.is_some_and(|gust| gust > wind.speed_kt) { "gusts " } else { "" };
+            found.push(format!("Hurricane-force wind, {}{:.0} kt", gusts, strongest));
+        }
+    }
+    found
+}
+
+/// Plays the system's warning sound without waiting for it to finish.
+pub fn chime() -> Result<(), String> {
+    chime_command()
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to play the severe weather chime: {}", e))
+}
+
+#[cfg(windows)]
+fn chime_command() -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    // SystemSounds.Play returns at once, so wait for the sound before exiting
+    let mut command = Command::new("powershell");
+    command
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "[System.Media.SystemSounds]::Exclamation.Play(); Start-Sleep -Seconds 1",
+        ])
+        .creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn chime_command() -> Command {
+    let mut command = Command::new("afplay");
+    command.arg("/System/Library/Sounds/Sosumi.aiff");
+    command
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn chime_command() -> Command {
+    // The freedesktop sound theme ships with PulseAudio and PipeWire desktops
+    let mut command = Command::new("paplay");
+    command.arg("/usr/share/sounds/freedesktop/stereo/dialog-warning.oga");
+    command
+}
+
+fn is_severe_alert(alert: &str) -> bool {
+    let alert = alert.to_lowercase();
+    SEVERE_ALERT_WORDS.iter().any(|word| alert.contains(word))
+}
+
+// Positions are matched to four decimals, about 10 m
+fn position_key(lat: f64, lon: f64) -> String {
+    format!("{:.4},{:.4}", lat, lon)
+}
@@ -0,0 +1,84 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Space weather from the NOAA Space Weather Prediction Center, for users who
+// want to know when GPS or ADS-B trouble would be realistic. Only the NOAA scale
+// levels are used: G for geomagnetic storms, S for solar radiation storms and
+// R for radio blackouts, each 0 (none) to 5 (extreme).
+
+use serde_json::Value;
+use crate::input_handler;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpaceWeather {
+    pub geomagnetic: u8,
+    pub radiation: u8,
+    pub radio_blackout: u8,
+    // Date and time of the observation as given by SWPC (UTC)
+    pub observed: Option<String>,
+}
+
+impl SpaceWeather {
+    /// "G2 (moderate)" style summary of the geomagnetic storm level.
+    pub fn geomagnetic_label(&self) -> String {
+        format!("G{} ({})", self.geomagnetic, level_name(self.geomagnetic))
+    }
+
+    /// A note when satellite navigation degradation is plausible at these levels,
+    /// following the effects in the NOAA scale descriptions.
+    pub fn navigation_note(&self) -> Option<&'static str> {
+        match (self.geomagnetic, self.radio_blackout) {
+            (5, _) => Some("Satellite navigation may be degraded for days; expect GPS and ADS-B position errors"),
+            (4, _) => Some("Satellite navigation may be degraded for hours; GPS and ADS-B position errors are plausible"),
+            (3, _) => Some("Intermittent satellite navigation problems may occur; occasional GPS or ADS-B errors are plausible"),
+            (_, 3..) => Some("Radio blackout on the sunlit side: HF and low-frequency navigation signals may be degraded"),
+            _ => None,
+        }
+    }
+}
+
+/// Today's observed scale levels, or None when SWPC can't be reached.
+pub fn fetch() -> Option<SpaceWeather> {
+    parse(&input_handler::fetch_space_weather_scales()?)
+}
+
+fn parse(scales: &Value) -> Option<SpaceWeather> {
+    let current = scales.get("0")?;
+    let level = |scale: &str| {
+        current[scale]["Scale"]
+            .as_str()
+            .and_then(|level| level.trim().parse::<u8>().ok())
+            .unwrap_or(0)
+            .min(5)
+    };
+    let observed = match (current["DateStamp"].as_str(), current["TimeStamp"].as_str()) {
+        (Some(date), Some(time)) => Some(format!("{} {}", date, time)),
+        (Some(date), None) => Some(date.to_string()),
+        _ => None,
+    };
+    Some(SpaceWeather { geomagnetic: level("G"), radiation: level("S"), radio_blackout: level("R"), observed })
+}
+
+fn level_name(level: u8) -> &'static str {
+    match level {
+        0 => "none",
+        1 => "minor",
+        2 => "moderate",
+        3 => "strong",
+        4 => "severe",
+        _ => "extreme",
+    }
+}
@@ -0,0 +1,355 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Spoken readback of METARs. The report is expanded into radio phraseology and
+// handed to the speech engine that ships with the OS, so no extra runtime is needed.
+
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+
+pub const DEFAULT_SPEECH_RATE: f64 = 1.0;
+pub const MIN_SPEECH_RATE: f64 = 0.5;
+pub const MAX_SPEECH_RATE: f64 = 2.0;
+
+const PHONETIC_ALPHABET: [&str; 26] = [
+    "alfa", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india",
+    "juliett", "kilo", "lima", "mike", "november", "oscar", "papa", "quebec", "romeo",
+    "sierra", "tango", "uniform", "victor", "whiskey", "x-ray", "yankee", "zulu",
+];
+
+const DIGITS: [&str; 10] = ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "niner"];
+
+const WEATHER_WORDS: [(&str, &str); 29] = [
+    ("MI", "shallow"), ("BC", "patches of"), ("PR", "partial"), ("DR", "low drifting"),
+    ("BL", "blowing"), ("SH", "showers of"), ("TS", "thunderstorm"), ("FZ", "freezing"),
+    ("DZ", "drizzle"), ("RA", "rain"), ("SN", "snow"), ("SG", "snow grains"),
+    ("IC", "ice crystals"), ("PL", "ice pellets"), ("GR", "hail"), ("GS", "small hail"),
+    ("UP", "unknown precipitation"), ("BR", "mist"), ("FG", "fog"), ("FU", "smoke"),
+    ("VA", "volcanic ash"), ("DU", "dust"), ("SA", "sand"), ("HZ", "haze"),
+    ("PO", "dust whirls"), ("SQ", "squalls"), ("FC", "funnel cloud"), ("SS", "sandstorm"),
+    ("DS", "duststorm"),
+];
+
+const CLOUD_WORDS: [(&str, &str); 4] = [
+    ("FEW", "few clouds"), ("SCT", "scattered clouds"), ("BKN", "broken clouds"), ("OVC", "overcast"),
+];
+
+// Reads a number digit by digit, as on the radio
+fn spell_digits(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10).map(|d| DIGITS[d as usize]))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+// Like spell_digits but without leading zeros: "05" is read as "five"
+fn spell_number(digits: &str) -> String {
+    let significant = digits.trim_start_matches('0');
+    if significant.is_empty() {
+        spell_digits("0")
+    } else {
+        spell_digits(significant)
+    }
+}
+
+fn spell_letters(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'A'..='Z' => PHONETIC_ALPHABET[(c as u8 - b'A') as usize].to_string(),
+            '0'..='9' => spell_digits(&c.to_string()),
+            _ => c.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn spoken_temperature(value: &str) -> String {
+    match value.strip_prefix('M') {
+        Some(below_zero) => format!("minus {}", spell_number(below_zero)),
+        None => spell_number(value),
+    }
+}
+
+fn spoken_wind(group: &str) -> Option<String> {
+    let (body, unit) = if let Some(body) = group.strip_suffix("KT") {
+        (body, "knots")
+    } else {
+        (group.strip_suffix("MPS")?, "meters per second")
+    };
+    if body.len() < 5 || !body[3..5].chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (direction, rest) = body.split_at(3);
+    let (speed, gust) = match rest.split_once('G') {
+        Some((speed, gust)) => (speed, Some(gust)),
+        None => (rest, None),
+    };
+    if direction == "000" && speed.trim_start_matches('0').is_empty() {
+        return Some("wind calm".to_string());
+    }
+
+    let direction = if direction == "VRB" {
+        "variable".to_string()
+    } else if direction == "///" {
+        "direction unknown".to_string()
+    } else if direction.chars().all(|c| c.is_ascii_digit()) {
+        format!("{} degrees", spell_digits(direction))
+    } else {
+        return None;
+    };
+    let mut spoken = format!("wind {} at {} {}", direction, spell_number(speed), unit);
+    if let Some(gust) = gust {
+        spoken.push_str(&format!(" gusting {}", spell_digits(gust)));
+    }
+    Some(spoken)
+}
+
+fn spoken_weather(group: &str) -> Option<String> {
+    let (intensity, mut rest) = match group.chars().next()? {
+        '-' => ("light ", &group[1..]),
+        '+' => ("heavy ", &group[1..]),
+        _ => ("", group),
+    };
+    let vicinity = rest.starts_with("VC");
+    if vicinity {
+        rest = &rest[2..];
+    }
+    if rest.is_empty() || rest.len() % 2 != 0 {
+        return None;
+    }
+
+    let mut words = Vec::new();
+    for index in (0..rest.len()).step_by(2) {
+        let code = rest.get(index..index + 2)?;
+        words.push(WEATHER_WORDS.iter().find(|(abbreviation, _)| *abbreviation == code)?.1);
+    }
+    let mut spoken = format!("{}{}", intensity, words.join(" "));
+    if vicinity {
+        spoken.push_str(" in the vicinity");
+    }
+    Some(spoken)
+}
+
+fn spoken_clouds(group: &str) -> Option<String> {
+    let (cover, words) = CLOUD_WORDS.iter().find(|(cover, _)| group.starts_with(cover))?;
+    let rest = &group[cover.len()..];
+    let height = rest.get(..3).filter(|height| height.chars().all(|c| c.is_ascii_digit()));
+    let mut spoken = words.to_string();
+    if let Some(height) = height {
+        let feet = height.parse::<u32>().ok()? * 100;
+        spoken.push_str(&format!(" at {} feet", spoken_height(feet)));
+    }
+    match &rest[height.map_or(0, |_| 3)..] {
+        "CB" => spoken.push_str(" cumulonimbus"),
+        "TCU" => spoken.push_str(" towering cumulus"),
+        _ => {}
+    }
+    Some(spoken)
+}
+
+// Cloud heights are read as thousands and hundreds: "two thousand five hundred"
+fn spoken_height(feet: u32) -> String {
+    let thousands = feet / 1000;
+    let hundreds = (feet % 1000) / 100;
+    let mut parts = Vec::new();
+    if thousands > 0 {
+        parts.push(format!("{} thousand", spell_digits(&thousands.to_string())));
+    }
+    if hundreds > 0 || thousands == 0 {
+        parts.push(format!("{} hundred", spell_digits(&hundreds.to_string())));
+    }
+    parts.join(" ")
+}
+
+fn spoken_visibility(group: &str, whole_miles: Option<&str>) -> Option<String> {
+    if let Some(miles) = group.strip_suffix("SM") {
+        let mut spoken = match miles.split_once('/') {
+            Some((numerator, denominator)) => format!("{} over {}", spell_digits(numerator), spell_digits(denominator)),
+            None => spell_digits(miles),
+        };
+        if let Some(whole) = whole_miles {
+            spoken = format!("{} and {}", spell_digits(whole), spoken);
+        }
+        return Some(format!("visibility {} statute miles", spoken));
+    }
+
+    if group.len() == 4 && group.chars().all(|c| c.is_ascii_digit()) {
+        let meters = group.parse::<u32>().ok()?;
+        return Some(match meters {
+            9999 => "visibility one zero kilometers or more".to_string(),
+            m if m >= 5000 => format!("visibility {} kilometers", spell_digits(&(m / 1000).to_string())),
+            m => format!("visibility {} meters", spell_digits(&m.to_string())),
+        });
+    }
+    None
+}
+
+/// Expands a METAR into words suitable for speech, e.g. "METAR kilo juliett foxtrot kilo,
+/// time one two five one zulu, wind two seven zero degrees at one five knots, ...".
+/// Remarks are not read.
+pub fn spoken_metar(metar: &str) -> String {
+    let groups: Vec<&str> = metar.split_whitespace().collect();
+    let mut phrases = Vec::new();
+    let mut index = 0;
+
+    while index < groups.len() {
+        let group = groups[index];
+        let next = groups.get(index + 1).copied();
+        index += 1;
+
+        if index == 1 {
+            phrases.push(format!("METAR {}", spell_letters(group)));
+            continue;
+        }
+
+        let phrase = match group {
+            "RMK" => break,
+            "AUTO" => Some("automated observation".to_string()),
+            "COR" => Some("corrected".to_string()),
+            "CAVOK" => Some("CAV OK".to_string()),
+            "CLR" | "SKC" => Some("sky clear".to_string()),
+            "NSC" => Some("no significant cloud".to_string()),
+            "NCD" => Some("no cloud detected".to_string()),
+            "NOSIG" => Some("no significant change".to_string()),
+            "BECMG" => Some("becoming".to_string()),
+            "TEMPO" => Some("temporarily".to_string()),
+            "FCST" => Some("forecast".to_string()),
+            "////" | "/////" | "/// ///" => None,
+            _ if group.len() == 7 && group.ends_with('Z') && group[..6].chars().all(|c| c.is_ascii_digit()) => {
+                Some(format!("time {} zulu", spell_digits(&group[2..6])))
+            }
+            _ if group.starts_with('Q') && group.len() == 5 && group[1..].chars().all(|c| c.is_ascii_digit()) => {
+                Some(format!("QNH {}", spell_digits(&group[1..])))
+            }
+            _ if group.starts_with('A') && group.len() == 5 && group[1..].chars().all(|c| c.is_ascii_digit()) => {
+                Some(format!("altimeter {}", spell_digits(&group[1..])))
+            }
+            _ if group.starts_with("VV") && group.len() == 5 => {
+                group[2..].parse::<u32>().ok().map(|height| format!("vertical visibility {} feet", spoken_height(height * 100)))
+            }
+            _ if (group.starts_with("FM") || group.starts_with("TL") || group.starts_with("AT"))
+                && (group.len() == 6 || group.len() == 8)
+                && group[2..].chars().all(|c| c.is_ascii_digit()) =>
+            {
+                let prefix = match &group[..2] {
+                    "FM" => "from",
+                    "TL" => "until",
+                    _ => "at",
+                };
+                // FMddhhmm carries the day before the time
+                let time = &group[group.len() - 4..];
+                if group.len() == 8 {
+                    Some(format!("{} day {} {}", prefix, spell_number(&group[2..4]), spell_digits(time)))
+                } else {
+                    Some(format!("{} {}", prefix, spell_digits(time)))
+                }
+            }
+            _ if group.contains('/') && !group.ends_with("SM") => {
+                // Temperature and dew point: "15/10", "M02/M05"
+                group.split_once('/').map(|(temperature, dew_point)| {
+                    format!(
+                        "temperature {}, dew point {}",
+                        spoken_temperature(temperature),
+                        spoken_temperature(dew_point)
+                    )
+                })
+            }
+            _ if group.chars().all(|c| c.is_ascii_digit()) && next.is_some_and(|n| n.ends_with("SM") && n.contains('/')) => {
+                // Whole miles of a mixed visibility such as "1 1/2SM"
+                let fraction = groups[index];
+                index += 1;
+                spoken_visibility(fraction, Some(group))
+            }
+            _ => spoken_wind(group)
+                .or_else(|| spoken_visibility(group, None))
+                .or_else(|| spoken_clouds(group))
+                .or_else(|| spoken_weather(group))
+                .or_else(|| Some(spell_letters(group))),
+        };
+
+        if let Some(phrase) = phrase {
+            phrases.push(phrase);
+        }
+    }
+
+    phrases.join(", ")
+}
+
+/// Starts speaking the text with the OS speech engine at the given rate, where 1.0 is
+/// the engine's normal speed. The returned process can be killed to stop the readback.
+pub fn speak(text: &str, rate: f64) -> Result<Child, String> {
+    let rate = rate.clamp(MIN_SPEECH_RATE, MAX_SPEECH_RATE);
+    let mut command = speech_command(rate);
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Text-to-speech is not available: {}", e))?;
+
+    // Text goes in through stdin to avoid any shell quoting of the report
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("Failed to send text to the speech engine: {}", e))?;
+    }
+    Ok(child)
+}
+
+#[cfg(windows)]
+fn speech_command(rate: f64) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    // SpeechSynthesizer.Rate runs from -10 to 10 with 0 as normal speed
+    let synthesizer_rate = ((rate - 1.0) * 10.0).round().clamp(-10.0, 10.0) as i32;
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; \
+         $speech = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+         $speech.Rate = {}; \
+         $speech.Speak([Console]::In.ReadToEnd())",
+        synthesizer_rate
+    );
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn speech_command(rate: f64) -> Command {
+    let mut command = Command::new("say");
+    command.args(["-r", &((175.0 * rate).round() as i32).to_string()]);
+    command
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn speech_command(rate: f64) -> Command {
+    // Prefer speech-dispatcher, which uses whatever voice the desktop is set up with
+    if Command::new("spd-say").arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok() {
+        let dispatcher_rate = ((rate - 1.0) * 100.0).round().clamp(-100.0, 100.0) as i32;
+        let mut command = Command::new("spd-say");
+        command.args(["--wait", "--pipe-mode", "--rate", &dispatcher_rate.to_string()]);
+        command
+    } else {
+        let mut command = Command::new("espeak");
+        command.args(["--stdin", "-s", &((175.0 * rate).round() as i32).to_string()]);
+        command
+    }
+}
@@ -0,0 +1,186 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// The kind of station a synthesized report claims to come from. Real automated
+// stations can only report what their sensors detect: an AO1 station has no
+// precipitation discriminator, so it reports no precipitation at all, and an AO2
+// station tells rain from snow but can't see drizzle, hail, showers, funnel
+// clouds or dust. Reports are constrained to match, and the AO1/AO2 remark is
+// added. A manual station drops AUTO and may report anything.
+
+use serde_json::Value;
+
+// Weather groups are split into their two-letter codes
+const DESCRIPTORS: [&str; 8] = ["MI", "BC", "PR", "DR", "BL", "SH", "TS", "FZ"];
+const PHENOMENA: [&str; 21] = [
+    "DZ", "RA", "SN", "SG", "IC", "PL", "GR", "GS", "UP", "BR", "FG", "FU", "VA", "DU", "SA", "HZ", "PO", "SQ", "FC", "SS", "DS",
+];
+// What visibility sensors infer without a present weather sensor
+const OBSCURATIONS: [&str; 3] = ["BR", "FG", "HZ"];
+// Groups the observed part of a report ends at
+const TRAILING_GROUPS: [&str; 5] = ["BECMG", "TEMPO", "NOSIG", "FCST", "RMK"];
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum StationType {
+    /// AUTO without an AO1/AO2 remark, as synthesized
+    #[default]
+    Automated,
+    /// Automated without a precipitation discriminator
+    Ao1,
+    /// Automated with a precipitation discriminator
+    Ao2,
+    /// Human observer
+    Manual,
+}
+
+impl StationType {
+    pub const ALL: [StationType; 4] = [StationType::Automated, StationType::Ao1, StationType::Ao2, StationType::Manual];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StationType::Automated => "Automated (unspecified)",
+            StationType::Ao1 => "AO1 (no precipitation sensor)",
+            StationType::Ao2 => "AO2 (precipitation sensor)",
+            StationType::Manual => "Manual",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            StationType::Automated => "automated",
+            StationType::Ao1 => "ao1",
+            StationType::Ao2 => "ao2",
+            StationType::Manual => "manual",
+        }
+    }
+
+    pub fn from_config(config: &Value) -> StationType {
+        let key = config.get("station_type").and_then(|kind| kind.as_str()).unwrap_or_default();
+        StationType::ALL
+            .into_iter()
+            .find(|kind| kind.key() == key)
+            .unwrap_or_default()
+    }
+}
+
+/// Rewrites a synthesized report as the station type would have reported it.
+/// With `remarks` the AO1/AO2 remark is added, starting a remarks section if
+/// there is none.
+pub fn apply_station_type(metar: &str, station_type: StationType, remarks: bool) -> String {
+    let mut groups: Vec<String> = Vec::new();
+    let mut observed = true;
+    for (index, group) in metar.split_whitespace().enumerate() {
+        if TRAILING_GROUPS.contains(&group) {
+            observed = false;
+        }
+        // The station identifier could spell weather codes
+        if !observed || index == 0 {
+            groups.push(group.to_string());
+            continue;
+        }
+        match station_type {
+            StationType::Automated => groups.push(group.to_string()),
+            StationType::Manual => match group {
+                "AUTO" => {}
+                // Observers report a clear sky as SKC; CLR is the automated "nothing below 12,000 ft"
+                "CLR" => groups.push("SKC".to_string()),
+                _ => groups.push(group.to_string()),
+            },
+            StationType::Ao1 | StationType::Ao2 => match split_weather(group) {
+                Some((intensity, codes)) => {
+                    if let Some(weather) = sensed_weather(intensity, &codes, station_type == StationType::Ao2) {
+                        if !groups.contains(&weather) {
+                            groups.push(weather);
+                        }
+                    }
+                }
+                None => groups.push(group.to_string()),
+            },
+        }
+    }
+
+    let remark = match station_type {
+        StationType::Ao1 => "AO1",
+        StationType::Ao2 => "AO2",
+        _ => return groups.join(" "),
+    };
+    if remarks {
+        // The station type is the first remark
+        match groups.iter().position(|group| group == "RMK") {
+            Some(index) => groups.insert(index + 1, remark.to_string()),
+            None => groups.extend(["RMK".to_string(), remark.to_string()]),
+        }
+    }
+    groups.join(" ")
+}
+
+// A present weather group as its intensity or proximity prefix and its codes,
+// e.g. "+SHRA" as ("+", ["SH", "RA"])
+fn split_weather(group: &str) -> Option<(&str, Vec<&str>)> {
+    let (intensity, rest) = ["+", "-", "VC"]
+        .into_iter()
+        .find_map(|prefix| group.strip_prefix(prefix).map(|rest| (prefix, rest)))
+        .unwrap_or(("", group));
+    if rest.is_empty() || rest.len() % 2 != 0 || !rest.is_ascii() {
+        return None;
+    }
+    let codes: Vec<&str> = (0..rest.len()).step_by(2).map(|i| &rest[i..i + 2]).collect();
+    codes
+        .iter()
+        .all(|code| DESCRIPTORS.contains(code) || PHENOMENA.contains(code))
+        .then_some((intensity, codes))
+}
+
+// The part of a weather group an automated station could have reported. AO1
+// keeps only the obscurations; AO2 also reports rain, snow, ice pellets,
+// unknown precipitation, freezing precipitation, thunder (from its lightning
+// sensor) and squalls, with drizzle as rain and snow grains as snow.
+fn sensed_weather(intensity: &str, codes: &[&str], discriminator: bool) -> Option<String> {
+    let mut descriptors = Vec::new();
+    let mut phenomena = Vec::new();
+    for &code in codes {
+        match code {
+            // Shallow, patches, partial, blowing and drifting can't be sensed: drop the group
+            "MI" | "BC" | "PR" | "DR" | "BL" => return None,
+            "TS" | "FZ" if discriminator => descriptors.push(code),
+            "DZ" | "RA" if discriminator => phenomena.push("RA"),
+            "SN" | "SG" if discriminator => phenomena.push("SN"),
+            "PL" | "UP" | "SQ" if discriminator => phenomena.push(code),
+            _ if OBSCURATIONS.contains(&code) => phenomena.push(code),
+            _ => {}
+        }
+    }
+    phenomena.dedup();
+    // Thunder is only reported nearby; precipitation isn't
+    if intensity == "VC" && !descriptors.contains(&"TS") {
+        return None;
+    }
+    // Freezing only qualifies what it freezes
+    if phenomena.is_empty() {
+        descriptors.retain(|&descriptor| descriptor == "TS");
+    }
+    if descriptors.is_empty() && phenomena.is_empty() {
+        return None;
+    }
+    // Obscurations have no intensity
+    let precipitation = phenomena.iter().any(|code| !OBSCURATIONS.contains(code));
+    let prefix = match intensity {
+        "+" | "-" if precipitation => intensity,
+        "VC" => intensity,
+        _ => "",
+    };
+    Some(format!("{}{}{}", prefix, descriptors.concat(), phenomena.concat()))
+}
@@ -0,0 +1,618 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Where saved airports, bookmarks and the report history are kept. Normally a
+// single SQLite database; the JSON files used before (the user_airports list in
+// each profile's config and history.jsonl) are imported into it the first time.
+// If the database can't be opened the JSON files are used directly, with
+// bookmarks in a "bookmarks" list next to the saved airports.
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use crate::bookmarks::{Bookmark, BookmarkKind};
+use crate::config::{self, UserAirport};
+use crate::history::{HistoryEntry, Retention};
+use crate::paths;
+
+const DATABASE_FILE: &str = "metgen.db";
+const HISTORY_FILE: &str = "history.jsonl";
+const HISTORY_TEMP_FILE: &str = "history.jsonl.tmp";
+
+/// Which reports to return: all of them by default, newest first.
+#[derive(Clone, Default)]
+pub struct ReportQuery {
+    pub icao: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl ReportQuery {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.icao.as_ref().is_none_or(|icao| entry.icao.eq_ignore_ascii_case(icao))
+            && self.since.is_none_or(|since| entry.time >= since)
+    }
+}
+
+pub trait Storage: Send + Sync {
+    /// Short description for the Diagnostics tab.
+    fn description(&self) -> &'static str;
+
+    /// A profile's saved airports, in display order.
+    fn user_airports(&self, profile: &str) -> Vec<UserAirport>;
+    /// Replaces a profile's saved airports in one step.
+    fn set_user_airports(&self, profile: &str, airports: &[UserAirport]) -> Result<(), String>;
+
+    /// A profile's bookmarks, in display order.
+    fn bookmarks(&self, profile: &str) -> Vec<Bookmark>;
+    /// Replaces a profile's bookmarks in one step.
+    fn set_bookmarks(&self, profile: &str, bookmarks: &[Bookmark]) -> Result<(), String>;
+
+    fn record_report(&self, entry: &HistoryEntry) -> Result<(), String>;
+    fn reports(&self, query: &ReportQuery) -> Vec<HistoryEntry>;
+    /// Removes reports beyond the retention limits and compacts the storage.
+    /// Returns the number of reports removed.
+    fn prune_reports(&self, retention: Retention) -> Result<usize, String>;
+    /// Space used on disk in bytes.
+    fn size(&self) -> u64;
+}
+
+static STORAGE: OnceLock<Box<dyn Storage>> = OnceLock::new();
+
+/// The storage in use, opening the database on first use.
+pub fn get() -> &'static dyn Storage {
+    STORAGE
+        .get_or_init(|| match SqliteStorage::open(paths::data_dir()) {
+            Ok(storage) => Box::new(storage),
+            Err(e) => {
+                eprintln!("Failed to open {}, using the JSON files instead: {}", DATABASE_FILE, e);
+                Box::new(JsonStorage { history_lock: Mutex::new(()) })
+            }
+        })
+        .as_ref()
+}
+
+// Saved airports and bookmarks in the profile config files and the history in history.jsonl
+struct JsonStorage {
+    // Held while the history is appended to or rewritten, so a prune can't drop a new report
+    history_lock: Mutex<()>,
+}
+
+impl Storage for JsonStorage {
+    fn description(&self) -> &'static str {
+        "JSON files (config and history.jsonl)"
+    }
+
+    fn user_airports(&self, profile: &str) -> Vec<UserAirport> {
+        config::read_config_airports(profile)
+    }
+
+    fn set_user_airports(&self, profile: &str, airports: &[UserAirport]) -> Result<(), String> {
+        config::write_config_airports(profile, airports).map_err(|e| e.to_string())
+    }
+
+    fn bookmarks(&self, profile: &str) -> Vec<Bookmark> {
+        serde_json::from_value(config::read_profile_setting(profile, "bookmarks")).unwrap_or_default()
+    }
+
+    fn set_bookmarks(&self, profile: &str, bookmarks: &[Bookmark]) -> Result<(), String> {
+        let value = serde_json::to_value(bookmarks).map_err(|e| e.to_string())?;
+        config::write_profile_setting(profile, "bookmarks", value).map_err(|e| e.to_string())
+    }
+
+    fn record_report(&self, entry: &HistoryEntry) -> Result<(), String> {
+        let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        let _lock = self.history_lock.lock();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(paths::data_file(HISTORY_FILE))
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| e.to_string())
+    }
+
+    fn reports(&self, query: &ReportQuery) -> Vec<HistoryEntry> {
+        let mut reports: Vec<HistoryEntry> = read_history_file(&paths::data_file(HISTORY_FILE))
+            .into_iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .collect();
+        if let Some(limit) = query.limit {
+            reports.truncate(limit);
+        }
+        reports
+    }
+
+    // Rewrites the file without the old entries and any lines that can't be read
+    fn prune_reports(&self, retention: Retention) -> Result<usize, String> {
+        let _lock = self.history_lock.lock();
+        let contents = match fs::read_to_string(paths::data_file(HISTORY_FILE)) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let cutoff = retention_cutoff(retention);
+        let lines: Vec<&str> = contents.lines().collect();
+        let mut kept: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|line| {
+                serde_json::from_str::<HistoryEntry>(line)
+                    .is_ok_and(|entry| cutoff.is_none_or(|cutoff| entry.time >= cutoff))
+            })
+            .collect();
+        // The file is in recording order, so the newest entries are at the end
+        if let Some(max_entries) = retention.max_entries {
+            let excess = kept.len().saturating_sub(max_entries);
+            kept.drain(..excess);
+        }
+
+        let removed = lines.len() - kept.len();
+        if removed > 0 {
+            let mut pruned = kept.join("\n");
+            if !pruned.is_empty() {
+                pruned.push('\n');
+            }
+            // Write a copy and swap it in, so an interrupted prune can't truncate the history
+            fs::write(paths::data_file(HISTORY_TEMP_FILE), pruned)
+                .and_then(|_| fs::rename(paths::data_file(HISTORY_TEMP_FILE), paths::data_file(HISTORY_FILE)))
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(removed)
+    }
+
+    fn size(&self) -> u64 {
+        fs::metadata(paths::data_file(HISTORY_FILE)).map(|metadata| metadata.len()).unwrap_or(0)
+    }
+}
+
+// history.jsonl in recording order, skipping lines that can't be parsed
+fn read_history_file(path: &Path) -> Vec<HistoryEntry> {
+    fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn retention_cutoff(retention: Retention) -> Option<DateTime<Utc>> {
+    retention.max_days.map(|days| Utc::now() - Duration::days(days as i64))
+}
+
+struct SqliteStorage {
+    connection: Mutex<Connection>,
+    path: PathBuf,
+}
+
+// Schema version kept in the database's user_version
+const SCHEMA_VERSION: i64 = 3;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS reports (
+        id INTEGER PRIMARY KEY,
+        time INTEGER NOT NULL,
+        icao TEXT NOT NULL,
+        provider TEXT NOT NULL,
+        metar TEXT NOT NULL,
+        cache_hits INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS reports_time ON reports (time);
+    CREATE INDEX IF NOT EXISTS reports_icao_time ON reports (icao, time);
+    CREATE TABLE IF NOT EXISTS airports (
+        profile TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        icao TEXT NOT NULL,
+        latitude REAL NOT NULL,
+        longitude REAL NOT NULL,
+        name TEXT,
+        PRIMARY KEY (profile, icao)
+    );
+    -- Profiles whose config file airports have been imported
+    CREATE TABLE IF NOT EXISTS imported_profiles (profile TEXT PRIMARY KEY);
+    -- Added in version 2
+    CREATE TABLE IF NOT EXISTS bookmarks (
+        profile TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        latitude REAL NOT NULL,
+        longitude REAL NOT NULL,
+        identifier TEXT NOT NULL,
+        PRIMARY KEY (profile, identifier)
+    );
+";
+
+// Version 3 added cache_hits to the reports table, which older databases
+// already have without it
+const ADD_CACHE_HITS: &str = "ALTER TABLE reports ADD COLUMN cache_hits INTEGER NOT NULL DEFAULT 0";
+
+impl SqliteStorage {
+    // Opens metgen.db in `dir`, creating or upgrading it, with history.jsonl
+    // from the same directory imported into a new database
+    fn open(dir: &Path) -> Result<SqliteStorage, String> {
+        let path = dir.join(DATABASE_FILE);
+        let mut connection = Connection::open(&path).map_err(|e| e.to_string())?;
+        let version: i64 = connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        if version > SCHEMA_VERSION {
+            return Err(format!("database version {} is newer than this build supports", version));
+        }
+
+        if version < SCHEMA_VERSION {
+            let transaction = connection.transaction().map_err(|e| e.to_string())?;
+            if (1..3).contains(&version) {
+                transaction.execute_batch(ADD_CACHE_HITS).map_err(|e| e.to_string())?;
+            }
+            transaction.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+            // A new database starts with the reports from history.jsonl, which is kept as it is
+            if version == 0 {
+                for entry in read_history_file(&dir.join(HISTORY_FILE)) {
+                    insert_report(&transaction, &entry)?;
+                }
+            }
+            transaction
+                .pragma_update(None, "user_version", SCHEMA_VERSION)
+                .map_err(|e| e.to_string())?;
+            transaction.commit().map_err(|e| e.to_string())?;
+        }
+
+        Ok(SqliteStorage { connection: Mutex::new(connection), path })
+    }
+
+    fn connection(&self) -> Result<std::sync::MutexGuard<'_, Connection>, String> {
+        self.connection.lock().map_err(|_| "storage lock poisoned".to_string())
+    }
+
+    // Copies a profile's airports from its config file the first time the profile is used
+    fn import_profile(&self, connection: &mut Connection, profile: &str) -> Result<(), String> {
+        let imported = connection
+            .query_row("SELECT 1 FROM imported_profiles WHERE profile = ?1", [profile], |_| Ok(()))
+            .optional()
+            .map_err(|e| e.to_string())?
+            .is_some();
+        if imported {
+            return Ok(());
+        }
+
+        let transaction = connection.transaction().map_err(|e| e.to_string())?;
+        replace_airports(&transaction, profile, &config::read_config_airports(profile))?;
+        transaction
+            .execute("INSERT INTO imported_profiles (profile) VALUES (?1)", [profile])
+            .map_err(|e| e.to_string())?;
+        transaction.commit().map_err(|e| e.to_string())
+    }
+}
+
+fn insert_report(connection: &Connection, entry: &HistoryEntry) -> Result<(), String> {
+    connection
+        .execute(
+            "INSERT INTO reports (time, icao, provider, metar, cache_hits) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![entry.time.timestamp_millis(), entry.icao, entry.provider, entry.metar, entry.cache_hits as i64],
+        )
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn replace_airports(connection: &Connection, profile: &str, airports: &[UserAirport]) -> Result<(), String> {
+    connection
+        .execute("DELETE FROM airports WHERE profile = ?1", [profile])
+        .map_err(|e| e.to_string())?;
+    for (position, airport) in airports.iter().enumerate() {
+        connection
+            .execute(
+                "INSERT OR IGNORE INTO airports (profile, position, icao, latitude, longitude, name)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![profile, position as i64, airport.icao, airport.latitude, airport.longitude, airport.name],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+impl Storage for SqliteStorage {
+    fn description(&self) -> &'static str {
+        "SQLite (metgen.db)"
+    }
+
+    fn user_airports(&self, profile: &str) -> Vec<UserAirport> {
+        let result = self.connection().and_then(|mut connection| {
+            self.import_profile(&mut connection, profile)?;
+            let mut statement = connection
+                .prepare(
+                    "SELECT icao, latitude, longitude, name FROM airports
+                     WHERE profile = ?1 ORDER BY position",
+                )
+                .map_err(|e| e.to_string())?;
+            let airports = statement
+                .query_map([profile], |row| {
+                    Ok(UserAirport {
+                        icao: row.get(0)?,
+                        latitude: row.get(1)?,
+                        longitude: row.get(2)?,
+                        name: row.get(3)?,
+                    })
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| e.to_string());
+            airports
+        });
+        result.unwrap_or_else(|e| {
+            eprintln!("Failed to read saved airports: {}", e);
+            Vec::new()
+        })
+    }
+
+    fn set_user_airports(&self, profile: &str, airports: &[UserAirport]) -> Result<(), String> {
+        let mut connection = self.connection()?;
+        self.import_profile(&mut connection, profile)?;
+        let transaction = connection.transaction().map_err(|e| e.to_string())?;
+        replace_airports(&transaction, profile, airports)?;
+        transaction.commit().map_err(|e| e.to_string())
+    }
+
+    fn bookmarks(&self, profile: &str) -> Vec<Bookmark> {
+        let result = self.connection().and_then(|connection| {
+            let mut statement = connection
+                .prepare(
+                    "SELECT name, kind, latitude, longitude, identifier FROM bookmarks
+                     WHERE profile = ?1 ORDER BY position",
+                )
+                .map_err(|e| e.to_string())?;
+            let bookmarks = statement
+                .query_map([profile], |row| {
+                    Ok(Bookmark {
+                        name: row.get(0)?,
+                        kind: BookmarkKind::from_key(&row.get::<_, String>(1)?),
+                        latitude: row.get(2)?,
+                        longitude: row.get(3)?,
+                        identifier: row.get(4)?,
+                    })
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| e.to_string());
+            bookmarks
+        });
+        result.unwrap_or_else(|e| {
+            eprintln!("Failed to read bookmarks: {}", e);
+            Vec::new()
+        })
+    }
+
+    fn set_bookmarks(&self, profile: &str, bookmarks: &[Bookmark]) -> Result<(), String> {
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction().map_err(|e| e.to_string())?;
+        transaction
+            .execute("DELETE FROM bookmarks WHERE profile = ?1", [profile])
+            .map_err(|e| e.to_string())?;
+        for (position, bookmark) in bookmarks.iter().enumerate() {
+            transaction
+                .execute(
+                    "INSERT OR IGNORE INTO bookmarks (profile, position, name, kind, latitude, longitude, identifier)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        profile,
+                        position as i64,
+                        bookmark.name,
+                        bookmark.kind.key(),
+                        bookmark.latitude,
+                        bookmark.longitude,
+                        bookmark.identifier
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        transaction.commit().map_err(|e| e.to_string())
+    }
+
+    fn record_report(&self, entry: &HistoryEntry) -> Result<(), String> {
+        insert_report(&*self.connection()?, entry)
+    }
+
+    fn reports(&self, query: &ReportQuery) -> Vec<HistoryEntry> {
+        let result = self.connection().and_then(|connection| {
+            let mut statement = connection
+                .prepare(
+                    "SELECT time, icao, provider, metar, cache_hits FROM reports
+                     WHERE (?1 IS NULL OR icao = ?1 COLLATE NOCASE) AND (?2 IS NULL OR time >= ?2)
+                     ORDER BY time DESC, id DESC LIMIT ?3",
+                )
+                .map_err(|e| e.to_string())?;
+            let limit = query.limit.map(|limit| limit as i64).unwrap_or(-1);
+            let since = query.since.map(|since| since.timestamp_millis());
+            let reports = statement
+                .query_map(params![query.icao, since, limit], |row| {
+                    Ok(HistoryEntry {
+                        time: DateTime::<Utc>::from_timestamp_millis(row.get(0)?).unwrap_or_default(),
+                        icao: row.get(1)?,
+                        provider: row.get(2)?,
+                        metar: row.get(3)?,
+                        cache_hits: row.get::<_, i64>(4)? as u64,
+                    })
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| e.to_string());
+            reports
+        });
+        result.unwrap_or_else(|e| {
+            eprintln!("Failed to read the report history: {}", e);
+            Vec::new()
+        })
+    }
+
+    fn prune_reports(&self, retention: Retention) -> Result<usize, String> {
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction().map_err(|e| e.to_string())?;
+        let mut removed = 0;
+        if let Some(cutoff) = retention_cutoff(retention) {
+            removed += transaction
+                .execute("DELETE FROM reports WHERE time < ?1", [cutoff.timestamp_millis()])
+                .map_err(|e| e.to_string())?;
+        }
+        if let Some(max_entries) = retention.max_entries {
+            removed += transaction
+                .execute(
+                    "DELETE FROM reports WHERE id NOT IN
+                     (SELECT id FROM reports ORDER BY time DESC, id DESC LIMIT ?1)",
+                    [max_entries as i64],
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        transaction.commit().map_err(|e| e.to_string())?;
+
+        // Give the freed pages back to the file system
+        if removed > 0 {
+            connection.execute_batch("VACUUM").map_err(|e| e.to_string())?;
+        }
+        Ok(removed)
+    }
+
+    fn size(&self) -> u64 {
+        fs::metadata(&self.path).map(|metadata| metadata.len()).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty directory of its own for each test, inside the test data directory
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = paths::data_file(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn airports() -> Vec<UserAirport> {
+        vec![
+            UserAirport { icao: "KSEA".to_string(), latitude: 47.4502, longitude: -122.3088, name: None },
+            UserAirport { icao: "1N7".to_string(), latitude: 40.5, longitude: -74.9, name: Some("ALEXANDRIA".to_string()) },
+        ]
+    }
+
+    fn bookmarks() -> Vec<Bookmark> {
+        vec![
+            Bookmark {
+                name: "Lake Union".to_string(),
+                kind: BookmarkKind::SeaplaneBase,
+                latitude: 47.6275,
+                longitude: -122.3375,
+                identifier: "W55X".to_string(),
+            },
+            Bookmark {
+                name: "Space Needle".to_string(),
+                kind: BookmarkKind::Landmark,
+                latitude: 47.6205,
+                longitude: -122.3493,
+                identifier: "NEDL".to_string(),
+            },
+        ]
+    }
+
+    fn assert_round_trip(storage: &dyn Storage, profile: &str) {
+        storage.set_user_airports(profile, &airports()).unwrap();
+        let saved = storage.user_airports(profile);
+        let expected = airports();
+        assert_eq!(saved.len(), expected.len());
+        for (saved, expected) in saved.iter().zip(&expected) {
+            assert_eq!(saved.icao, expected.icao);
+            assert_eq!((saved.latitude, saved.longitude), (expected.latitude, expected.longitude));
+            assert_eq!(saved.name, expected.name);
+        }
+
+        storage.set_bookmarks(profile, &bookmarks()).unwrap();
+        let saved = storage.bookmarks(profile);
+        let expected = bookmarks();
+        assert_eq!(saved.len(), expected.len());
+        for (saved, expected) in saved.iter().zip(&expected) {
+            assert_eq!((&saved.name, saved.kind, &saved.identifier), (&expected.name, expected.kind, &expected.identifier));
+            assert_eq!((saved.latitude, saved.longitude), (expected.latitude, expected.longitude));
+        }
+    }
+
+    #[test]
+    fn new_database_imports_the_history_file() {
+        let dir = temp_dir("new_database");
+        let history = [
+            r#"{"time":"2024-03-12T12:00:00Z","icao":"KSEA","provider":"NOAA","metar":"KSEA 121200Z"}"#,
+            "not a report",
+            r#"{"time":"2024-03-12T13:00:00Z","icao":"KPDX","provider":"Open-Meteo","metar":"KPDX 121300Z","cache_hits":2}"#,
+        ];
+        fs::write(dir.join(HISTORY_FILE), history.join("\n")).unwrap();
+
+        let storage = SqliteStorage::open(&dir).unwrap();
+        let reports = storage.reports(&ReportQuery::default());
+        let summary: Vec<(&str, &str, u64)> = reports
+            .iter()
+            .map(|entry| (entry.icao.as_str(), entry.metar.as_str(), entry.cache_hits))
+            .collect();
+        assert_eq!(summary, [("KPDX", "KPDX 121300Z", 2), ("KSEA", "KSEA 121200Z", 0)]);
+        let version: i64 = storage.connection().unwrap().query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn version_1_database_is_upgraded_in_place() {
+        let dir = temp_dir("version_1_database");
+        let connection = Connection::open(dir.join(DATABASE_FILE)).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE reports (id INTEGER PRIMARY KEY, time INTEGER NOT NULL, icao TEXT NOT NULL,
+                     provider TEXT NOT NULL, metar TEXT NOT NULL);
+                 CREATE TABLE airports (profile TEXT NOT NULL, position INTEGER NOT NULL, icao TEXT NOT NULL,
+                     latitude REAL NOT NULL, longitude REAL NOT NULL, name TEXT, PRIMARY KEY (profile, icao));
+                 CREATE TABLE imported_profiles (profile TEXT PRIMARY KEY);
+                 INSERT INTO reports (time, icao, provider, metar) VALUES (1710244800000, 'KSEA', 'NOAA', 'KSEA 121200Z');
+                 PRAGMA user_version = 1;",
+            )
+            .unwrap();
+        drop(connection);
+        // Only a new database imports the history file
+        fs::write(dir.join(HISTORY_FILE), r#"{"time":"2024-03-12T13:00:00Z","icao":"KPDX","provider":"NOAA","metar":""}"#).unwrap();
+
+        let storage = SqliteStorage::open(&dir).unwrap();
+        let reports = storage.reports(&ReportQuery::default());
+        assert_eq!(reports.len(), 1);
+        assert_eq!((reports[0].icao.as_str(), reports[0].cache_hits), ("KSEA", 0));
+        assert_round_trip(&storage, "upgraded");
+    }
+
+    #[test]
+    fn sqlite_round_trip() {
+        let storage = SqliteStorage::open(&temp_dir("sqlite_round_trip")).unwrap();
+        assert_round_trip(&storage, "sqlite_round_trip");
+        // Profiles are kept apart
+        assert!(storage.user_airports("other").is_empty());
+        assert!(storage.bookmarks("other").is_empty());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        // The JSON backend keeps both lists in the profile's config file
+        let profile = "json_round_trip";
+        fs::write(paths::config_file(&format!("config-{}.json", profile)), "{}").unwrap();
+        let storage = JsonStorage { history_lock: Mutex::new(()) };
+        assert_round_trip(&storage, profile);
+    }
+}
@@ -0,0 +1,333 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Units used for each METAR element. Many regions mix units, so each element is
+// chosen separately, either through a regional preset or individually.
+
+use serde_json::{json, Value};
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum VisibilityUnit {
+    #[default]
+    Meters,
+    StatuteMiles,
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum PressureUnit {
+    #[default]
+    Hectopascals,
+    InchesOfMercury,
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum WindUnit {
+    #[default]
+    Knots,
+    MetersPerSecond,
+}
+
+/// Unit for temperatures in the decoded report. The METAR itself is always in Celsius.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    /// The temperature with its unit, e.g. "-3 °C" or "27 °F".
+    pub fn format(self, celsius: f64) -> String {
+        match self {
+            TemperatureUnit::Celsius => format!("{:.0} °C", celsius),
+            TemperatureUnit::Fahrenheit => format!("{:.0} °F", celsius_to_fahrenheit(celsius)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct MetarUnits {
+    pub visibility: VisibilityUnit,
+    pub pressure: PressureUnit,
+    pub wind: WindUnit,
+    // Adds the sea level pressure in hPa as an "RMK SLPppp" group
+    pub sea_level_pressure_remark: bool,
+    // Only used to display decoded reports
+    pub temperature: TemperatureUnit,
+}
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum UnitPreset {
+    #[default]
+    Icao,
+    UnitedStates,
+    Canada,
+    UnitedKingdom,
+    MetersPerSecond,
+    Custom,
+}
+
+impl UnitPreset {
+    pub const ALL: [UnitPreset; 6] = [
+        UnitPreset::Icao,
+        UnitPreset::UnitedStates,
+        UnitPreset::Canada,
+        UnitPreset::UnitedKingdom,
+        UnitPreset::MetersPerSecond,
+        UnitPreset::Custom,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            UnitPreset::Icao => "Metric (ICAO)",
+            UnitPreset::UnitedStates => "Imperial (US)",
+            UnitPreset::Canada => "Canada",
+            UnitPreset::UnitedKingdom => "United Kingdom",
+            UnitPreset::MetersPerSecond => "Russia/China (m/s)",
+            UnitPreset::Custom => "Custom",
+        }
+    }
+
+    // Value stored under "units" in the config. The first two keep the names
+    // used before presets existed so older config files load unchanged.
+    pub fn key(self) -> &'static str {
+        match self {
+            UnitPreset::Icao => "metric",
+            UnitPreset::UnitedStates => "imperial",
+            UnitPreset::Canada => "canada",
+            UnitPreset::UnitedKingdom => "uk",
+            UnitPreset::MetersPerSecond => "mps",
+            UnitPreset::Custom => "custom",
+        }
+    }
+
+    /// The units the preset applies, or None for Custom.
+    pub fn units(self) -> Option<MetarUnits> {
+        let units = match self {
+            UnitPreset::Icao | UnitPreset::UnitedKingdom => MetarUnits::default(),
+            UnitPreset::UnitedStates => MetarUnits {
+                visibility: VisibilityUnit::StatuteMiles,
+                pressure: PressureUnit::InchesOfMercury,
+                temperature: TemperatureUnit::Fahrenheit,
+                ..MetarUnits::default()
+            },
+            // Statute miles and inches, with the hPa sea level pressure in remarks
+            UnitPreset::Canada => MetarUnits {
+                visibility: VisibilityUnit::StatuteMiles,
+                pressure: PressureUnit::InchesOfMercury,
+                sea_level_pressure_remark: true,
+                ..MetarUnits::default()
+            },
+            UnitPreset::MetersPerSecond => MetarUnits {
+                wind: WindUnit::MetersPerSecond,
+                ..MetarUnits::default()
+            },
+            UnitPreset::Custom => return None,
+        };
+        Some(units)
+    }
+}
+
+/// Reads the unit preset and the resulting per-element units from the config.
+pub fn units_from_config(config: &Value) -> (UnitPreset, MetarUnits) {
+    let key = config.get("units").and_then(|u| u.as_str()).unwrap_or_default();
+    let preset = UnitPreset::ALL
+        .into_iter()
+        .find(|preset| preset.key() == key)
+        .unwrap_or_default();
+
+    let units = preset.units().unwrap_or_else(|| MetarUnits {
+        visibility: match config["unit_visibility"].as_str() {
+            Some("sm") => VisibilityUnit::StatuteMiles,
+            _ => VisibilityUnit::Meters,
+        },
+        pressure: match config["unit_pressure"].as_str() {
+            Some("inhg") => PressureUnit::InchesOfMercury,
+            _ => PressureUnit::Hectopascals,
+        },
+        wind: match config["unit_wind"].as_str() {
+            Some("mps") => WindUnit::MetersPerSecond,
+            _ => WindUnit::Knots,
+        },
+        sea_level_pressure_remark: config["unit_slp_remark"].as_bool().unwrap_or(false),
+        temperature: match config["unit_temperature"].as_str() {
+            Some("f") => TemperatureUnit::Fahrenheit,
+            _ => TemperatureUnit::Celsius,
+        },
+    });
+    (preset, units)
+}
+
+/// Writes the preset, and for Custom the individual units, into the config.
+pub fn store_units(config: &mut Value, preset: UnitPreset, units: &MetarUnits) {
+    config["units"] = json!(preset.key());
+    if preset == UnitPreset::Custom {
+        config["unit_visibility"] = json!(match units.visibility {
+            VisibilityUnit::Meters => "m",
+            VisibilityUnit::StatuteMiles => "sm",
+        });
+        config["unit_pressure"] = json!(match units.pressure {
+            PressureUnit::Hectopascals => "hpa",
+            PressureUnit::InchesOfMercury => "inhg",
+        });
+        config["unit_wind"] = json!(match units.wind {
+            WindUnit::Knots => "kt",
+            WindUnit::MetersPerSecond => "mps",
+        });
+        config["unit_slp_remark"] = json!(units.sea_level_pressure_remark);
+        config["unit_temperature"] = json!(match units.temperature {
+            TemperatureUnit::Celsius => "c",
+            TemperatureUnit::Fahrenheit => "f",
+        });
+    }
+}
+
+/// Sea level pressure remark, e.g. 1013.2 hPa becomes "SLP132".
+pub fn sea_level_pressure_remark(pressure: Hpa) -> String {
+    format!("SLP{:03}", ((pressure.0 * 10.0).round() as i64).rem_euclid(1000))
+}
+
+// Quantities as carried by observations. Provider values are wrapped in the unit
+// they arrive in and only converted (through From) when a METAR group is
+// formatted, so passing m/s where knots are expected doesn't compile.
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct MetersPerSecond(pub f64);
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct Knots(pub f64);
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct Hpa(pub f64);
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct InHg(pub f64);
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct Celsius(pub f64);
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct Meters(pub f64);
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct StatuteMiles(pub f64);
+
+impl From<MetersPerSecond> for Knots {
+    fn from(speed: MetersPerSecond) -> Self {
+        Knots(mps_to_knots(speed.0))
+    }
+}
+
+impl From<Hpa> for InHg {
+    fn from(pressure: Hpa) -> Self {
+        InHg(hpa_to_inhg(pressure.0))
+    }
+}
+
+impl From<Meters> for StatuteMiles {
+    fn from(distance: Meters) -> Self {
+        StatuteMiles(meters_to_statute_miles(distance.0))
+    }
+}
+
+// Conversions shared by the METAR formatters, the decoder and the conversion
+// calculator, so all of them use the same factors
+
+// Inches of mercury per hectopascal (1 inHg = 33.8638866667 hPa)
+const INHG_PER_HPA: f64 = 1.0 / 33.863_886_666_7;
+// One knot is one nautical mile (1852 m) per hour
+pub const KNOTS_PER_MPS: f64 = 3600.0 / 1852.0;
+pub const KNOTS_PER_KMH: f64 = 1000.0 / 1852.0;
+pub const METERS_PER_STATUTE_MILE: f64 = 1609.344;
+const METERS_PER_FOOT: f64 = 0.3048;
+
+pub fn hpa_to_inhg(hpa: f64) -> f64 {
+    hpa * INHG_PER_HPA
+}
+
+pub fn inhg_to_hpa(inhg: f64) -> f64 {
+    inhg / INHG_PER_HPA
+}
+
+pub fn celsius_to_fahrenheit(celsius: f64) -> f64 {
+    celsius * 9.0 / 5.0 + 32.0
+}
+
+pub fn fahrenheit_to_celsius(fahrenheit: f64) -> f64 {
+    (fahrenheit - 32.0) * 5.0 / 9.0
+}
+
+pub fn mps_to_knots(mps: f64) -> f64 {
+    mps * KNOTS_PER_MPS
+}
+
+pub fn knots_to_mps(knots: f64) -> f64 {
+    knots / KNOTS_PER_MPS
+}
+
+pub fn meters_to_statute_miles(meters: f64) -> f64 {
+    meters / METERS_PER_STATUTE_MILE
+}
+
+pub fn statute_miles_to_meters(miles: f64) -> f64 {
+    miles * METERS_PER_STATUTE_MILE
+}
+
+pub fn feet_to_meters(feet: f64) -> f64 {
+    feet * METERS_PER_FOOT
+}
+
+pub fn meters_to_feet(meters: f64) -> f64 {
+    meters / METERS_PER_FOOT
+}
+
+/// Altimeter setting in hundredths of an inch, rounded half up: standard pressure
+/// 1013.25 hPa gives 2992, while 1013 hPa (29.914 inHg) gives 2991.
+pub fn altimeter_hundredths(pressure: Hpa) -> i32 {
+    // The small offset absorbs floating point error on exact halves
+    (InHg::from(pressure).0 * 100.0 + 0.5 + 1e-9).floor() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metar_format::format_pressure;
+
+    #[test]
+    fn altimeter_settings_at_boundary_pressures() {
+        assert_eq!(altimeter_hundredths(Hpa(1013.0)), 2991);
+        assert_eq!(altimeter_hundredths(Hpa(1013.2)), 2992);
+        assert_eq!(altimeter_hundredths(Hpa(1013.25)), 2992);
+        assert_eq!(altimeter_hundredths(Hpa(1000.0)), 2953);
+        assert_eq!(altimeter_hundredths(Hpa(1050.0)), 3101);
+    }
+
+    #[test]
+    fn exact_half_hundredths_round_up() {
+        assert_eq!(altimeter_hundredths(Hpa(inhg_to_hpa(29.925))), 2993);
+        assert_eq!(altimeter_hundredths(Hpa(inhg_to_hpa(29.915))), 2992);
+        assert_eq!(altimeter_hundredths(Hpa(inhg_to_hpa(30.005))), 3001);
+    }
+
+    #[test]
+    fn altimeter_groups() {
+        let units = MetarUnits { pressure: PressureUnit::InchesOfMercury, ..MetarUnits::default() };
+        assert_eq!(format_pressure(Some(Hpa(1013.0)), &units), "A2991");
+        assert_eq!(format_pressure(Some(Hpa(1013.25)), &units), "A2992");
+        assert_eq!(format_pressure(Some(Hpa(inhg_to_hpa(29.925))), &units), "A2993");
+        assert_eq!(format_pressure(None, &units), "A////");
+    }
+}
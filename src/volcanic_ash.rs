@@ -0,0 +1,195 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Volcanic ash warnings. A position is affected when it lies inside a volcanic
+// ash SIGMET (issued from the VAAC advisories) or when a One Call alert mentions
+// ash. OpenWeather's own volcanic ash condition (762) already becomes VA in the
+// report. Depending on the setting, affected reports only get a warning in the
+// app, or also a VA group and a remark.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::input_handler;
+use crate::metar_decoder;
+
+// SIGMETs are issued for four to six hours, so a short cache is plenty
+const SIGMET_CACHE_AGE: Duration = Duration::from_secs(10 * 60);
+// Remark added with the VA group when injecting
+const ADVISORY_REMARK: &str = "VA ADVISORY";
+// Groups the injected VA goes before: the trend and the remarks
+const TRAILING_GROUPS: [&str; 5] = ["BECMG", "TEMPO", "NOSIG", "FCST", "RMK"];
+
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum AshMode {
+    Off,
+    /// Warn in the app but leave the report as synthesized
+    #[default]
+    Warn,
+    /// Also add VA and a remark to affected reports
+    Inject,
+}
+
+impl AshMode {
+    pub const ALL: [AshMode; 3] = [AshMode::Off, AshMode::Warn, AshMode::Inject];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AshMode::Off => "Off",
+            AshMode::Warn => "Warn only",
+            AshMode::Inject => "Warn and add VA",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            AshMode::Off => "off",
+            AshMode::Warn => "warn",
+            AshMode::Inject => "inject",
+        }
+    }
+
+    pub fn from_config(config: &Value) -> AshMode {
+        let key = config.get("volcanic_ash").and_then(|mode| mode.as_str()).unwrap_or_default();
+        AshMode::ALL
+            .into_iter()
+            .find(|mode| mode.key() == key)
+            .unwrap_or_default()
+    }
+}
+
+// Affected area of a SIGMET as (lat, lon) vertices, with a description
+struct AshArea {
+    polygon: Vec<(f64, f64)>,
+    description: String,
+}
+
+static SIGMETS: Mutex<Option<(Instant, Vec<AshArea>)>> = Mutex::new(None);
+// Advisories found for each position at its last synthesis
+static ADVISORIES: Mutex<Option<HashMap<String, Vec<String>>>> = Mutex::new(None);
+
+/// Advisories in force at the position, from the SIGMETs and the provider's alerts.
+/// The result is kept for `advisories`.
+pub fn check(lat: f64, lon: f64, alerts: &[String]) -> Vec<String> {
+    let mut advisories: Vec<String> = alerts
+        .iter()
+        .filter(|alert| mentions_ash(alert))
+        .cloned()
+        .collect();
+    with_sigmets(|areas| {
+        advisories.extend(areas.iter().filter(|area| contains(&area.polygon, lat, lon)).map(|area| area.description.clone()));
+    });
+
+    if let Ok(mut cache) = ADVISORIES.lock() {
+        cache.get_or_insert_with(HashMap::new).insert(position_key(lat, lon), advisories.clone());
+    }
+    advisories
+}
+
+/// Advisories found when a report for the position was last synthesized.
+pub fn advisories(lat: f64, lon: f64) -> Vec<String> {
+    ADVISORIES
+        .lock()
+        .ok()
+        .and_then(|cache| cache.as_ref()?.get(&position_key(lat, lon)).cloned())
+        .unwrap_or_default()
+}
+
+/// Whether the report itself has volcanic ash, as a weather group or the injected remark.
+pub fn reported(metar: &str) -> bool {
+    let decoded = metar_decoder::decode_metar(metar);
+    decoded.weather.iter().any(|group| {
+        group.trim_start_matches(['+', '-']).trim_start_matches("VC").as_bytes().chunks(2).any(|code| code == b"VA")
+    })
+        || decoded.remarks.is_some_and(|remarks| remarks.contains(ADVISORY_REMARK))
+}
+
+/// Adds VA to the present weather and an advisory remark, unless the report has them.
+pub fn inject(metar: &str) -> String {
+    if reported(metar) {
+        return metar.to_string();
+    }
+    let mut groups: Vec<&str> = metar.split_whitespace().collect();
+    let position = groups
+        .iter()
+        .position(|group| TRAILING_GROUPS.contains(group))
+        .unwrap_or(groups.len());
+    groups.insert(position, "VA");
+    let mut metar = groups.join(" ");
+    if groups.contains(&"RMK") {
+        metar.push(' ');
+    } else {
+        metar.push_str(" RMK ");
+    }
+    metar.push_str(ADVISORY_REMARK);
+    metar
+}
+
+// "Volcanic ash advisory", "Ashfall warning"; not "Ashford" or "washout"
+fn mentions_ash(alert: &str) -> bool {
+    let alert = alert.to_lowercase();
+    alert.contains("volcan")
+        || alert
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| word == "ash" || word == "ashfall")
+}
+
+// Runs `f` with the current SIGMET areas, downloading them when the cache is old
+fn with_sigmets(f: impl FnOnce(&[AshArea])) {
+    let Ok(mut cache) = SIGMETS.lock() else { return };
+    let fresh = cache.as_ref().is_some_and(|(fetched, _)| fetched.elapsed() < SIGMET_CACHE_AGE);
+    if !fresh {
+        if let Some(sigmets) = input_handler::fetch_volcanic_ash_sigmets() {
+            *cache = Some((Instant::now(), sigmets.iter().filter_map(parse_area).collect()));
+        }
+    }
+    if let Some((_, areas)) = cache.as_ref() {
+        f(areas);
+    }
+}
+
+fn parse_area(sigmet: &Value) -> Option<AshArea> {
+    let polygon: Vec<(f64, f64)> = sigmet["coords"]
+        .as_array()?
+        .iter()
+        .filter_map(|point| Some((point["lat"].as_f64()?, point["lon"].as_f64()?)))
+        .collect();
+    if polygon.len() < 3 {
+        return None;
+    }
+    let fir = sigmet["firName"].as_str().or(sigmet["firId"].as_str()).unwrap_or("unknown FIR");
+    Some(AshArea { polygon, description: format!("Volcanic ash SIGMET, {}", fir.trim()) })
+}
+
+// Ray casting; SIGMET areas are small enough to treat latitude and longitude as flat
+fn contains(polygon: &[(f64, f64)], lat: f64, lon: f64) -> bool {
+    let mut inside = false;
+    let mut previous = polygon[polygon.len() - 1];
+    for &point in polygon {
+        let ((lat1, lon1), (lat2, lon2)) = (previous, point);
+        if (lat1 > lat) != (lat2 > lat) && lon < lon1 + (lat - lat1) / (lat2 - lat1) * (lon2 - lon1) {
+            inside = !inside;
+        }
+        previous = point;
+    }
+    inside
+}
+
+// Positions are matched to four decimals, about 10 m
+fn position_key(lat: f64, lon: f64) -> String {
+    format!("{:.4},{:.4}", lat, lon)
+}
@@ -0,0 +1,254 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Exchange of saved airports with other planning tools: GPX and KML waypoint
+// import, and Little Navmap userpoint export
+
+use chrono::Utc;
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use crate::config::UserAirport;
+use crate::input_handler::validate_lat_lon;
+
+// A named point read from a waypoint file
+pub struct Waypoint {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Reads the waypoints from a GPX or KML file, chosen by its extension.
+pub fn read_waypoint_file(path: &Path) -> Result<Vec<Waypoint>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let waypoints = match extension.as_str() {
+        "gpx" => parse_gpx(&contents),
+        "kml" => parse_kml(&contents),
+        _ => return Err("Unsupported file type. Please choose a .gpx or .kml file".to_string()),
+    };
+
+    if waypoints.is_empty() {
+        Err(format!("No waypoints found in {}", path.display()))
+    } else {
+        Ok(waypoints)
+    }
+}
+
+// GPX stores waypoints as <wpt lat=".." lon=".."><name>..</name></wpt>
+fn parse_gpx(contents: &str) -> Vec<Waypoint> {
+    let wpt = Regex::new(r"(?s)<wpt\s([^>]*)>(.*?)</wpt>").unwrap();
+    let lat = Regex::new(r#"lat\s*=\s*["']([^"']+)["']"#).unwrap();
+    let lon = Regex::new(r#"lon\s*=\s*["']([^"']+)["']"#).unwrap();
+
+    wpt.captures_iter(contents)
+        .filter_map(|captures| {
+            let attributes = &captures[1];
+            let latitude = lat.captures(attributes)?[1].trim().parse::<f64>().ok()?;
+            let longitude = lon.captures(attributes)?[1].trim().parse::<f64>().ok()?;
+            let (latitude, longitude) = validate_lat_lon(latitude, longitude)?;
+            Some(Waypoint {
+                name: tag_text(&captures[2], "name").unwrap_or_default(),
+                latitude,
+                longitude,
+            })
+        })
+        .collect()
+}
+
+// KML stores points as <Placemark><name>..</name><Point><coordinates>lon,lat[,alt]</coordinates>
+fn parse_kml(contents: &str) -> Vec<Waypoint> {
+    let placemark = Regex::new(r"(?s)<Placemark[^>]*>(.*?)</Placemark>").unwrap();
+    let point = Regex::new(r"(?s)<Point[^>]*>(.*?)</Point>").unwrap();
+
+    placemark
+        .captures_iter(contents)
+        .filter_map(|captures| {
+            let body = &captures[1];
+            // Only points; lines and polygons are not landing sites
+            let coordinates = tag_text(&point.captures(body)?[1], "coordinates")?;
+            let mut values = coordinates.split(',').map(|value| value.trim().parse::<f64>());
+            let longitude = values.next()?.ok()?;
+            let latitude = values.next()?.ok()?;
+            let (latitude, longitude) = validate_lat_lon(latitude, longitude)?;
+            Some(Waypoint {
+                name: tag_text(body, "name").unwrap_or_default(),
+                latitude,
+                longitude,
+            })
+        })
+        .collect()
+}
+
+// Text content of the first <tag> element, with CDATA and common entities unwrapped
+fn tag_text(contents: &str, tag: &str) -> Option<String> {
+    let pattern = format!(r"(?s)<{0}(?:\s[^>]*)?>(.*?)</{0}>", tag);
+    let text = Regex::new(&pattern).ok()?.captures(contents)?[1].trim().to_string();
+    let text = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|rest| rest.strip_suffix("]]>"))
+        .map(String::from)
+        .unwrap_or(text);
+    Some(
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Turns waypoints into saved airports. Each gets an identifier built from its name
+/// (e.g. "Bear Creek Strip" becomes "BEAR"), numbered where it would clash with an
+/// identifier in `taken` or another waypoint from the same file.
+pub fn waypoints_to_airports(waypoints: Vec<Waypoint>, taken: &[String]) -> Vec<UserAirport> {
+    let mut used: Vec<String> = taken.iter().map(|icao| icao.to_uppercase()).collect();
+    waypoints
+        .into_iter()
+        .map(|waypoint| {
+            let icao = generate_identifier(&waypoint.name, &used);
+            used.push(icao.clone());
+            UserAirport {
+                icao,
+                latitude: waypoint.latitude,
+                longitude: waypoint.longitude,
+                name: Some(waypoint.name).filter(|name| !name.trim().is_empty()),
+            }
+        })
+        .collect()
+}
+
+fn generate_identifier(name: &str, used: &[String]) -> String {
+    let mut base: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .take(4)
+        .collect();
+    if base.len() < 3 {
+        base = "WPT".to_string();
+    }
+    if !used.contains(&base) {
+        return base;
+    }
+
+    // Keep the identifier at five characters or fewer while numbering
+    let stem: String = base.chars().take(3).collect();
+    (1..)
+        .map(|n| format!("{}{}", stem, n))
+        .find(|candidate| !used.contains(candidate))
+        .unwrap_or(base)
+}
+
+// Column layout of the Little Navmap userpoint CSV format
+const LITTLE_NAVMAP_HEADER: [&str; 13] = [
+    "Type", "Name", "Ident", "Latitude", "Longitude", "Elevation", "Magnetic Declination",
+    "Tags", "Description", "Region", "Visible From", "Last Edit", "Import Filename",
+];
+
+/// Writes the saved airports as a Little Navmap userpoints CSV, ready for
+/// Userpoints > Import CSV in Little Navmap.
+pub fn export_little_navmap(path: &Path, airports: &[UserAirport]) -> Result<(), String> {
+    let mut writer = csv::Writer::from_path(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let last_edit = Utc::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    let write_error = |e: csv::Error| format!("Failed to write {}: {}", path.display(), e);
+
+    writer.write_record(LITTLE_NAVMAP_HEADER).map_err(write_error)?;
+    for airport in airports {
+        writer
+            .write_record([
+                "Airstrip",
+                airport.name.as_deref().unwrap_or(&airport.icao),
+                &airport.icao,
+                &format!("{:.6}", airport.latitude),
+                &format!("{:.6}", airport.longitude),
+                "0",
+                "0",
+                "METGen",
+                "Saved airport exported from METGen",
+                "",
+                "250",
+                &last_edit,
+                "",
+            ])
+            .map_err(write_error)?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(waypoints: &[Waypoint]) -> Vec<(&str, f64, f64)> {
+        waypoints.iter().map(|waypoint| (waypoint.name.as_str(), waypoint.latitude, waypoint.longitude)).collect()
+    }
+
+    #[test]
+    fn gpx_waypoints() {
+        let gpx = r#"<?xml version="1.0"?>
+            <gpx version="1.1">
+              <wpt lat="47.6275" lon="-122.3375"><name>Lake Union</name></wpt>
+              <wpt lon='-121.9' lat='46.5'>
+                <name><![CDATA[Bear & Creek]]></name>
+              </wpt>
+              <wpt lat="95.0" lon="10.0"><name>Off the map</name></wpt>
+              <wpt lat="40.1" lon="-75.2"></wpt>
+            </gpx>"#;
+        assert_eq!(
+            summary(&parse_gpx(gpx)),
+            [("Lake Union", 47.6275, -122.3375), ("Bear & Creek", 46.5, -121.9), ("", 40.1, -75.2)]
+        );
+    }
+
+    #[test]
+    fn kml_points_only() {
+        let kml = r#"<kml><Document>
+              <Placemark id="a"><name>Ranch &amp; Strip</name>
+                <Point><coordinates> -110.5,44.25,1500 </coordinates></Point></Placemark>
+              <Placemark><name>Route</name>
+                <LineString><coordinates>-110,44 -111,45</coordinates></LineString></Placemark>
+              <Placemark><name>Broken</name><Point><coordinates>east</coordinates></Point></Placemark>
+            </Document></kml>"#;
+        assert_eq!(summary(&parse_kml(kml)), [("Ranch & Strip", 44.25, -110.5)]);
+    }
+
+    #[test]
+    fn identifiers_are_built_from_names_and_numbered_on_clashes() {
+        let waypoint = |name: &str| Waypoint { name: name.to_string(), latitude: 45.0, longitude: -110.0 };
+        let waypoints = vec![
+            waypoint("Bear Creek Strip"),
+            waypoint("Bear Lake"),
+            waypoint("St. Mary's"),
+            waypoint("X"),
+            waypoint(" "),
+        ];
+        let airports = waypoints_to_airports(waypoints, &["bear".to_string()]);
+        let identifiers: Vec<&str> = airports.iter().map(|airport| airport.icao.as_str()).collect();
+        assert_eq!(identifiers, ["BEA1", "BEA2", "STMA", "WPT", "WPT1"]);
+        assert_eq!(airports[0].name.as_deref(), Some("Bear Creek Strip"));
+        assert_eq!(airports[4].name, None);
+    }
+}
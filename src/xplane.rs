@@ -0,0 +1,68 @@
+// METGen - The Synthesized METAR Generator
+// Copyright (C) 2025 FiendishDrWu
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// Export of the open stations to X-Plane's real weather file. With real weather
+// downloads turned off, X-Plane reads METAR.rwx from its own folder. The file
+// follows the NOAA cycle files X-Plane normally downloads: a timestamp line and
+// the report for each station, separated by blank lines.
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::briefing::Briefing;
+use crate::paths;
+
+pub const METAR_FILE: &str = "METAR.rwx";
+
+/// The X-Plane folder from "xplane_path" in the config, if one is set.
+pub fn xplane_path_from_config(config: &Value) -> Option<PathBuf> {
+    config["xplane_path"]
+        .as_str()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The stations' reports in METAR.rwx layout.
+pub fn format_rwx(briefing: &Briefing) -> String {
+    briefing
+        .stations
+        .iter()
+        .map(|station| format!("{}\n{}\n\n", station.generated.format("%Y/%m/%d %H:%M"), station.metar))
+        .collect()
+}
+
+/// Writes METAR.rwx to the data folder and, when an X-Plane folder is given,
+/// copies it there. Returns the files written.
+pub fn export(briefing: &Briefing, xplane_path: Option<&Path>) -> Result<Vec<PathBuf>, String> {
+    if briefing.stations.is_empty() {
+        return Err("No stations to export".to_string());
+    }
+
+    let local = paths::data_file(METAR_FILE);
+    fs::write(&local, format_rwx(briefing)).map_err(|e| format!("Failed to write {}: {}", METAR_FILE, e))?;
+    let mut written = vec![local.clone()];
+
+    if let Some(folder) = xplane_path {
+        if !folder.is_dir() {
+            return Err(format!("X-Plane folder not found: {}", folder.display()));
+        }
+        let target = folder.join(METAR_FILE);
+        fs::copy(&local, &target).map_err(|e| format!("Failed to copy {} to {}: {}", METAR_FILE, target.display(), e))?;
+        written.push(target);
+    }
+    Ok(written)
+}